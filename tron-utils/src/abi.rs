@@ -0,0 +1,1124 @@
+//! Solidity ABI encoding and decoding.
+
+use crate::address::{parse_address, raw_address_to_tron};
+use crate::error::{Result, ResultExt, TronError};
+use crate::hash::keccak256;
+use serde::Deserialize;
+
+/// Returns the `index`-th 32-byte word of ABI-encoded hex data.
+pub fn abi_word(data: &str, index: usize) -> Result<&str> {
+    data.get(index * 64..(index + 1) * 64)
+        .ok_or_else(|| TronError::Decode(format!("ABI data too short: no word at index {}", index)))
+}
+
+/// Decodes an ABI-encoded `address[]` return value into TRON base58
+/// addresses with `prefix`.
+pub fn decode_address_array(data: &str, prefix: u8) -> Result<Vec<String>> {
+    let bytes = hex::decode(data).or_fail(TronError::Decode, "Invalid ABI hex")?;
+    let array_type = AbiType::Array(Box::new(AbiType::Address));
+    match decode_params(&[array_type], &bytes)?.pop() {
+        Some(AbiValue::Array(items)) => Ok(items
+            .iter()
+            .filter_map(|item| match item {
+                AbiValue::Address(address) => Some(raw_address_to_tron(address, prefix)),
+                _ => None,
+            })
+            .collect()),
+        _ => unreachable!("decoded value matches the requested type"),
+    }
+}
+
+/// Decodes a 32-byte ABI word (hex) holding an address into TRON base58
+/// with `prefix`.
+pub fn abi_word_to_tron_address(word: &str, prefix: u8) -> Result<String> {
+    if word.len() != 64 {
+        return Err(TronError::Decode(format!(
+            "Invalid ABI word length: {}",
+            word.len()
+        )));
+    }
+    let raw = word
+        .get(24..)
+        .and_then(|hex| parse_address(hex, prefix).ok())
+        .ok_or_else(|| TronError::InvalidAddress(format!("Invalid address word: {}", word)))?;
+    Ok(raw_address_to_tron(&raw, prefix))
+}
+
+/// Decodes a 32-byte ABI word (hex) holding a uint256 that must fit in a u128.
+pub fn abi_word_to_u128(word: &str) -> Result<u128> {
+    if word.len() != 64 {
+        return Err(TronError::Decode(format!(
+            "Invalid ABI word length: {}",
+            word.len()
+        )));
+    }
+    if word[..32].chars().any(|c| c != '0') {
+        return Err(TronError::Decode(format!(
+            "uint256 value does not fit in 128 bits: 0x{}",
+            word
+        )));
+    }
+    u128::from_str_radix(&word[32..], 16).or_fail(TronError::Decode, "Invalid uint256 hex")
+}
+
+pub fn encode_constructor_params(
+    usdt: &str,
+    owners: &[&str],
+    threshold: u64,
+    prefix: u8,
+) -> Result<String> {
+    // ABI encode: (address _usdt, address[] _owners, uint256 _threshold)
+    let owners = owners
+        .iter()
+        .map(|owner| AbiValue::address(owner, prefix))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(encode_params(&[
+        AbiValue::address(usdt, prefix)?,
+        AbiValue::Array(owners),
+        AbiValue::uint(threshold as u128),
+    ]))
+}
+
+/// Decodes pre-encoded constructor arguments, e.g. captured from an earlier
+/// deployment, against the constructor in `abi`. The data must be exactly the
+/// canonical encoding of its inputs: trailing bytes or odd offsets would be
+/// deployed as-is, and nothing here could say what the constructor read.
+pub fn decode_constructor_params(abi: &ContractAbi, params_hex: &str) -> Result<Vec<AbiValue>> {
+    let constructor = abi
+        .entries
+        .iter()
+        .find(|e| e.kind == "constructor")
+        .ok_or_else(|| TronError::InvalidInput("ABI has no constructor".into()))?;
+    let signature = format!("constructor{}", constructor.signature()?);
+    let data = hex::decode(params_hex.trim_start_matches("0x")).map_err(|e| {
+        TronError::InvalidInput(format!("Invalid constructor parameter hex: {}", e))
+    })?;
+
+    let values = decode_params(&constructor.input_types()?, &data).map_err(|e| {
+        TronError::InvalidInput(format!(
+            "Constructor parameters don't decode as {}: {}",
+            signature, e
+        ))
+    })?;
+    if encode_params(&values) != hex::encode(&data) {
+        return Err(TronError::InvalidInput(format!(
+            "Constructor parameters are not the canonical encoding of {} \
+             (trailing or misplaced data)",
+            signature
+        )));
+    }
+    Ok(values)
+}
+
+/// A value to be ABI-encoded as a contract call or constructor argument, or
+/// decoded from a call result.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AbiValue {
+    /// 20-byte address (without the TRON 0x41 prefix)
+    Address([u8; 20]),
+    /// Big-endian uint256
+    Uint([u8; 32]),
+    /// Two's-complement int256
+    Int([u8; 32]),
+    Bool(bool),
+    /// `bytes1`..`bytes32`
+    FixedBytes(Vec<u8>),
+    /// Dynamic `bytes`
+    Bytes(Vec<u8>),
+    String(String),
+    /// Dynamic array `T[]` of a single element type
+    Array(Vec<AbiValue>),
+    /// Struct or fixed-size array `T[k]`, encoded in place like a tuple
+    Tuple(Vec<AbiValue>),
+}
+
+impl AbiValue {
+    /// Builds an address value from a TRON address in any [`AddressFormat`],
+    /// prefixed forms with `prefix`.
+    ///
+    /// [`AddressFormat`]: crate::address::AddressFormat
+    pub fn address(address: &str, prefix: u8) -> Result<Self> {
+        Ok(AbiValue::Address(parse_address(address, prefix)?))
+    }
+
+    pub fn uint(value: u128) -> Self {
+        let mut word = [0u8; 32];
+        word[16..].copy_from_slice(&value.to_be_bytes());
+        AbiValue::Uint(word)
+    }
+
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            AbiValue::Bytes(_) | AbiValue::String(_) | AbiValue::Array(_) => true,
+            AbiValue::Tuple(items) => items.iter().any(AbiValue::is_dynamic),
+            _ => false,
+        }
+    }
+}
+
+/// ABI-encodes `values` as a tuple (the layout used for call and constructor
+/// parameters) and returns it as hex, without a function selector.
+pub fn encode_params(values: &[AbiValue]) -> String {
+    hex::encode(encode_tuple(values))
+}
+
+/// Static values are encoded in place in the head; dynamic values get an
+/// offset (relative to the start of the tuple) in the head and their
+/// length-prefixed contents in the tail.
+fn encode_tuple(values: &[AbiValue]) -> Vec<u8> {
+    let encoded: Vec<Vec<u8>> = values.iter().map(encode_value).collect();
+    let head_size: usize = values
+        .iter()
+        .zip(&encoded)
+        .map(|(value, bytes)| if value.is_dynamic() { 32 } else { bytes.len() })
+        .sum();
+    let mut head = Vec::with_capacity(head_size);
+    let mut tail = Vec::new();
+
+    for (value, bytes) in values.iter().zip(encoded) {
+        if value.is_dynamic() {
+            head.extend_from_slice(&usize_word(head_size + tail.len()));
+            tail.extend(bytes);
+        } else {
+            head.extend(bytes);
+        }
+    }
+
+    head.extend(tail);
+    head
+}
+
+fn encode_value(value: &AbiValue) -> Vec<u8> {
+    let mut word = [0u8; 32];
+    match value {
+        AbiValue::Address(address) => word[12..].copy_from_slice(address),
+        AbiValue::Uint(uint) | AbiValue::Int(uint) => word = *uint,
+        AbiValue::Bool(b) => word[31] = *b as u8,
+        AbiValue::FixedBytes(bytes) => word[..bytes.len()].copy_from_slice(bytes),
+        AbiValue::Bytes(bytes) => return encode_padded_bytes(bytes),
+        AbiValue::String(string) => return encode_padded_bytes(string.as_bytes()),
+        AbiValue::Array(items) => {
+            let mut encoded = usize_word(items.len()).to_vec();
+            encoded.extend(encode_tuple(items));
+            return encoded;
+        }
+        AbiValue::Tuple(items) => return encode_tuple(items),
+    }
+    word.to_vec()
+}
+
+/// Length word followed by the data, right-padded with zeros to a multiple of 32 bytes.
+fn encode_padded_bytes(data: &[u8]) -> Vec<u8> {
+    let mut encoded = usize_word(data.len()).to_vec();
+    encoded.extend_from_slice(data);
+    encoded.resize(32 + data.len().div_ceil(32) * 32, 0);
+    encoded
+}
+
+fn usize_word(value: usize) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&(value as u64).to_be_bytes());
+    word
+}
+
+/// The largest in-place encoding a fixed-size array type may have: far more
+/// than fits in a TRON transaction, and small enough that decoding one can't
+/// exhaust memory.
+const MAX_FIXED_ARRAY_BYTES: usize = 1 << 20;
+
+/// A Solidity ABI type, parsed from its canonical name (`uint256`, `address[]`, ...).
+#[derive(Debug, Clone, PartialEq)]
+pub enum AbiType {
+    Address,
+    /// `uintN`, with N in bits
+    Uint(usize),
+    /// `intN`, with N in bits
+    Int(usize),
+    Bool,
+    /// `bytesN`, with N in bytes
+    FixedBytes(usize),
+    Bytes,
+    String,
+    Array(Box<AbiType>),
+    FixedArray(Box<AbiType>, usize),
+    Tuple(Vec<AbiType>),
+}
+
+impl AbiType {
+    /// Parses a type name. `components` are the tuple members, for `tuple` types.
+    pub fn parse(name: &str, components: &[AbiParam]) -> Result<Self> {
+        if let Some(inner) = name.strip_suffix("[]") {
+            return Ok(AbiType::Array(Box::new(AbiType::parse(inner, components)?)));
+        }
+        if let Some((inner, len)) = name
+            .strip_suffix(']')
+            .and_then(|rest| rest.rsplit_once('['))
+        {
+            let invalid_len =
+                || TronError::InvalidInput(format!("Invalid array length in ABI type: {}", name));
+            let len: usize = len.parse().map_err(|_| invalid_len())?;
+            let inner = AbiType::parse(inner, components)?;
+            if len == 0
+                || inner
+                    .static_size()
+                    .checked_mul(len)
+                    .is_none_or(|size| size > MAX_FIXED_ARRAY_BYTES)
+            {
+                return Err(invalid_len());
+            }
+            return Ok(AbiType::FixedArray(Box::new(inner), len));
+        }
+
+        let sized = |prefix: &str, default: usize| -> Option<Result<usize>> {
+            let size = name.strip_prefix(prefix)?;
+            if size.is_empty() {
+                return Some(Ok(default));
+            }
+            Some(
+                size.parse()
+                    .ok()
+                    .filter(|bits| bits % 8 == 0 && (8..=256).contains(bits))
+                    .ok_or_else(|| {
+                        TronError::InvalidInput(format!("Unsupported ABI type: {}", name))
+                    }),
+            )
+        };
+
+        Ok(match name {
+            "address" => AbiType::Address,
+            "bool" => AbiType::Bool,
+            "string" => AbiType::String,
+            "bytes" => AbiType::Bytes,
+            "tuple" => AbiType::Tuple(
+                components
+                    .iter()
+                    .map(AbiParam::abi_type)
+                    .collect::<Result<_>>()?,
+            ),
+            _ => {
+                if let Some(bits) = sized("uint", 256) {
+                    AbiType::Uint(bits?)
+                } else if let Some(bits) = sized("int", 256) {
+                    AbiType::Int(bits?)
+                } else if let Some(len) = name
+                    .strip_prefix("bytes")
+                    .and_then(|n| n.parse().ok())
+                    .filter(|len| (1..=32).contains(len))
+                {
+                    AbiType::FixedBytes(len)
+                } else {
+                    return Err(TronError::InvalidInput(format!(
+                        "Unsupported ABI type: {}",
+                        name
+                    )));
+                }
+            }
+        })
+    }
+
+    /// The type as written in a function signature (tuples expanded).
+    pub fn canonical(&self) -> String {
+        match self {
+            AbiType::Address => "address".to_string(),
+            AbiType::Uint(bits) => format!("uint{}", bits),
+            AbiType::Int(bits) => format!("int{}", bits),
+            AbiType::Bool => "bool".to_string(),
+            AbiType::FixedBytes(len) => format!("bytes{}", len),
+            AbiType::Bytes => "bytes".to_string(),
+            AbiType::String => "string".to_string(),
+            AbiType::Array(inner) => format!("{}[]", inner.canonical()),
+            AbiType::FixedArray(inner, len) => format!("{}[{}]", inner.canonical(), len),
+            AbiType::Tuple(members) => format!(
+                "({})",
+                members
+                    .iter()
+                    .map(AbiType::canonical)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+        }
+    }
+
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            AbiType::Bytes | AbiType::String | AbiType::Array(_) => true,
+            AbiType::FixedArray(inner, _) => inner.is_dynamic(),
+            AbiType::Tuple(members) => members.iter().any(AbiType::is_dynamic),
+            _ => false,
+        }
+    }
+
+    /// Size in bytes of a static type's in-place encoding.
+    pub fn static_size(&self) -> usize {
+        match self {
+            AbiType::FixedArray(inner, len) => inner.static_size() * len,
+            AbiType::Tuple(members) => members.iter().map(AbiType::static_size).sum(),
+            _ => 32,
+        }
+    }
+}
+
+/// A function parameter or return value in a contract ABI.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AbiParam {
+    #[serde(default)]
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub components: Vec<AbiParam>,
+}
+
+impl AbiParam {
+    pub fn abi_type(&self) -> Result<AbiType> {
+        AbiType::parse(&self.kind, &self.components)
+    }
+}
+
+/// One entry (function, constructor, event, ...) of a contract ABI.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AbiEntry {
+    #[serde(rename = "type", default)]
+    pub kind: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub inputs: Vec<AbiParam>,
+    #[serde(default)]
+    pub outputs: Vec<AbiParam>,
+    #[serde(default)]
+    pub state_mutability: String,
+}
+
+impl AbiEntry {
+    /// Canonical signature, e.g. `isApproved(uint256,address)`.
+    pub fn signature(&self) -> Result<String> {
+        let inputs = self.input_types()?;
+        Ok(format!(
+            "{}({})",
+            self.name,
+            inputs
+                .iter()
+                .map(AbiType::canonical)
+                .collect::<Vec<_>>()
+                .join(",")
+        ))
+    }
+
+    pub fn input_types(&self) -> Result<Vec<AbiType>> {
+        self.inputs.iter().map(AbiParam::abi_type).collect()
+    }
+
+    pub fn output_types(&self) -> Result<Vec<AbiType>> {
+        self.outputs.iter().map(AbiParam::abi_type).collect()
+    }
+}
+
+/// A parsed contract ABI, from a forge artifact, a plain ABI array, or the
+/// `abi` stored on-chain.
+#[derive(Debug, Clone)]
+pub struct ContractAbi {
+    pub entries: Vec<AbiEntry>,
+}
+
+impl ContractAbi {
+    /// Accepts a plain ABI array, a forge artifact (`{"abi": [...]}`) or the
+    /// node's `{"entrys": [...]}` form, whose `type` and `stateMutability`
+    /// values are capitalized (`Function`, `View`).
+    pub fn from_json(json: &serde_json::Value) -> Result<Self> {
+        let entries = json
+            .as_array()
+            .or_else(|| json.get("abi").and_then(|abi| abi.as_array()))
+            .or_else(|| json.get("entrys").and_then(|entries| entries.as_array()))
+            .ok_or_else(|| TronError::Decode("No ABI entries found".into()))?;
+
+        let mut parsed: Vec<AbiEntry> =
+            serde_json::from_value(serde_json::Value::Array(entries.clone()))
+                .or_fail(TronError::Decode, "Failed to parse ABI")?;
+        for entry in &mut parsed {
+            entry.kind = entry.kind.to_ascii_lowercase();
+            entry.state_mutability = entry.state_mutability.to_ascii_lowercase();
+        }
+        Ok(ContractAbi { entries: parsed })
+    }
+
+    /// Finds the function whose selector is `selector`.
+    pub fn function_by_selector(&self, selector: &[u8]) -> Option<&AbiEntry> {
+        self.entries
+            .iter()
+            .filter(|e| e.kind == "function")
+            .find(|e| {
+                e.signature()
+                    .is_ok_and(|signature| function_selector(&signature) == selector)
+            })
+    }
+
+    /// Finds a function by name or full signature. Overloads sharing a name
+    /// are told apart by `arg_count`.
+    pub fn function(&self, function: &str, arg_count: usize) -> Result<&AbiEntry> {
+        let functions = self.entries.iter().filter(|e| e.kind == "function");
+        if function.contains('(') {
+            let wanted: String = function.chars().filter(|c| !c.is_whitespace()).collect();
+            return functions
+                .into_iter()
+                .find(|e| e.signature().ok().as_deref() == Some(wanted.as_str()))
+                .ok_or_else(|| {
+                    TronError::InvalidInput(format!("Function {} not found in ABI", function))
+                });
+        }
+
+        let named: Vec<&AbiEntry> = functions.filter(|e| e.name == function).collect();
+        let candidates: Vec<&AbiEntry> = match named.len() {
+            0 => {
+                return Err(TronError::InvalidInput(format!(
+                    "Function {} not found in ABI",
+                    function
+                )))
+            }
+            1 => named,
+            _ => named
+                .into_iter()
+                .filter(|e| e.inputs.len() == arg_count)
+                .collect(),
+        };
+        match candidates.as_slice() {
+            [entry] => Ok(entry),
+            _ => Err(TronError::InvalidInput(format!(
+                "Function {} is overloaded; pass the full signature",
+                function
+            ))),
+        }
+    }
+}
+
+/// First four bytes of keccak256 of the canonical function signature.
+pub fn function_selector(signature: &str) -> [u8; 4] {
+    let hash = keccak256(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// Parses a command-line argument into a value of type `ty`. Addresses are
+/// TRON base58 (with `prefix`), integers decimal (or `0x` hex), bytes hex,
+/// and arrays and tuples JSON arrays of such values.
+pub fn parse_abi_arg(ty: &AbiType, raw: &str, prefix: u8) -> Result<AbiValue> {
+    let raw = raw.trim();
+    match ty {
+        AbiType::Address => AbiValue::address(raw, prefix),
+        AbiType::Uint(bits) => {
+            let word = parse_uint_word(raw)?;
+            check_int_width(&word, *bits, None, raw)?;
+            Ok(AbiValue::Uint(word))
+        }
+        AbiType::Int(bits) => {
+            let (negative, magnitude) = match raw.strip_prefix('-') {
+                Some(rest) => (true, rest),
+                None => (false, raw),
+            };
+            let mut word = parse_uint_word(magnitude)?;
+            check_int_width(&word, *bits, Some(negative), raw)?;
+            if negative {
+                word = negate_word(&word);
+            }
+            Ok(AbiValue::Int(word))
+        }
+        AbiType::Bool => match raw {
+            "true" => Ok(AbiValue::Bool(true)),
+            "false" => Ok(AbiValue::Bool(false)),
+            _ => Err(TronError::InvalidInput(format!("Invalid bool: {:?}", raw))),
+        },
+        AbiType::FixedBytes(len) => {
+            let bytes = hex::decode(raw.trim_start_matches("0x"))
+                .or_fail(TronError::InvalidInput, "Invalid hex")?;
+            if bytes.len() != *len {
+                return Err(TronError::InvalidInput(format!(
+                    "Expected {} bytes for bytes{}, got {}",
+                    len,
+                    len,
+                    bytes.len()
+                )));
+            }
+            Ok(AbiValue::FixedBytes(bytes))
+        }
+        AbiType::Bytes => Ok(AbiValue::Bytes(
+            hex::decode(raw.trim_start_matches("0x"))
+                .or_fail(TronError::InvalidInput, "Invalid hex")?,
+        )),
+        AbiType::String => Ok(AbiValue::String(raw.to_string())),
+        AbiType::Array(_) | AbiType::FixedArray(..) | AbiType::Tuple(_) => {
+            let items: Vec<serde_json::Value> = serde_json::from_str(raw).map_err(|_| {
+                TronError::InvalidInput(format!(
+                    "Expected a JSON array for {}: {}",
+                    ty.canonical(),
+                    raw
+                ))
+            })?;
+            let item_types: Vec<AbiType> = match ty {
+                AbiType::Array(inner) => vec![(**inner).clone(); items.len()],
+                AbiType::FixedArray(inner, len) => vec![(**inner).clone(); *len],
+                AbiType::Tuple(members) => members.clone(),
+                _ => unreachable!(),
+            };
+            if item_types.len() != items.len() {
+                return Err(TronError::InvalidInput(format!(
+                    "Expected {} elements for {}, got {}",
+                    item_types.len(),
+                    ty.canonical(),
+                    items.len()
+                )));
+            }
+            let values = item_types
+                .iter()
+                .zip(&items)
+                .map(|(item_type, item)| match item {
+                    serde_json::Value::String(s) => parse_abi_arg(item_type, s, prefix),
+                    other => parse_abi_arg(item_type, &other.to_string(), prefix),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(match ty {
+                AbiType::Array(_) => AbiValue::Array(values),
+                _ => AbiValue::Tuple(values),
+            })
+        }
+    }
+}
+
+/// Parses a non-negative decimal or `0x` hex integer into a big-endian word.
+pub fn parse_uint_word(raw: &str) -> Result<[u8; 32]> {
+    let invalid = || TronError::InvalidInput(format!("Invalid integer: {:?}", raw));
+    let mut word = [0u8; 32];
+    if let Some(hex_digits) = raw.strip_prefix("0x") {
+        if hex_digits.is_empty() || hex_digits.len() > 64 {
+            return Err(invalid());
+        }
+        let bytes = hex::decode(format!("{:0>64}", hex_digits)).map_err(|_| invalid())?;
+        word.copy_from_slice(&bytes);
+        return Ok(word);
+    }
+
+    if raw.is_empty() {
+        return Err(invalid());
+    }
+    for c in raw.chars() {
+        let digit = c.to_digit(10).ok_or_else(invalid)?;
+        // word = word * 10 + digit
+        let mut carry = digit;
+        for byte in word.iter_mut().rev() {
+            let next = *byte as u32 * 10 + carry;
+            *byte = next as u8;
+            carry = next >> 8;
+        }
+        if carry != 0 {
+            return Err(TronError::InvalidInput(format!(
+                "Integer too large for 256 bits: {}",
+                raw
+            )));
+        }
+    }
+    Ok(word)
+}
+
+/// Rejects magnitudes that do not fit in a `bits`-wide uint (`signed: None`)
+/// or int (`signed: Some(negative)`).
+fn check_int_width(
+    magnitude: &[u8; 32],
+    bits: usize,
+    signed: Option<bool>,
+    raw: &str,
+) -> Result<()> {
+    let bit_len = magnitude
+        .iter()
+        .position(|b| *b != 0)
+        .map_or(0, |i| 256 - i * 8 - magnitude[i].leading_zeros() as usize);
+    let is_power_of_two = magnitude.iter().map(|b| b.count_ones()).sum::<u32>() == 1;
+    let fits = match signed {
+        None => bit_len <= bits,
+        Some(false) => bit_len < bits,
+        // -2^(N-1) is the one magnitude with N bits that still fits
+        Some(true) => bit_len < bits || (bit_len == bits && is_power_of_two),
+    };
+    if fits {
+        Ok(())
+    } else {
+        Err(TronError::InvalidInput(format!(
+            "Value {} out of range for {}{}",
+            raw,
+            if signed.is_some() { "int" } else { "uint" },
+            bits
+        )))
+    }
+}
+
+/// Two's-complement negation of a 256-bit word.
+pub fn negate_word(word: &[u8; 32]) -> [u8; 32] {
+    let mut negated = [0u8; 32];
+    let mut carry = 1u16;
+    for i in (0..32).rev() {
+        let next = (!word[i]) as u16 + carry;
+        negated[i] = next as u8;
+        carry = next >> 8;
+    }
+    negated
+}
+
+/// Formats a big-endian 256-bit word as an unsigned decimal.
+pub fn word_to_decimal(word: &[u8; 32]) -> String {
+    let mut value = *word;
+    let mut digits = Vec::new();
+    while value.iter().any(|b| *b != 0) {
+        // value = value / 10, collecting the remainder
+        let mut remainder = 0u32;
+        for byte in value.iter_mut() {
+            let current = (remainder << 8) | *byte as u32;
+            *byte = (current / 10) as u8;
+            remainder = current % 10;
+        }
+        digits.push(char::from(b'0' + remainder as u8));
+    }
+    if digits.is_empty() {
+        return "0".to_string();
+    }
+    digits.iter().rev().collect()
+}
+
+/// Decodes ABI-encoded `data` as a tuple of `types` (a call's return values).
+pub fn decode_params(types: &[AbiType], data: &[u8]) -> Result<Vec<AbiValue>> {
+    decode_tuple(types, data, 0)
+}
+
+/// Selector of `Error(string)`, the revert data of `require` and `revert`.
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// Selector of `Panic(uint256)`, the revert data of failed asserts and
+/// arithmetic errors.
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// The reason in revert data: the message of `Error(string)` or the code of
+/// `Panic(uint256)`. `None` for empty data or custom errors.
+pub fn decode_revert_reason(data: &[u8]) -> Option<String> {
+    let (selector, params) = data.split_at_checked(4)?;
+    let ty = if selector == ERROR_SELECTOR {
+        AbiType::String
+    } else if selector == PANIC_SELECTOR {
+        AbiType::Uint(256)
+    } else {
+        return None;
+    };
+    match decode_params(&[ty], params).ok()?.pop()? {
+        AbiValue::String(reason) => Some(reason),
+        AbiValue::Uint(code) => Some(format!("Panic(0x{:02x})", code[31])),
+        _ => None,
+    }
+}
+
+/// The [`TronError::Revert`] for revert `data`: its decoded reason, or the
+/// data as `0x` hex for custom errors.
+pub fn revert_error(data: &[u8]) -> TronError {
+    TronError::Revert(
+        decode_revert_reason(data).unwrap_or_else(|| format!("0x{}", hex::encode(data))),
+    )
+}
+
+/// Decodes a tuple whose head starts at `base`; dynamic members' offsets are
+/// relative to `base`.
+fn decode_tuple(types: &[AbiType], data: &[u8], base: usize) -> Result<Vec<AbiValue>> {
+    let mut values = Vec::with_capacity(types.len());
+    let mut position = base;
+    for ty in types {
+        if ty.is_dynamic() {
+            let offset = read_usize(data, position)?;
+            let start = base
+                .checked_add(offset)
+                .ok_or_else(|| TronError::Decode(format!("Invalid ABI offset: {}", offset)))?;
+            values.push(decode_value(ty, data, start)?);
+            position += 32;
+        } else {
+            values.push(decode_value(ty, data, position)?);
+            position += ty.static_size();
+        }
+    }
+    Ok(values)
+}
+
+fn decode_value(ty: &AbiType, data: &[u8], at: usize) -> Result<AbiValue> {
+    Ok(match ty {
+        AbiType::Address => {
+            let word = read_word(data, at)?;
+            AbiValue::Address(word[12..].try_into().expect("20-byte slice"))
+        }
+        AbiType::Uint(_) => AbiValue::Uint(read_word(data, at)?),
+        AbiType::Int(_) => AbiValue::Int(read_word(data, at)?),
+        AbiType::Bool => match read_word(data, at)? {
+            word if word[..31].iter().all(|b| *b == 0) && word[31] <= 1 => {
+                AbiValue::Bool(word[31] == 1)
+            }
+            _ => return Err(TronError::Decode(format!("Invalid bool at byte {}", at))),
+        },
+        AbiType::FixedBytes(len) => AbiValue::FixedBytes(read_word(data, at)?[..*len].to_vec()),
+        AbiType::Bytes | AbiType::String => {
+            let len = read_usize(data, at)?;
+            let bytes = data
+                .get(at + 32..)
+                .and_then(|rest| rest.get(..len))
+                .ok_or_else(|| {
+                    TronError::Decode(format!("ABI data too short for {} bytes at {}", len, at))
+                })?
+                .to_vec();
+            if *ty == AbiType::String {
+                AbiValue::String(
+                    String::from_utf8(bytes)
+                        .or_fail(TronError::Decode, "Invalid UTF-8 in string")?,
+                )
+            } else {
+                AbiValue::Bytes(bytes)
+            }
+        }
+        AbiType::Array(inner) => {
+            let len = read_usize(data, at)?;
+            // Every element takes at least one word, which bounds bogus lengths
+            if len > data.len() / 32 {
+                return Err(TronError::Decode(format!("Invalid array length: {}", len)));
+            }
+            AbiValue::Array(decode_tuple(&vec![(**inner).clone(); len], data, at + 32)?)
+        }
+        AbiType::FixedArray(inner, len) => {
+            AbiValue::Tuple(decode_tuple(&vec![(**inner).clone(); *len], data, at)?)
+        }
+        AbiType::Tuple(members) => AbiValue::Tuple(decode_tuple(members, data, at)?),
+    })
+}
+
+fn read_word(data: &[u8], at: usize) -> Result<[u8; 32]> {
+    data.get(at..at.saturating_add(32))
+        .and_then(|word| word.try_into().ok())
+        .ok_or_else(|| TronError::Decode(format!("ABI data too short: no word at byte {}", at)))
+}
+
+fn read_usize(data: &[u8], at: usize) -> Result<usize> {
+    let word = read_word(data, at)?;
+    if word[..24].iter().any(|b| *b != 0) {
+        return Err(TronError::Decode(format!(
+            "ABI length or offset too large at byte {}",
+            at
+        )));
+    }
+    usize::try_from(u64::from_be_bytes(
+        word[24..].try_into().expect("8-byte slice"),
+    ))
+    .or_fail(TronError::Decode, "ABI length or offset too large")
+}
+
+/// Converts a decoded value to JSON: addresses as TRON base58 with `prefix`,
+/// integers as decimal strings (they may exceed JSON's safe range), bytes as
+/// `0x` hex.
+pub fn abi_value_to_json(value: &AbiValue, prefix: u8) -> serde_json::Value {
+    match value {
+        AbiValue::Address(address) => raw_address_to_tron(address, prefix).into(),
+        AbiValue::Uint(word) => word_to_decimal(word).into(),
+        AbiValue::Int(word) if word[0] & 0x80 != 0 => {
+            format!("-{}", word_to_decimal(&negate_word(word))).into()
+        }
+        AbiValue::Int(word) => word_to_decimal(word).into(),
+        AbiValue::Bool(b) => (*b).into(),
+        AbiValue::FixedBytes(bytes) | AbiValue::Bytes(bytes) => {
+            format!("0x{}", hex::encode(bytes)).into()
+        }
+        AbiValue::String(s) => s.clone().into(),
+        AbiValue::Array(items) | AbiValue::Tuple(items) => items
+            .iter()
+            .map(|item| abi_value_to_json(item, prefix))
+            .collect(),
+    }
+}
+
+pub fn get_contract_abi() -> &'static str {
+    r#"[{"inputs":[{"internalType":"address","name":"_usdt","type":"address"},{"internalType":"address[]","name":"_owners","type":"address[]"},{"internalType":"uint256","name":"_threshold","type":"uint256"}],"stateMutability":"nonpayable","type":"constructor"},{"inputs":[],"name":"usdt","outputs":[{"internalType":"contract IERC20","name":"","type":"address"}],"stateMutability":"view","type":"function"},{"inputs":[],"name":"threshold","outputs":[{"internalType":"uint256","name":"","type":"uint256"}],"stateMutability":"view","type":"function"},{"inputs":[],"name":"getOwners","outputs":[{"internalType":"address[]","name":"","type":"address[]"}],"stateMutability":"view","type":"function"},{"inputs":[],"name":"getOwnerCount","outputs":[{"internalType":"uint256","name":"","type":"uint256"}],"stateMutability":"view","type":"function"},{"inputs":[],"name":"getBalance","outputs":[{"internalType":"uint256","name":"","type":"uint256"}],"stateMutability":"view","type":"function"},{"inputs":[],"name":"getTransactionCount","outputs":[{"internalType":"uint256","name":"","type":"uint256"}],"stateMutability":"view","type":"function"},{"inputs":[{"internalType":"uint256","name":"_txId","type":"uint256"}],"name":"getTransaction","outputs":[{"internalType":"address","name":"to","type":"address"},{"internalType":"uint256","name":"amount","type":"uint256"},{"internalType":"bool","name":"executed","type":"bool"},{"internalType":"uint256","name":"approvalCount","type":"uint256"},{"internalType":"uint256","name":"createdAt","type":"uint256"},{"internalType":"uint256","name":"expiresAt","type":"uint256"}],"stateMutability":"view","type":"function"},{"inputs":[{"internalType":"uint256","name":"_txId","type":"uint256"}],"name":"isExpired","outputs":[{"internalType":"bool","name":"","type":"bool"}],"stateMutability":"view","type":"function"},{"inputs":[{"internalType":"uint256","name":"_txId","type":"uint256"}],"name":"getTimeUntilExpiration","outputs":[{"internalType":"uint256","name":"","type":"uint256"}],"stateMutability":"view","type":"function"},{"inputs":[{"internalType":"uint256","name":"_txId","type":"uint256"},{"internalType":"address","name":"_owner","type":"address"}],"name":"isApproved","outputs":[{"internalType":"bool","name":"","type":"bool"}],"stateMutability":"view","type":"function"},{"inputs":[{"internalType":"address","name":"","type":"address"}],"name":"isOwner","outputs":[{"internalType":"bool","name":"","type":"bool"}],"stateMutability":"view","type":"function"},{"inputs":[{"internalType":"address","name":"_to","type":"address"},{"internalType":"uint256","name":"_amount","type":"uint256"}],"name":"submitTransaction","outputs":[{"internalType":"uint256","name":"txId","type":"uint256"}],"stateMutability":"nonpayable","type":"function"},{"inputs":[{"internalType":"uint256","name":"_txId","type":"uint256"}],"name":"approveTransaction","outputs":[],"stateMutability":"nonpayable","type":"function"},{"inputs":[{"internalType":"uint256","name":"_txId","type":"uint256"}],"name":"revokeApproval","outputs":[],"stateMutability":"nonpayable","type":"function"},{"inputs":[{"internalType":"uint256","name":"_txId","type":"uint256"}],"name":"cancelExpiredTransaction","outputs":[],"stateMutability":"nonpayable","type":"function"}]"#
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::{hex_to_tron_address, TRON_ADDRESS_PREFIX};
+
+    #[test]
+    fn decode_revert_reason_reads_error_and_panic() {
+        let error = [
+            "08c379a0",
+            "0000000000000000000000000000000000000000000000000000000000000020",
+            "000000000000000000000000000000000000000000000000000000000000000c",
+            "4e6f7420616e206f776e65720000000000000000000000000000000000000000",
+        ]
+        .concat();
+        assert_eq!(
+            decode_revert_reason(&hex::decode(error).unwrap()).as_deref(),
+            Some("Not an owner")
+        );
+
+        let panic =
+            hex::decode("4e487b710000000000000000000000000000000000000000000000000000000000000011")
+                .unwrap();
+        assert_eq!(decode_revert_reason(&panic).as_deref(), Some("Panic(0x11)"));
+        assert_eq!(decode_revert_reason(&[0xde, 0xad, 0xbe, 0xef]), None);
+        assert_eq!(
+            revert_error(&[0xde, 0xad, 0xbe, 0xef]),
+            TronError::Revert("0xdeadbeef".to_string())
+        );
+        assert_eq!(revert_error(&panic).to_string(), "Panic(0x11)");
+        assert_eq!(decode_revert_reason(&[]), None);
+    }
+
+    #[test]
+    fn decode_address_array_follows_offset() {
+        // Offset 0x40 with an unrelated word in between, as for `(uint256, address[])`
+        let data = [
+            "0000000000000000000000000000000000000000000000000000000000000040",
+            "0000000000000000000000000000000000000000000000000000000000000007",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "000000000000000000000000a614f803b6fd780986a42c78ec9c7f77e6ded13c",
+            "00000000000000000000000078c842ee63b253d8f0d2955bbc582c661a078c9d",
+        ]
+        .concat();
+
+        assert_eq!(
+            decode_address_array(&data, TRON_ADDRESS_PREFIX).unwrap(),
+            vec![
+                "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t".to_string(),
+                hex_to_tron_address(
+                    "4178c842ee63b253d8f0d2955bbc582c661a078c9d",
+                    TRON_ADDRESS_PREFIX
+                )
+                .unwrap(),
+            ]
+        );
+
+        // `decode-address-list` inverts the encoding of an owner list
+        let owners = [
+            "TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b",
+            "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t",
+        ];
+        let array = AbiValue::Array(
+            owners
+                .iter()
+                .map(|o| AbiValue::address(o, TRON_ADDRESS_PREFIX).unwrap())
+                .collect(),
+        );
+        assert_eq!(
+            decode_address_array(&encode_params(&[array]), TRON_ADDRESS_PREFIX).unwrap(),
+            owners
+        );
+    }
+
+    #[test]
+    fn encode_constructor_params_matches_fixed_layout() {
+        let params = encode_constructor_params(
+            "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t",
+            &["TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b"],
+            1,
+            TRON_ADDRESS_PREFIX,
+        )
+        .unwrap();
+
+        let expected = [
+            "000000000000000000000000a614f803b6fd780986a42c78ec9c7f77e6ded13c",
+            "0000000000000000000000000000000000000000000000000000000000000060",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "00000000000000000000000077e2c2ccf3eae21463a927d2767884468b7615de",
+        ]
+        .concat();
+        assert_eq!(params, expected);
+    }
+
+    #[test]
+    fn decode_constructor_params_requires_canonical_encoding() {
+        let abi =
+            ContractAbi::from_json(&serde_json::from_str(get_contract_abi()).unwrap()).unwrap();
+        let params = encode_constructor_params(
+            "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t",
+            &["TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b"],
+            1,
+            TRON_ADDRESS_PREFIX,
+        )
+        .unwrap();
+
+        let values = decode_constructor_params(&abi, &format!("0x{}", params)).unwrap();
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[2], AbiValue::uint(1));
+
+        let trailing = format!("{}{}", params, "00".repeat(32));
+        assert!(decode_constructor_params(&abi, &trailing)
+            .unwrap_err()
+            .to_string()
+            .contains("not the canonical encoding of constructor(address,address[],uint256)"));
+        assert!(decode_constructor_params(&abi, &params[..128])
+            .unwrap_err()
+            .to_string()
+            .starts_with("Constructor parameters don't decode as"));
+    }
+
+    #[test]
+    fn encode_params_mixes_static_and_dynamic_values() {
+        // (address, string, uint256)
+        let params = encode_params(&[
+            AbiValue::address("TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t", TRON_ADDRESS_PREFIX).unwrap(),
+            AbiValue::String("payout memo".to_string()),
+            AbiValue::uint(1_000_000),
+        ]);
+
+        let expected = [
+            "000000000000000000000000a614f803b6fd780986a42c78ec9c7f77e6ded13c",
+            "0000000000000000000000000000000000000000000000000000000000000060",
+            "00000000000000000000000000000000000000000000000000000000000f4240",
+            "000000000000000000000000000000000000000000000000000000000000000b",
+            "7061796f7574206d656d6f000000000000000000000000000000000000000000",
+        ]
+        .concat();
+        assert_eq!(params, expected);
+    }
+
+    #[test]
+    fn encode_params_offsets_account_for_earlier_tails() {
+        // (bytes, bool, string) with a 33-byte `bytes` spilling into two words
+        let params = encode_params(&[
+            AbiValue::Bytes(vec![0xab; 33]),
+            AbiValue::Bool(true),
+            AbiValue::String(String::new()),
+        ]);
+
+        let expected = [
+            "0000000000000000000000000000000000000000000000000000000000000060",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "00000000000000000000000000000000000000000000000000000000000000c0",
+            "0000000000000000000000000000000000000000000000000000000000000021",
+            "abababababababababababababababababababababababababababababababab",
+            "ab00000000000000000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+        ]
+        .concat();
+        assert_eq!(params, expected);
+    }
+
+    #[test]
+    fn contract_abi_parses_on_chain_entries() {
+        let json = serde_json::json!({
+            "entrys": [
+                {
+                    "name": "isApproved",
+                    "type": "Function",
+                    "stateMutability": "View",
+                    "inputs": [
+                        { "name": "_txId", "type": "uint256" },
+                        { "name": "_owner", "type": "address" }
+                    ],
+                    "outputs": [{ "type": "bool" }]
+                },
+                { "name": "TransactionApproved", "type": "Event" }
+            ]
+        });
+        let abi = ContractAbi::from_json(&json).unwrap();
+
+        let entry = abi.function("isApproved", 2).unwrap();
+        assert_eq!(entry.state_mutability, "view");
+        assert_eq!(entry.signature().unwrap(), "isApproved(uint256,address)");
+        assert_eq!(
+            hex::encode(function_selector("transfer(address,uint256)")),
+            "a9059cbb"
+        );
+        assert!(abi.function("TransactionApproved", 0).is_err());
+    }
+
+    #[test]
+    fn abi_types_reject_out_of_range_sizes() {
+        let parse = |name| AbiType::parse(name, &[]);
+        assert_eq!(parse("bytes32").unwrap(), AbiType::FixedBytes(32));
+        assert_eq!(parse("uint8[3]").unwrap().static_size(), 96);
+        for name in ["bytes0", "bytes33", "uint0", "uint7", "int264", "uint[0]"] {
+            assert!(parse(name).is_err(), "{}", name);
+        }
+        // 2 MiB in place, and nested lengths whose product overflows
+        let err = parse("bytes32[65536]").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid array length in ABI type: bytes32[65536]"
+        );
+        assert!(parse("uint8[4294967296][4294967296][4294967296]").is_err());
+    }
+
+    #[test]
+    fn decode_params_round_trips_encoded_values() {
+        let types = [
+            AbiType::parse("address", &[]).unwrap(),
+            AbiType::parse("int256", &[]).unwrap(),
+            AbiType::parse("string", &[]).unwrap(),
+            AbiType::parse("uint64[2]", &[]).unwrap(),
+            AbiType::parse("bytes[]", &[]).unwrap(),
+        ];
+        let values = vec![
+            parse_abi_arg(
+                &types[0],
+                "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t",
+                TRON_ADDRESS_PREFIX,
+            )
+            .unwrap(),
+            parse_abi_arg(&types[1], "-42", TRON_ADDRESS_PREFIX).unwrap(),
+            parse_abi_arg(&types[2], "memo", TRON_ADDRESS_PREFIX).unwrap(),
+            parse_abi_arg(&types[3], "[1, \"0x02\"]", TRON_ADDRESS_PREFIX).unwrap(),
+            parse_abi_arg(&types[4], "[\"0xabcd\", \"\"]", TRON_ADDRESS_PREFIX).unwrap(),
+        ];
+
+        let encoded = hex::decode(encode_params(&values)).unwrap();
+        assert_eq!(decode_params(&types, &encoded).unwrap(), values);
+
+        let json: Vec<serde_json::Value> = values
+            .iter()
+            .map(|value| abi_value_to_json(value, TRON_ADDRESS_PREFIX))
+            .collect();
+        assert_eq!(
+            serde_json::Value::Array(json),
+            serde_json::json!([
+                "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t",
+                "-42",
+                "memo",
+                ["1", "2"],
+                ["0xabcd", "0x"]
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_abi_arg_enforces_integer_width() {
+        let uint8 = AbiType::Uint(8);
+        let int8 = AbiType::Int(8);
+
+        assert!(parse_abi_arg(&uint8, "255", TRON_ADDRESS_PREFIX).is_ok());
+        assert!(parse_abi_arg(&uint8, "256", TRON_ADDRESS_PREFIX).is_err());
+        assert!(parse_abi_arg(&uint8, "-1", TRON_ADDRESS_PREFIX).is_err());
+        assert!(parse_abi_arg(&int8, "127", TRON_ADDRESS_PREFIX).is_ok());
+        assert!(parse_abi_arg(&int8, "128", TRON_ADDRESS_PREFIX).is_err());
+        assert!(parse_abi_arg(&int8, "-128", TRON_ADDRESS_PREFIX).is_ok());
+        assert!(parse_abi_arg(&int8, "-129", TRON_ADDRESS_PREFIX).is_err());
+    }
+
+    #[test]
+    fn word_to_decimal_handles_full_width_values() {
+        let max = parse_uint_word(&format!("0x{}", "f".repeat(64))).unwrap();
+
+        assert_eq!(
+            word_to_decimal(&max),
+            "115792089237316195423570985008687907853269984665640564039457584007913129639935"
+        );
+        assert_eq!(word_to_decimal(&[0u8; 32]), "0");
+        assert_eq!(parse_uint_word(&word_to_decimal(&max)).unwrap(), max);
+    }
+
+    #[test]
+    fn function_by_selector_matches_embedded_abi() {
+        let abi =
+            ContractAbi::from_json(&serde_json::from_str(get_contract_abi()).unwrap()).unwrap();
+        let selector = function_selector("approveTransaction(uint256)");
+
+        let entry = abi.function_by_selector(&selector).unwrap();
+        assert_eq!(entry.name, "approveTransaction");
+        assert!(abi.function_by_selector(&[0, 0, 0, 0]).is_none());
+    }
+}
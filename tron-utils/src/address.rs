@@ -0,0 +1,540 @@
+//! Keys, TRON addresses and Base58Check.
+
+use crate::error::{Result, ResultExt, TronError};
+use crate::hash::{base58_checksum, keccak256, sha256};
+use secp256k1::{PublicKey, Secp256k1, SecretKey};
+
+pub fn parse_private_key(private_key: &str) -> Result<SecretKey> {
+    let key_hex = private_key.trim_start_matches("0x");
+    let key_bytes =
+        hex::decode(key_hex).or_fail(TronError::InvalidKey, "Invalid private key hex")?;
+    SecretKey::from_slice(&key_bytes).or_fail(TronError::InvalidKey, "Invalid private key")
+}
+
+/// Minimum `--entropy` length: 32 bytes, the size of a private key.
+pub const MIN_ENTROPY_BYTES: usize = 32;
+
+/// Derives a private key deterministically from caller-supplied entropy by
+/// hashing it with SHA-256 (rehashing in the vanishingly unlikely case the
+/// digest is not a valid secp256k1 scalar). Rejects inputs shorter than 256 bits.
+pub fn secret_key_from_entropy(entropy: &[u8]) -> Result<SecretKey> {
+    if entropy.len() < MIN_ENTROPY_BYTES {
+        return Err(TronError::InvalidInput(format!(
+            "Entropy must be at least {} bytes (256 bits), got {}",
+            MIN_ENTROPY_BYTES,
+            entropy.len()
+        )));
+    }
+
+    let mut digest = sha256(entropy);
+    loop {
+        if let Ok(key) = SecretKey::from_slice(&digest) {
+            return Ok(key);
+        }
+        digest = sha256(&digest);
+    }
+}
+
+pub fn public_key_from_private_key(private_key: &str) -> Result<PublicKey> {
+    let secp = Secp256k1::new();
+    Ok(parse_private_key(private_key)?.public_key(&secp))
+}
+
+pub fn private_key_to_tron_address(private_key: &str, prefix: u8) -> Result<String> {
+    let public_key = public_key_from_private_key(private_key)?;
+    Ok(public_key_to_tron_address(&public_key, prefix))
+}
+
+/// The base58 address of `public_key` under address prefix byte `prefix`
+/// ([`TRON_ADDRESS_PREFIX`] on every TRON network).
+pub fn public_key_to_tron_address(public_key: &PublicKey, prefix: u8) -> String {
+    // Get uncompressed public key (65 bytes: 04 + x + y)
+    let pub_key_bytes = public_key.serialize_uncompressed();
+
+    // Keccak256 hash of public key (skip first byte 0x04); see `keccak256`
+    // for why this must not be SHA3-256
+    let hash = keccak256(&pub_key_bytes[1..]);
+
+    // Last 20 bytes of the hash are the address
+    let raw: [u8; 20] = hash[12..].try_into().expect("keccak256 digest is 32 bytes");
+    format_address(&raw, AddressFormat::Base58, prefix)
+}
+
+/// Prefix byte of TRON addresses. Private or forked TRON-compatible chains
+/// may use another; the functions that format, parse or derive addresses
+/// take the prefix as a parameter.
+pub const TRON_ADDRESS_PREFIX: u8 = 0x41;
+
+/// Textual forms of a 20-byte address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum AddressFormat {
+    /// TRON base58check (`T...`)
+    Base58,
+    /// Hex with the TRON `41` prefix (21 bytes)
+    Hex41,
+    /// Bare 20-byte hex, as in ABI words
+    HexRaw,
+    /// `0x`-prefixed 20-byte hex, as used by EVM tooling
+    Evm,
+}
+
+/// Renders a 20-byte address in `format`, with `prefix` for the prefixed ones.
+pub fn format_address(address: &[u8; 20], format: AddressFormat, prefix: u8) -> String {
+    match format {
+        AddressFormat::Base58 => base58_address(address, prefix),
+        AddressFormat::Hex41 => format!("{:02x}{}", prefix, hex::encode(address)),
+        AddressFormat::HexRaw => hex::encode(address),
+        AddressFormat::Evm => format!("0x{}", hex::encode(address)),
+    }
+}
+
+/// Base58Check of `prefix` followed by the 20-byte `address`.
+pub fn base58_address(address: &[u8; 20], prefix: u8) -> String {
+    let mut bytes = vec![prefix];
+    bytes.extend_from_slice(address);
+    bs58_check_encode(&bytes)
+}
+
+/// Parses an address in any [`AddressFormat`] (hex optionally `0x`-prefixed)
+/// into its 20 raw bytes. Prefixed forms must carry `prefix`.
+pub fn parse_address(input: &str, prefix: u8) -> Result<[u8; 20]> {
+    let input = input.trim();
+    let hex_part = input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+        .unwrap_or(input);
+    let is_hex = !hex_part.is_empty() && hex_part.chars().all(|c| c.is_ascii_hexdigit());
+
+    let bytes = match (is_hex, hex_part.len()) {
+        (true, 40) | (true, 42) => {
+            hex::decode(hex_part).or_fail(TronError::InvalidAddress, "Invalid hex address")?
+        }
+        _ if input.starts_with('T') || prefix != TRON_ADDRESS_PREFIX => {
+            validate_tron_address(input, prefix)?
+        }
+        _ => {
+            return Err(TronError::InvalidAddress(format!(
+                "Not a TRON address (expected T... base58, 41-prefixed hex or 20-byte hex): {}",
+                input
+            )))
+        }
+    };
+    strip_address_prefix(&bytes, prefix).ok_or_else(|| {
+        TronError::InvalidAddress(format!(
+            "Not a 21-byte 0x{:02x}-prefixed TRON address: {}",
+            prefix, input
+        ))
+    })
+}
+
+/// Splits the 20 raw bytes off a 21-byte address starting with `prefix`, or
+/// accepts 20 raw bytes as-is.
+fn strip_address_prefix(bytes: &[u8], prefix: u8) -> Option<[u8; 20]> {
+    match bytes {
+        [first, rest @ ..] if *first == prefix && rest.len() == 20 => rest.try_into().ok(),
+        _ if bytes.len() == 20 => bytes.try_into().ok(),
+        _ => None,
+    }
+}
+
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Length of every base58 TRON address: the 0x41 prefix puts 21 bytes plus
+/// checksum in the range that always encodes to 34 characters. Other
+/// prefixes are not held to it.
+pub const TRON_ADDRESS_LEN: usize = 34;
+
+/// Decodes a TRON base58 address, turning hand-typing mistakes (characters
+/// outside the base58 alphabet, truncated or over-long pastes) into an
+/// actionable message. The payload must be 21 bytes starting with `prefix`
+/// ([`TRON_ADDRESS_PREFIX`] on TRON), since other Base58Check strings
+/// (Bitcoin addresses, other chains) decode fine but name no TRON account.
+pub fn validate_tron_address(address: &str, prefix: u8) -> Result<Vec<u8>> {
+    let invalid = address
+        .chars()
+        .enumerate()
+        .find(|(_, c)| !BASE58_ALPHABET.contains(*c));
+    if let Some((index, c)) = invalid {
+        let hint = match c {
+            '0' | 'O' => " — did you mean 'o'?",
+            'I' => " — did you mean '1' or 'i'?",
+            'l' => " — did you mean '1' or 'L'?",
+            _ => "",
+        };
+        return Err(TronError::InvalidAddress(format!(
+            "Address contains invalid base58 character '{}' at position {}{}",
+            c,
+            index + 1,
+            hint
+        )));
+    }
+    let length = address.chars().count();
+    let standard = prefix == TRON_ADDRESS_PREFIX;
+    if standard && (length != TRON_ADDRESS_LEN || !address.starts_with('T')) {
+        return Err(TronError::InvalidAddress(format!(
+            "Not a TRON address: expected {} characters starting with 'T', got {} starting with {:?}",
+            TRON_ADDRESS_LEN,
+            length,
+            address.chars().next().map(String::from).unwrap_or_default()
+        )));
+    }
+
+    let payload = bs58_check_decode(address)?;
+    match payload.first() {
+        Some(&first) if first != prefix => Err(TronError::InvalidAddress(format!(
+            "Not a TRON address: expected 0x{:02x} prefix, got 0x{:02x}",
+            prefix, first
+        ))),
+        _ if payload.len() != 21 => Err(TronError::InvalidAddress(format!(
+            "Not a TRON address: expected 21 bytes, got {}",
+            payload.len()
+        ))),
+        _ => Ok(payload),
+    }
+}
+
+pub fn tron_address_to_hex(address: &str, prefix: u8) -> Result<String> {
+    let bytes = validate_tron_address(address, prefix)?;
+    Ok(hex::encode(&bytes))
+}
+
+/// The base58 form of a hex address (optionally `0x`-prefixed), which must
+/// be 21 bytes starting with `prefix`.
+pub fn hex_to_tron_address(hex_addr: &str, prefix: u8) -> Result<String> {
+    let clean_hex = hex_addr.trim().trim_start_matches("0x");
+    let bytes = hex::decode(clean_hex).or_fail(TronError::InvalidAddress, "Invalid hex")?;
+    match bytes.split_first() {
+        Some((&first, rest)) if first == prefix && rest.len() == 20 => {
+            Ok(bs58_check_encode(&bytes))
+        }
+        _ => Err(TronError::InvalidAddress(format!(
+            "Not a 21-byte 0x{:02x}-prefixed TRON address: {}",
+            prefix, hex_addr
+        ))),
+    }
+}
+
+/// Base58Check-encodes `data`. Each leading zero byte becomes a leading `1`
+/// per the base58 spec, so payloads with zero bytes up front round-trip intact.
+pub fn bs58_check_encode(data: &[u8]) -> String {
+    let mut with_checksum = data.to_vec();
+    with_checksum.extend_from_slice(&base58_checksum(data));
+
+    bs58::encode(&with_checksum).into_string()
+}
+
+pub fn bs58_check_decode(address: &str) -> Result<Vec<u8>> {
+    let decoded = bs58::decode(address)
+        .into_vec()
+        .or_fail(TronError::InvalidAddress, "Invalid base58 address")?;
+
+    if decoded.len() < 4 {
+        return Err(TronError::InvalidAddress("Address too short".into()));
+    }
+
+    let data = &decoded[..decoded.len() - 4];
+    let checksum = &decoded[decoded.len() - 4..];
+
+    // Verify checksum
+    if base58_checksum(data) != checksum {
+        return Err(TronError::InvalidAddress("Invalid checksum".into()));
+    }
+
+    Ok(data.to_vec())
+}
+
+/// TRON base58 address for a 20-byte ABI address.
+pub fn raw_address_to_tron(address: &[u8; 20], prefix: u8) -> String {
+    format_address(address, AddressFormat::Base58, prefix)
+}
+
+/// Last 20 bytes of Keccak-256 over `parts`, TRON's contract address hash.
+fn keccak_address(parts: &[&[u8]]) -> [u8; 20] {
+    let hash = keccak256(&parts.concat());
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&hash[12..]);
+    address
+}
+
+/// Address of the contract a `CreateSmartContract` transaction (what
+/// `/wallet/deploycontract` builds) deploys: derived from its txID and the
+/// owner address with its `prefix` byte (`0x41` on TRON). Known once the
+/// transaction is built, before it is signed or broadcast.
+pub fn deployment_address(tx_id: &[u8; 32], owner: &[u8; 20], prefix: u8) -> [u8; 20] {
+    keccak_address(&[tx_id, &[prefix], owner])
+}
+
+/// Address of the `nonce`-th contract created with the CREATE opcode while
+/// executing transaction `tx_id`. TRON has no account nonces; `nonce` counts
+/// the internal transactions of that one transaction.
+pub fn create_address(tx_id: &[u8; 32], nonce: u64) -> [u8; 20] {
+    keccak_address(&[tx_id, &nonce.to_be_bytes()])
+}
+
+/// Address a CREATE2 from `deployer` yields for `salt` and the Keccak-256 of
+/// the init code (creation bytecode followed by constructor arguments). As on
+/// Ethereum, except that the leading `0xff` byte is the address `prefix`
+/// (`0x41` on TRON).
+pub fn create2_address(
+    deployer: &[u8; 20],
+    salt: &[u8; 32],
+    init_code_hash: &[u8; 32],
+    prefix: u8,
+) -> [u8; 20] {
+    keccak_address(&[&[prefix], deployer, salt, init_code_hash])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bs58_check_encode_preserves_leading_zero_bytes() {
+        let payload = [0x00, 0x00, 0x00, 0x41, 0xde, 0xad, 0xbe, 0xef];
+        let encoded = bs58_check_encode(&payload);
+
+        assert!(encoded.starts_with("111"));
+        assert!(!encoded.starts_with("1111"));
+        assert_eq!(bs58_check_decode(&encoded).unwrap(), payload);
+    }
+
+    #[test]
+    fn bs58_check_encode_all_zero_payload() {
+        let payload = [0u8; 21];
+        let encoded = bs58_check_encode(&payload);
+
+        assert!(encoded.starts_with(&"1".repeat(21)));
+        assert_eq!(bs58_check_decode(&encoded).unwrap(), payload);
+    }
+
+    #[test]
+    fn validate_tron_address_points_at_confusable_characters() {
+        let err = validate_tron_address("TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6O", TRON_ADDRESS_PREFIX)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Address contains invalid base58 character 'O' at position 34 — did you mean 'o'?"
+        );
+
+        let err = validate_tron_address("TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj 6", TRON_ADDRESS_PREFIX)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Address contains invalid base58 character ' ' at position 33"
+        );
+
+        let truncated =
+            validate_tron_address("TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj", TRON_ADDRESS_PREFIX)
+                .unwrap_err();
+        assert_eq!(
+            truncated.to_string(),
+            "Not a TRON address: expected 34 characters starting with 'T', got 32 starting with \"T\""
+        );
+        let bitcoin =
+            validate_tron_address("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2", TRON_ADDRESS_PREFIX)
+                .unwrap_err();
+        assert!(bitcoin.to_string().ends_with("got 34 starting with \"1\""));
+        // Valid Base58Check that looks right but carries prefix 0x42
+        let foreign =
+            validate_tron_address("TZQ9596PFNVSh3tEsypax47Hdff4DKLkmj", TRON_ADDRESS_PREFIX)
+                .unwrap_err();
+        assert_eq!(
+            foreign.to_string(),
+            "Not a TRON address: expected 0x41 prefix, got 0x42"
+        );
+        assert!(
+            validate_tron_address("TA4Y62o6YC2Zsck9rZVGTvqW1AQ7X9zTnj", TRON_ADDRESS_PREFIX)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn secret_key_from_entropy_is_deterministic() {
+        let entropy = [0x5au8; 32];
+        let key = secret_key_from_entropy(&entropy).unwrap();
+
+        assert_eq!(key, secret_key_from_entropy(&entropy).unwrap());
+        assert_eq!(key.secret_bytes().to_vec(), sha256(&entropy));
+        assert_ne!(key, secret_key_from_entropy(&[0x5bu8; 32]).unwrap());
+    }
+
+    #[test]
+    fn entropy_to_address_pipeline_is_reproducible() {
+        let key = secret_key_from_entropy(&[0xab; 32]).unwrap();
+        let address =
+            private_key_to_tron_address(&hex::encode(key.secret_bytes()), TRON_ADDRESS_PREFIX)
+                .unwrap();
+
+        assert_eq!(address, "TMSritvdndPwxnL3pUjTWMLGozq7XNFavE");
+    }
+
+    #[test]
+    fn secret_key_from_entropy_rejects_short_input() {
+        let err = secret_key_from_entropy(&[0x5au8; 31]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Entropy must be at least 32 bytes (256 bits), got 31"
+        );
+    }
+
+    #[test]
+    fn contract_addresses_hash_the_documented_inputs() {
+        let deployer =
+            parse_address("TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t", TRON_ADDRESS_PREFIX).unwrap();
+        let tx_id = [0x11u8; 32];
+        let keccak_tail = |data: Vec<u8>| keccak256(&data)[12..].to_vec();
+
+        let mut preimage = tx_id.to_vec();
+        preimage.push(0x41);
+        preimage.extend_from_slice(&deployer);
+        assert_eq!(
+            deployment_address(&tx_id, &deployer, TRON_ADDRESS_PREFIX).to_vec(),
+            keccak_tail(preimage)
+        );
+
+        let mut preimage = tx_id.to_vec();
+        preimage.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 2]);
+        assert_eq!(create_address(&tx_id, 2).to_vec(), keccak_tail(preimage));
+
+        let salt = [0x22u8; 32];
+        let code_hash = keccak256(&[0x60, 0x80]);
+        let mut preimage = vec![0x41];
+        preimage.extend_from_slice(&deployer);
+        preimage.extend_from_slice(&salt);
+        preimage.extend_from_slice(&code_hash);
+        assert_eq!(
+            create2_address(&deployer, &salt, &code_hash, TRON_ADDRESS_PREFIX).to_vec(),
+            keccak_tail(preimage)
+        );
+        assert_ne!(
+            create2_address(&deployer, &[0x23; 32], &code_hash, TRON_ADDRESS_PREFIX),
+            create2_address(&deployer, &salt, &code_hash, TRON_ADDRESS_PREFIX)
+        );
+    }
+
+    #[test]
+    fn private_key_derives_known_address() {
+        let private_key = "c88c165be5e6d8c58eca95747f8811aa956fa6227c9e0276543bc30d49252d76";
+        assert_eq!(
+            private_key_to_tron_address(private_key, TRON_ADDRESS_PREFIX).unwrap(),
+            "TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b"
+        );
+    }
+
+    #[test]
+    fn errors_carry_their_kind() {
+        assert_eq!(
+            parse_private_key("0xzz").unwrap_err(),
+            TronError::InvalidKey(
+                "Invalid private key hex: Invalid character 'z' at position 0".into()
+            )
+        );
+        assert!(matches!(
+            parse_address("TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6u", TRON_ADDRESS_PREFIX),
+            Err(TronError::InvalidAddress(msg)) if msg == "Invalid checksum"
+        ));
+        assert!(matches!(
+            secret_key_from_entropy(&[0; 16]),
+            Err(TronError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn tron_address_round_trips_through_hex() {
+        let address = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t";
+        let hex = tron_address_to_hex(address, TRON_ADDRESS_PREFIX).unwrap();
+
+        assert_eq!(hex, "41a614f803b6fd780986a42c78ec9c7f77e6ded13c");
+        assert_eq!(
+            hex_to_tron_address(&hex, TRON_ADDRESS_PREFIX).unwrap(),
+            address
+        );
+        assert_eq!(
+            hex_to_tron_address(&format!("0x{}", hex), TRON_ADDRESS_PREFIX).unwrap(),
+            address
+        );
+
+        for bad in ["00", &hex[2..], &"ab".repeat(32), "41zz"] {
+            assert!(matches!(
+                hex_to_tron_address(bad, TRON_ADDRESS_PREFIX),
+                Err(TronError::InvalidAddress(_))
+            ));
+        }
+        assert_eq!(
+            hex_to_tron_address(&hex, 0xa0).unwrap_err().to_string(),
+            format!("Not a 21-byte 0xa0-prefixed TRON address: {}", hex)
+        );
+    }
+
+    #[test]
+    fn format_address_renders_every_format() {
+        let raw = parse_address("TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t", TRON_ADDRESS_PREFIX).unwrap();
+        let hex = "a614f803b6fd780986a42c78ec9c7f77e6ded13c";
+
+        assert_eq!(
+            format_address(&raw, AddressFormat::Base58, TRON_ADDRESS_PREFIX),
+            "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t"
+        );
+        assert_eq!(
+            format_address(&raw, AddressFormat::Hex41, TRON_ADDRESS_PREFIX),
+            format!("41{}", hex)
+        );
+        assert_eq!(
+            format_address(&raw, AddressFormat::HexRaw, TRON_ADDRESS_PREFIX),
+            hex
+        );
+        assert_eq!(
+            format_address(&raw, AddressFormat::Evm, TRON_ADDRESS_PREFIX),
+            format!("0x{}", hex)
+        );
+    }
+
+    #[test]
+    fn parse_address_accepts_every_format() {
+        let expected =
+            parse_address("TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t", TRON_ADDRESS_PREFIX).unwrap();
+        for input in [
+            "41a614f803b6fd780986a42c78ec9c7f77e6ded13c",
+            "0x41a614f803b6fd780986a42c78ec9c7f77e6ded13c",
+            "a614f803b6fd780986a42c78ec9c7f77e6ded13c",
+            "0xA614F803B6FD780986A42C78EC9C7F77E6DED13C",
+        ] {
+            assert_eq!(
+                parse_address(input, TRON_ADDRESS_PREFIX).unwrap(),
+                expected,
+                "{}",
+                input
+            );
+        }
+
+        assert!(parse_address(
+            "42a614f803b6fd780986a42c78ec9c7f77e6ded13c",
+            TRON_ADDRESS_PREFIX
+        )
+        .is_err());
+        assert!(parse_address("0xa614f803", TRON_ADDRESS_PREFIX).is_err());
+    }
+
+    #[test]
+    fn other_address_prefixes_encode_and_strip() {
+        // 0xa0 is the prefix java-tron's early testnets used
+        let raw = parse_address("TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t", TRON_ADDRESS_PREFIX).unwrap();
+        let encoded = base58_address(&raw, 0xa0);
+        assert!(encoded.starts_with('2'));
+        let decoded = bs58_check_decode(&encoded).unwrap();
+        assert_eq!(decoded[0], 0xa0);
+        assert_eq!(strip_address_prefix(&decoded, 0xa0), Some(raw));
+        assert_eq!(strip_address_prefix(&decoded, TRON_ADDRESS_PREFIX), None);
+        assert_eq!(parse_address(&encoded, 0xa0).unwrap(), raw);
+        assert!(parse_address(&encoded, TRON_ADDRESS_PREFIX).is_err());
+        assert_eq!(
+            format_address(&raw, AddressFormat::Hex41, 0xa0),
+            format!("a0{}", hex::encode(raw))
+        );
+        assert_eq!(
+            base58_address(&raw, TRON_ADDRESS_PREFIX),
+            "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t"
+        );
+    }
+}
@@ -0,0 +1,50 @@
+//! Comparing deployed runtime bytecode against compiler output.
+
+/// Splits off the CBOR metadata trailer solc appends to runtime code: the
+/// last two bytes hold the big-endian length of a CBOR map that precedes
+/// them. Returns `(code, metadata)`; `metadata` is empty if none is found.
+pub fn split_metadata(code: &[u8]) -> (&[u8], &[u8]) {
+    let Some(len_bytes) = code.len().checked_sub(2).map(|at| &code[at..]) else {
+        return (code, &[]);
+    };
+    let cbor_len = u16::from_be_bytes([len_bytes[0], len_bytes[1]]) as usize;
+    match code.len().checked_sub(cbor_len + 2) {
+        // A CBOR map starts with a major type 5 header (0xa0..=0xbf)
+        Some(start) if cbor_len > 0 && (0xa0..=0xbf).contains(&code[start]) => code.split_at(start),
+        _ => (code, &[]),
+    }
+}
+
+/// Zeroes `(start, length)` ranges, e.g. immutable variables that the
+/// constructor fills in at deploy time. Ranges past the end are clipped.
+pub fn mask_ranges(code: &[u8], ranges: &[(usize, usize)]) -> Vec<u8> {
+    let mut masked = code.to_vec();
+    for &(start, length) in ranges {
+        let end = start.saturating_add(length).min(masked.len());
+        if start < end {
+            masked[start..end].fill(0);
+        }
+    }
+    masked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_metadata_finds_cbor_trailer() {
+        let code = [0x60, 0x80, 0xa2, 0x64, 0x69, 0x00, 0x03];
+        assert_eq!(split_metadata(&code), (&code[..2], &code[2..]));
+
+        let no_metadata = [0x60, 0x80, 0x60, 0x40];
+        assert_eq!(split_metadata(&no_metadata), (&no_metadata[..], &[][..]));
+        assert_eq!(split_metadata(&[0x00]), (&[0x00][..], &[][..]));
+    }
+
+    #[test]
+    fn mask_ranges_zeroes_and_clips() {
+        let code = [1u8, 2, 3, 4, 5];
+        assert_eq!(mask_ranges(&code, &[(1, 2), (4, 10)]), vec![1, 0, 0, 4, 0]);
+    }
+}
@@ -0,0 +1,289 @@
+//! Signature-collection files: the owner signatures gathered for one
+//! transaction during offline approval, passed between signers and merged
+//! before broadcast.
+//!
+//! ```json
+//! {"version":1,"txId":"…","contract":"T…","chainId":"…",
+//!  "signatures":[{"signer":"T…","sig":"…"}]}
+//! ```
+//!
+//! Every signature must recover to its `signer` over `txId`, so an edited
+//! entry is caught on load, and files are only merged when they name the
+//! same transaction, contract and chain.
+
+use serde::{Deserialize, Serialize};
+
+use crate::address::{parse_address, raw_address_to_tron};
+use crate::error::{Result, ResultExt, TronError};
+use crate::signing::{recover_signer, V_OFFSET_ETHEREUM};
+
+/// The schema version written by [`SignatureCollection::new`]; files with
+/// any other version are rejected rather than guessed at.
+pub const COLLECTION_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct SignatureCollection {
+    pub version: u32,
+    pub tx_id: String,
+    /// The multisig (or other contract) the transaction calls, base58.
+    pub contract: String,
+    /// The genesis block ID of the network the transaction is for.
+    pub chain_id: String,
+    pub signatures: Vec<CollectedSignature>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CollectedSignature {
+    /// Base58 address of the owner who signed.
+    pub signer: String,
+    /// Hex `r || s || v` signature over `txId`.
+    pub sig: String,
+}
+
+impl SignatureCollection {
+    /// An empty collection for `tx_id` on `contract` (any address format).
+    /// `prefix` is the chain's address prefix byte, as for every method that
+    /// reads or writes addresses.
+    pub fn new(tx_id: &str, contract: &str, chain_id: &str, prefix: u8) -> Result<Self> {
+        let collection = SignatureCollection {
+            version: COLLECTION_VERSION,
+            tx_id: tx_id.to_lowercase(),
+            contract: raw_address_to_tron(&parse_address(contract, prefix)?, prefix),
+            chain_id: chain_id.to_lowercase(),
+            signatures: Vec::new(),
+        };
+        collection.validate(prefix)?;
+        Ok(collection)
+    }
+
+    /// Parses and validates a collection file. The version is checked before
+    /// the rest of the schema so a newer file gets a clear error.
+    pub fn from_json(json: &str, prefix: u8) -> Result<Self> {
+        let value: serde_json::Value = serde_json::from_str(json)
+            .or_fail(TronError::Decode, "Signature collection is not valid JSON")?;
+        match value.get("version").and_then(|v| v.as_u64()) {
+            Some(version) if version == u64::from(COLLECTION_VERSION) => {}
+            Some(version) => {
+                return Err(TronError::InvalidInput(format!(
+                    "Unsupported signature collection version {} (expected {})",
+                    version, COLLECTION_VERSION
+                )))
+            }
+            None => {
+                return Err(TronError::InvalidInput(
+                    "Signature collection has no version".into(),
+                ))
+            }
+        }
+        let collection: SignatureCollection = serde_json::from_value(value)
+            .or_fail(TronError::Decode, "Invalid signature collection")?;
+        collection.validate(prefix)?;
+        Ok(collection)
+    }
+
+    /// Checks the txId and contract are well-formed, every signature recovers
+    /// to its signer, and no signer appears twice.
+    pub fn validate(&self, prefix: u8) -> Result<()> {
+        if self.version != COLLECTION_VERSION {
+            return Err(TronError::InvalidInput(format!(
+                "Unsupported signature collection version {} (expected {})",
+                self.version, COLLECTION_VERSION
+            )));
+        }
+        if hex::decode(&self.tx_id).map(|b| b.len()) != Ok(32) {
+            return Err(TronError::InvalidInput(format!(
+                "txId must be 32 bytes of hex, got {}",
+                self.tx_id
+            )));
+        }
+        parse_address(&self.contract, prefix)
+            .map_err(|e| e.context("Invalid contract in signature collection"))?;
+        if self.chain_id.is_empty() {
+            return Err(TronError::InvalidInput(
+                "Signature collection has an empty chainId".into(),
+            ));
+        }
+        for (i, entry) in self.signatures.iter().enumerate() {
+            let signer = parse_address(&entry.signer, prefix)
+                .map_err(|e| e.context(format!("Invalid signer in signature {}", i + 1)))?;
+            let recovered = recover_signer(&self.tx_id, &entry.sig, prefix)
+                .map_err(|e| e.context(format!("Invalid signature {}", i + 1)))?;
+            if parse_address(&recovered, prefix)? != signer {
+                return Err(TronError::InvalidSignature(format!(
+                    "Signature {} is by {}, not the listed signer {}",
+                    i + 1,
+                    recovered,
+                    entry.signer
+                )));
+            }
+            if self.signatures[..i]
+                .iter()
+                .any(|earlier| parse_address(&earlier.signer, prefix).ok() == Some(signer))
+            {
+                return Err(TronError::InvalidInput(format!(
+                    "{} appears more than once",
+                    entry.signer
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds `sig`, which must recover to a signer over this collection's
+    /// txId, stored with the 0/1 recovery byte nodes expect. Re-adding an
+    /// identical signature is a no-op. Returns the signer.
+    pub fn add(&mut self, sig: &str, prefix: u8) -> Result<String> {
+        let signer = recover_signer(&self.tx_id, sig, prefix)?;
+        let mut bytes = hex::decode(sig.trim_start_matches("0x"))
+            .or_fail(TronError::InvalidSignature, "Invalid signature hex")?;
+        if bytes[64] >= V_OFFSET_ETHEREUM {
+            bytes[64] -= V_OFFSET_ETHEREUM;
+        }
+        let sig = hex::encode(bytes);
+        match self.signatures.iter().find(|s| s.signer == signer) {
+            Some(existing) if existing.sig == sig => {}
+            // ECDSA signatures aren't unique, but a signer with two in
+            // circulation for one transaction is worth a look
+            Some(_) => {
+                return Err(TronError::InvalidInput(format!(
+                    "{} already has a different signature in the collection",
+                    signer
+                )))
+            }
+            None => self.signatures.push(CollectedSignature {
+                signer: signer.clone(),
+                sig,
+            }),
+        }
+        Ok(signer)
+    }
+
+    /// Adds the signatures of `other`, which must be for the same txId,
+    /// contract and chain. Returns how many signatures were new.
+    pub fn merge(&mut self, other: &SignatureCollection, prefix: u8) -> Result<usize> {
+        if !other.tx_id.eq_ignore_ascii_case(&self.tx_id) {
+            return Err(TronError::InvalidInput(format!(
+                "Cannot merge signatures for transaction {} into {}",
+                other.tx_id, self.tx_id
+            )));
+        }
+        if parse_address(&other.contract, prefix)? != parse_address(&self.contract, prefix)? {
+            return Err(TronError::InvalidInput(format!(
+                "Cannot merge signatures for contract {} into {}",
+                other.contract, self.contract
+            )));
+        }
+        if !other.chain_id.eq_ignore_ascii_case(&self.chain_id) {
+            return Err(TronError::InvalidInput(format!(
+                "Cannot merge signatures for chain {} into {}",
+                other.chain_id, self.chain_id
+            )));
+        }
+        let before = self.signatures.len();
+        for entry in &other.signatures {
+            self.add(&entry.sig, prefix)?;
+        }
+        Ok(self.signatures.len() - before)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::TRON_ADDRESS_PREFIX;
+    use crate::hash::sha256;
+    use crate::signing::sign_transaction;
+
+    const KEY_A: &str = "c88c165be5e6d8c58eca95747f8811aa956fa6227c9e0276543bc30d49252d76";
+    const KEY_B: &str = "0000000000000000000000000000000000000000000000000000000000000001";
+    const CONTRACT: &str = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t";
+    const CHAIN: &str = "00000000000000001ebf88508a03865c71d452e25f4d51194196a1d22b6653dc";
+
+    fn collection(tx: &[u8]) -> SignatureCollection {
+        let tx_id = hex::encode(sha256(tx));
+        SignatureCollection::new(&tx_id, CONTRACT, CHAIN, TRON_ADDRESS_PREFIX).unwrap()
+    }
+
+    #[test]
+    fn collection_round_trips_and_rejects_tampering() {
+        let mut signed = collection(b"payout");
+        let signer = signed
+            .add(
+                &sign_transaction(&signed.tx_id, KEY_A).unwrap(),
+                TRON_ADDRESS_PREFIX,
+            )
+            .unwrap();
+        assert_eq!(signer, "TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b");
+        signed
+            .add(
+                &sign_transaction(&signed.tx_id, KEY_B).unwrap(),
+                TRON_ADDRESS_PREFIX,
+            )
+            .unwrap();
+
+        let json = serde_json::to_string(&signed).unwrap();
+        assert_eq!(
+            SignatureCollection::from_json(&json, TRON_ADDRESS_PREFIX).unwrap(),
+            signed
+        );
+
+        let mut relabelled = signed.clone();
+        relabelled.signatures[1].signer = signer.clone();
+        let err = SignatureCollection::from_json(
+            &serde_json::to_string(&relabelled).unwrap(),
+            TRON_ADDRESS_PREFIX,
+        );
+        assert!(err
+            .unwrap_err()
+            .to_string()
+            .contains("not the listed signer"));
+
+        let future = json.replace("\"version\":1", "\"version\":2");
+        assert_eq!(
+            SignatureCollection::from_json(&future, TRON_ADDRESS_PREFIX)
+                .unwrap_err()
+                .to_string(),
+            "Unsupported signature collection version 2 (expected 1)"
+        );
+    }
+
+    #[test]
+    fn merge_rejects_other_transactions_contracts_and_chains() {
+        let mut mine = collection(b"payout");
+        mine.add(
+            &sign_transaction(&mine.tx_id, KEY_A).unwrap(),
+            TRON_ADDRESS_PREFIX,
+        )
+        .unwrap();
+        let mut theirs = collection(b"payout");
+        theirs
+            .add(
+                &sign_transaction(&theirs.tx_id, KEY_B).unwrap(),
+                TRON_ADDRESS_PREFIX,
+            )
+            .unwrap();
+        theirs
+            .add(
+                &sign_transaction(&theirs.tx_id, KEY_A).unwrap(),
+                TRON_ADDRESS_PREFIX,
+            )
+            .unwrap();
+
+        let mut other_contract = theirs.clone();
+        other_contract.contract = "TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b".to_string();
+        assert!(mine.merge(&other_contract, TRON_ADDRESS_PREFIX).is_err());
+        let mut other_chain = theirs.clone();
+        other_chain.chain_id =
+            "0000000000000000d698d4192c56cb6be724a558448e2684802de4d6cd8690dc".to_string();
+        assert!(mine.merge(&other_chain, TRON_ADDRESS_PREFIX).is_err());
+        assert!(mine
+            .merge(&collection(b"another payout"), TRON_ADDRESS_PREFIX)
+            .is_err());
+        assert_eq!(mine.signatures.len(), 1);
+
+        assert_eq!(mine.merge(&theirs, TRON_ADDRESS_PREFIX).unwrap(), 1);
+        assert_eq!(mine.signatures.len(), 2);
+    }
+}
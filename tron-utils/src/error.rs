@@ -0,0 +1,79 @@
+//! The library's error type.
+
+/// Why a library call failed. Each variant carries the message the CLI
+/// prints; context added on the way up is prefixed to it (`context: cause`),
+/// so the message reads the same as an `anyhow` chain printed with `{:#}`.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum TronError {
+    /// An address that isn't valid base58check or hex, or is the wrong length
+    #[error("{0}")]
+    InvalidAddress(String),
+    /// A private key (or key material) that isn't a valid secp256k1 key
+    #[error("{0}")]
+    InvalidKey(String),
+    /// A signature that is malformed or doesn't recover to the expected key
+    #[error("{0}")]
+    InvalidSignature(String),
+    /// ABI data, protobuf `raw_data` or JSON that doesn't decode
+    #[error("{0}")]
+    Decode(String),
+    /// Other input the caller can fix, such as an out-of-range parameter
+    #[error("{0}")]
+    InvalidInput(String),
+    /// A node that couldn't be reached, refused the request or answered
+    /// with something other than the expected JSON
+    #[error("{0}")]
+    Rpc(String),
+    /// A signed transaction the node rejected on broadcast
+    #[error("{0}")]
+    Broadcast(String),
+    /// A contract call that reverted, with the decoded reason where there is one
+    #[error("{0}")]
+    Revert(String),
+}
+
+pub type Result<T, E = TronError> = std::result::Result<T, E>;
+
+impl TronError {
+    /// The message, without the kind.
+    pub fn message(&self) -> &str {
+        match self {
+            TronError::InvalidAddress(msg)
+            | TronError::InvalidKey(msg)
+            | TronError::InvalidSignature(msg)
+            | TronError::Decode(msg)
+            | TronError::InvalidInput(msg)
+            | TronError::Rpc(msg)
+            | TronError::Broadcast(msg)
+            | TronError::Revert(msg) => msg,
+        }
+    }
+
+    /// The same kind of error, with `context` prefixed to the message.
+    pub fn context(self, context: impl std::fmt::Display) -> Self {
+        let wrap = |msg: String| format!("{}: {}", context, msg);
+        match self {
+            TronError::InvalidAddress(msg) => TronError::InvalidAddress(wrap(msg)),
+            TronError::InvalidKey(msg) => TronError::InvalidKey(wrap(msg)),
+            TronError::InvalidSignature(msg) => TronError::InvalidSignature(wrap(msg)),
+            TronError::Decode(msg) => TronError::Decode(wrap(msg)),
+            TronError::InvalidInput(msg) => TronError::InvalidInput(wrap(msg)),
+            TronError::Rpc(msg) => TronError::Rpc(wrap(msg)),
+            TronError::Broadcast(msg) => TronError::Broadcast(wrap(msg)),
+            TronError::Revert(msg) => TronError::Revert(wrap(msg)),
+        }
+    }
+}
+
+/// Turns a foreign error into a [`TronError`] of the given kind, as
+/// `message: cause`.
+pub(crate) trait ResultExt<T> {
+    fn or_fail(self, kind: fn(String) -> TronError, message: &str) -> Result<T>;
+}
+
+impl<T, E: std::fmt::Display> ResultExt<T> for std::result::Result<T, E> {
+    fn or_fail(self, kind: fn(String) -> TronError, message: &str) -> Result<T> {
+        self.map_err(|err| kind(format!("{}: {}", message, err)))
+    }
+}
@@ -0,0 +1,62 @@
+//! The hash functions behind txIDs, Base58Check, addresses and selectors,
+//! kept here so each can be checked against known vectors on its own.
+
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+
+/// SHA-256: transaction IDs, key derivation from entropy, and (twice)
+/// Base58Check checksums.
+pub fn sha256(data: &[u8]) -> Vec<u8> {
+    Sha256::digest(data).to_vec()
+}
+
+/// SHA-256 of the SHA-256 of `data`.
+pub fn double_sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(Sha256::digest(data)).into()
+}
+
+/// The Base58Check checksum appended to `payload`: the first four bytes of
+/// its double SHA-256.
+pub fn base58_checksum(payload: &[u8]) -> [u8; 4] {
+    let hash = double_sha256(payload);
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// Keccak-256, for addresses, contract addresses, selectors and event topics.
+/// This is the original Keccak submission that TRON and Ethereum use, not
+/// NIST SHA3-256 (`sha3::Sha3_256`): that pads differently, and swapping it
+/// in would derive valid-looking but wrong addresses.
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keccak256_is_legacy_keccak_not_sha3_256() {
+        // Digests of the empty string under each padding rule
+        let keccak = "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470";
+        let sha3 = "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a";
+        assert_eq!(hex::encode(keccak256(b"")), keccak);
+        assert_eq!(hex::encode(sha3::Sha3_256::digest(b"")), sha3);
+    }
+
+    #[test]
+    fn base58_checksum_is_the_start_of_double_sha256() {
+        // The payload of TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t
+        let payload = hex::decode("41a614f803b6fd780986a42c78ec9c7f77e6ded13c").unwrap();
+        assert_eq!(
+            hex::encode(sha256(&payload)),
+            "3a42512dd4f64e4d9dad3d5e6aa0ecf55adbd8a85979135cf211ba347278d029"
+        );
+        assert_eq!(double_sha256(&payload)[..], sha256(&sha256(&payload))[..]);
+        assert_eq!(hex::encode(base58_checksum(&payload)), "710277f5");
+
+        let encoded = bs58::decode("TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t")
+            .into_vec()
+            .unwrap();
+        assert_eq!(encoded[21..], base58_checksum(&payload));
+    }
+}
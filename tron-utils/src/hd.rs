@@ -0,0 +1,227 @@
+//! BIP39 seeds and BIP32 key derivation, for signing with a key held as a
+//! mnemonic phrase (the form wallets such as TronLink export).
+//!
+//! Mnemonics are checked against the English wordlist and their checksum by
+//! the `bip39` crate, so a mistyped or swapped word is an error rather than
+//! a different, valid key.
+
+use bip39::{Language, Mnemonic};
+use hmac::{Hmac, Mac};
+use secp256k1::{PublicKey, Scalar, Secp256k1, SecretKey};
+use sha2::Sha512;
+
+use crate::error::{Result, ResultExt, TronError};
+
+/// SLIP-44 coin type of TRON.
+pub const TRON_COIN_TYPE: u32 = 195;
+
+/// Child indices at or above this are hardened (written `n'`).
+pub const HARDENED: u32 = 0x8000_0000;
+
+/// The path TronLink and most TRON wallets use for their first account.
+pub const DEFAULT_TRON_PATH: &str = "m/44'/195'/0'/0/0";
+
+/// Word counts BIP39 defines (128 to 256 bits of entropy).
+const WORD_COUNTS: [usize; 5] = [12, 15, 18, 21, 24];
+
+/// A BIP32 derivation path such as `m/44'/195'/0'/0/3`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    /// Parses `m/`-rooted paths; `'`, `h` or `H` mark hardened indices.
+    pub fn parse(path: &str) -> Result<Self> {
+        let mut parts = path.trim().split('/');
+        if parts.next() != Some("m") {
+            return Err(TronError::InvalidInput(format!(
+                "Derivation path must start with m/: {}",
+                path
+            )));
+        }
+        let indices = parts
+            .map(|part| {
+                let (digits, hardened) = match part.strip_suffix(['\'', 'h', 'H']) {
+                    Some(digits) => (digits, true),
+                    None => (part, false),
+                };
+                let index: u32 = digits
+                    .parse()
+                    .ok()
+                    .filter(|index| *index < HARDENED)
+                    .ok_or_else(|| {
+                        TronError::InvalidInput(format!(
+                            "Invalid derivation path index: {:?}",
+                            part
+                        ))
+                    })?;
+                Ok(if hardened { index | HARDENED } else { index })
+            })
+            .collect::<Result<_>>()?;
+        Ok(DerivationPath(indices))
+    }
+
+    /// `m/44'/195'/account'/0/index`, the TRON wallet layout.
+    pub fn tron(account: u32, index: u32) -> Result<Self> {
+        DerivationPath::parse(&format!(
+            "m/44'/{}'/{}'/0/{}",
+            TRON_COIN_TYPE, account, index
+        ))
+    }
+
+    pub fn indices(&self) -> &[u32] {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("m")?;
+        for index in &self.0 {
+            match index.checked_sub(HARDENED) {
+                Some(hardened) => write!(f, "/{}'", hardened)?,
+                None => write!(f, "/{}", index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The 64-byte BIP39 seed of `mnemonic` and `passphrase`. Words may be
+/// separated by any whitespace; they must all be in the English wordlist and
+/// carry a valid checksum.
+pub fn mnemonic_to_seed(mnemonic: &str, passphrase: &str) -> Result<[u8; 64]> {
+    let words: Vec<&str> = mnemonic.split_whitespace().collect();
+    if !WORD_COUNTS.contains(&words.len()) {
+        return Err(TronError::InvalidInput(format!(
+            "A mnemonic has 12, 15, 18, 21 or 24 words, got {}",
+            words.len()
+        )));
+    }
+    let mnemonic = Mnemonic::parse_in(Language::English, words.join(" ")).map_err(|err| {
+        TronError::InvalidInput(match err {
+            bip39::Error::UnknownWord(index) => format!(
+                "Mnemonic word {} ({:?}) is not in the BIP39 English wordlist",
+                index + 1,
+                words[index]
+            ),
+            bip39::Error::InvalidChecksum => {
+                "Mnemonic checksum doesn't match: a word is mistyped or out of order".into()
+            }
+            other => format!("Invalid mnemonic: {}", other),
+        })
+    })?;
+    Ok(mnemonic.to_seed(passphrase))
+}
+
+/// The private key at `path` below the BIP32 master key of `seed`.
+pub fn derive_private_key(seed: &[u8], path: &DerivationPath) -> Result<SecretKey> {
+    let secp = Secp256k1::new();
+    let master = hmac_sha512(b"Bitcoin seed", seed);
+    let mut key = SecretKey::from_slice(&master[..32])
+        .or_fail(TronError::InvalidKey, "Invalid master key")?;
+    let mut chain_code: [u8; 32] = master[32..].try_into().expect("64-byte HMAC");
+
+    for &index in path.indices() {
+        let mut data = Vec::with_capacity(37);
+        if index >= HARDENED {
+            data.push(0);
+            data.extend_from_slice(&key.secret_bytes());
+        } else {
+            data.extend_from_slice(&PublicKey::from_secret_key(&secp, &key).serialize());
+        }
+        data.extend_from_slice(&index.to_be_bytes());
+
+        let child = hmac_sha512(&chain_code, &data);
+        let tweak: [u8; 32] = child[..32].try_into().expect("64-byte HMAC");
+        // Out-of-range tweaks (probability ~2^-127) mean skipping to the
+        // next index per BIP32; reported instead, as no wallet does that
+        key = Scalar::from_be_bytes(tweak)
+            .ok()
+            .and_then(|tweak| key.add_tweak(&tweak).ok())
+            .ok_or_else(|| {
+                TronError::InvalidKey(format!("Index {} derives no valid key", index))
+            })?;
+        chain_code.copy_from_slice(&child[32..]);
+    }
+    Ok(key)
+}
+
+/// HMAC-SHA512 (RFC 2104).
+fn hmac_sha512(key: &[u8], message: &[u8]) -> [u8; 64] {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABANDON: &str = "abandon abandon abandon abandon abandon abandon \
+                           abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn hmac_sha512_matches_rfc_4231() {
+        // Test case 2
+        assert_eq!(
+            hex::encode(hmac_sha512(b"Jefe", b"what do ya want for nothing?")),
+            "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554\
+             9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737"
+        );
+    }
+
+    #[test]
+    fn mnemonic_seed_matches_bip39_vector() {
+        assert_eq!(
+            hex::encode(mnemonic_to_seed(ABANDON, "TREZOR").unwrap()),
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f\
+             09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+        );
+        assert!(mnemonic_to_seed("abandon about", "").is_err());
+        assert!(mnemonic_to_seed(&ABANDON.replace("about", "About"), "").is_err());
+
+        let mistyped = mnemonic_to_seed(&ABANDON.replacen("abandon", "abandn", 1), "");
+        assert_eq!(
+            mistyped.unwrap_err().to_string(),
+            "Mnemonic word 1 (\"abandn\") is not in the BIP39 English wordlist"
+        );
+        // Valid words, wrong checksum word
+        let swapped = mnemonic_to_seed(&ABANDON.replace("about", "abandon"), "");
+        assert!(swapped.unwrap_err().to_string().contains("checksum"));
+    }
+
+    #[test]
+    fn derivation_matches_bip32_vector_1() {
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let key = |path: &str| {
+            let path = DerivationPath::parse(path).unwrap();
+            hex::encode(derive_private_key(&seed, &path).unwrap().secret_bytes())
+        };
+        assert_eq!(
+            key("m"),
+            "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35"
+        );
+        assert_eq!(
+            key("m/0'"),
+            "edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea"
+        );
+        assert_eq!(
+            key("m/0H/1"),
+            "3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368"
+        );
+    }
+
+    #[test]
+    fn derivation_paths_parse_and_print() {
+        let path = DerivationPath::tron(0, 3).unwrap();
+        assert_eq!(path.to_string(), "m/44'/195'/0'/0/3");
+        assert_eq!(DerivationPath::parse("m/44h/195H/0'/0/3").unwrap(), path);
+        assert_eq!(
+            DerivationPath::parse(DEFAULT_TRON_PATH).unwrap(),
+            DerivationPath::tron(0, 0).unwrap()
+        );
+        for bad in ["44'/195'", "m/x", "m/2147483648", "m//0"] {
+            assert!(DerivationPath::parse(bad).is_err(), "accepted {}", bad);
+        }
+    }
+}
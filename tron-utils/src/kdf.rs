@@ -0,0 +1,205 @@
+//! Argon2id (RFC 9106) and scrypt (RFC 7914), for deriving keys from
+//! passphrases.
+//!
+//! Thin wrappers over the RustCrypto `argon2`, `scrypt` and `pbkdf2` crates,
+//! which build for WebAssembly too, checking parameters up front for clearer
+//! errors. The tests pin each against its RFC vectors. Derived keys are
+//! returned in [`Zeroizing`] buffers, wiped when dropped.
+
+use argon2::{Algorithm, Argon2, AssociatedData, ParamsBuilder, Version};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use crate::error::{Result, ResultExt, TronError};
+
+/// The smallest salt Argon2 accepts.
+pub const MIN_SALT_BYTES: usize = 8;
+
+/// Argon2id cost parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Argon2Params {
+    /// Memory in KiB (the RFC's `m`).
+    pub memory_kib: u32,
+    /// Passes over memory (`t`).
+    pub iterations: u32,
+    /// Lanes (`p`).
+    pub parallelism: u32,
+}
+
+/// The `tag_len`-byte Argon2id tag of `password` under `salt`, wiped from
+/// memory when dropped.
+pub fn argon2id(
+    password: &[u8],
+    salt: &[u8],
+    params: Argon2Params,
+    tag_len: usize,
+) -> Result<Zeroizing<Vec<u8>>> {
+    argon2id_keyed(password, salt, &[], &[], params, tag_len)
+}
+
+/// Argon2id with the optional secret `K` and associated data `X`.
+fn argon2id_keyed(
+    password: &[u8],
+    salt: &[u8],
+    secret: &[u8],
+    associated: &[u8],
+    params: Argon2Params,
+    tag_len: usize,
+) -> Result<Zeroizing<Vec<u8>>> {
+    let Argon2Params {
+        memory_kib,
+        iterations,
+        parallelism,
+    } = params;
+    if salt.len() < MIN_SALT_BYTES {
+        return Err(TronError::InvalidInput(format!(
+            "Argon2 salt must be at least {} bytes, got {}",
+            MIN_SALT_BYTES,
+            salt.len()
+        )));
+    }
+    if iterations == 0 || parallelism == 0 || tag_len < 4 {
+        return Err(TronError::InvalidInput(
+            "Argon2 needs at least one pass, one lane and a 4-byte tag".into(),
+        ));
+    }
+    if u64::from(memory_kib) < 8 * u64::from(parallelism) {
+        return Err(TronError::InvalidInput(format!(
+            "Argon2 memory must be at least {} KiB for {} lanes",
+            8 * parallelism,
+            parallelism
+        )));
+    }
+
+    let argon2_params = ParamsBuilder::new()
+        .m_cost(memory_kib)
+        .t_cost(iterations)
+        .p_cost(parallelism)
+        .output_len(tag_len)
+        .data(AssociatedData::new(associated).or_fail(TronError::InvalidInput, "Argon2 data")?)
+        .build()
+        .or_fail(TronError::InvalidInput, "Invalid Argon2 parameters")?;
+    let argon2 =
+        Argon2::new_with_secret(secret, Algorithm::Argon2id, Version::V0x13, argon2_params)
+            .or_fail(TronError::InvalidInput, "Invalid Argon2 secret")?;
+    let mut tag = Zeroizing::new(vec![0u8; tag_len]);
+    argon2
+        .hash_password_into(password, salt, &mut tag)
+        .or_fail(TronError::InvalidInput, "Argon2 failed")?;
+    Ok(tag)
+}
+
+/// scrypt cost parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScryptParams {
+    /// CPU/memory cost, a power of two above 1 (`N`).
+    pub n: u64,
+    /// Block size (`r`); memory is `128 * r * n` bytes.
+    pub r: u32,
+    /// Parallelization (`p`).
+    pub p: u32,
+}
+
+/// Memory scrypt may use, so a hostile keystore can't ask for terabytes.
+pub const MAX_SCRYPT_MEMORY_BYTES: u64 = 2 << 30;
+
+/// The `dk_len`-byte scrypt key of `password` under `salt`, wiped from
+/// memory when dropped.
+pub fn scrypt(
+    password: &[u8],
+    salt: &[u8],
+    params: ScryptParams,
+    dk_len: usize,
+) -> Result<Zeroizing<Vec<u8>>> {
+    let ScryptParams { n, r, p } = params;
+    if n < 2 || !n.is_power_of_two() {
+        return Err(TronError::InvalidInput(format!(
+            "scrypt N must be a power of two above 1, got {}",
+            n
+        )));
+    }
+    if r == 0 || p == 0 || u64::from(r) * u64::from(p) >= 1 << 30 {
+        return Err(TronError::InvalidInput(format!(
+            "Invalid scrypt parameters r={}, p={}",
+            r, p
+        )));
+    }
+    n.checked_mul(128 * u64::from(r))
+        .filter(|memory| *memory <= MAX_SCRYPT_MEMORY_BYTES)
+        .ok_or_else(|| {
+            TronError::InvalidInput(format!(
+                "scrypt N={}, r={} needs more than {} GiB of memory",
+                n,
+                r,
+                MAX_SCRYPT_MEMORY_BYTES >> 30
+            ))
+        })?;
+
+    let log_n = n.trailing_zeros() as u8;
+    let params = scrypt::Params::new(log_n, r, p, scrypt::Params::RECOMMENDED_LEN)
+        .or_fail(TronError::InvalidInput, "Invalid scrypt parameters")?;
+    let mut key = Zeroizing::new(vec![0u8; dk_len]);
+    scrypt::scrypt(password, salt, &params, &mut key)
+        .or_fail(TronError::InvalidInput, "Invalid scrypt key length")?;
+    Ok(key)
+}
+
+/// PBKDF2 (RFC 8018) with HMAC-SHA256.
+pub(crate) fn pbkdf2_hmac_sha256(
+    password: &[u8],
+    salt: &[u8],
+    rounds: u32,
+    dk_len: usize,
+) -> Zeroizing<Vec<u8>> {
+    let mut key = Zeroizing::new(vec![0u8; dk_len]);
+    pbkdf2::pbkdf2_hmac::<Sha256>(password, salt, rounds, &mut key);
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn argon2id_matches_rfc_9106() {
+        let params = Argon2Params {
+            memory_kib: 32,
+            iterations: 3,
+            parallelism: 4,
+        };
+        let tag = argon2id_keyed(&[1; 32], &[2; 16], &[3; 8], &[4; 12], params, 32).unwrap();
+        assert_eq!(
+            hex::encode(tag),
+            "0d640df58d78766c08c037a34a8b53c9d01ef0452d75b65eb52520e96b01e659"
+        );
+        assert!(argon2id(b"password", b"short", params, 32).is_err());
+    }
+
+    #[test]
+    fn scrypt_matches_rfc_7914() {
+        assert_eq!(
+            hex::encode(&*pbkdf2_hmac_sha256(b"passwd", b"salt", 1, 64)),
+            "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc\
+             49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783"
+        );
+        let params = ScryptParams { n: 16, r: 1, p: 1 };
+        assert_eq!(
+            hex::encode(&*scrypt(b"", b"", params, 64).unwrap()),
+            "77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442\
+             fcd0069ded0948f8326a753a0fc81f17e8d3e0fb2e0d3628cf35e20c38d18906"
+        );
+        let params = ScryptParams {
+            n: 1024,
+            r: 8,
+            p: 16,
+        };
+        assert_eq!(
+            hex::encode(&*scrypt(b"password", b"NaCl", params, 64).unwrap()),
+            "fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b373162\
+             2eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640"
+        );
+        for (n, r, p) in [(15, 1, 1), (1, 1, 1), (16, 0, 1), (1 << 40, 8, 1)] {
+            assert!(scrypt(b"", b"", ScryptParams { n, r, p }, 32).is_err());
+        }
+    }
+}
@@ -0,0 +1,441 @@
+//! Web3 Secret Storage (version 3) keystore files, the encrypted key format
+//! TronLink, geth and most wallets export.
+//!
+//! ```json
+//! {"version":3,"id":"…","address":"…",
+//!  "crypto":{"cipher":"aes-128-ctr","ciphertext":"…","cipherparams":{"iv":"…"},
+//!            "kdf":"scrypt","kdfparams":{"n":262144,"r":8,"p":1,"dklen":32,"salt":"…"},
+//!            "mac":"…"}}
+//! ```
+//!
+//! [`KeystoreInfo`] reads the plaintext metadata; the `address` field is
+//! optional and unauthenticated, so it says which key a file claims to hold,
+//! not which it does. [`encrypt_key`] writes new keystores and
+//! [`decrypt_key`] opens them. Decrypted key bytes and derived keys are
+//! wiped from memory once used.
+
+use aes::cipher::{KeyIvInit, StreamCipher};
+use aes::Aes128;
+use ctr::Ctr128BE;
+use secp256k1::{Secp256k1, SecretKey};
+use serde::Deserialize;
+use zeroize::Zeroizing;
+
+use crate::address::{parse_address, public_key_to_tron_address, raw_address_to_tron};
+use crate::error::{Result, ResultExt, TronError};
+use crate::hash::keccak256;
+use crate::kdf::{pbkdf2_hmac_sha256, scrypt, ScryptParams};
+
+/// The only keystore version in use.
+pub const KEYSTORE_VERSION: u32 = 3;
+
+/// The scrypt cost geth and TronLink use for new keystores (256 MiB).
+pub const STANDARD_SCRYPT: ScryptParams = ScryptParams {
+    n: 1 << 18,
+    r: 8,
+    p: 1,
+};
+
+/// The only cipher keystores use.
+const CIPHER: &str = "aes-128-ctr";
+
+/// Derived key lengths a keystore may ask for: the MAC and cipher keys take
+/// 32 bytes, and nothing writes more than 64.
+const DKLEN: std::ops::RangeInclusive<u32> = 32..=64;
+
+/// PBKDF2 rounds a keystore may ask for, so a hostile one can't make opening
+/// it spin for hours. Wallets use 262,144.
+pub const MAX_PBKDF2_ROUNDS: u32 = 10_000_000;
+
+/// How a keystore derives its encryption key from the passphrase.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeystoreKdf {
+    Scrypt {
+        n: u64,
+        r: u32,
+        p: u32,
+        dklen: u32,
+    },
+    Pbkdf2 {
+        c: u32,
+        prf: String,
+        dklen: u32,
+    },
+    /// A KDF this module doesn't know, by name
+    Other(String),
+}
+
+impl std::fmt::Display for KeystoreKdf {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            KeystoreKdf::Scrypt { n, r, p, .. } => write!(f, "scrypt (n={}, r={}, p={})", n, r, p),
+            KeystoreKdf::Pbkdf2 { c, prf, .. } => write!(f, "pbkdf2 ({}, c={})", prf, c),
+            KeystoreKdf::Other(name) => f.write_str(name),
+        }
+    }
+}
+
+/// The metadata of a keystore that can be read without the passphrase.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeystoreInfo {
+    pub id: Option<String>,
+    /// The stored address in base58, if the file has one
+    pub address: Option<String>,
+    pub cipher: String,
+    pub kdf: KeystoreKdf,
+}
+
+#[derive(Deserialize)]
+struct KeystoreFile {
+    version: u32,
+    id: Option<String>,
+    address: Option<String>,
+    // geth wrote `Crypto` before settling on `crypto`
+    #[serde(alias = "Crypto")]
+    crypto: CryptoSection,
+}
+
+#[derive(Deserialize)]
+struct CryptoSection {
+    cipher: String,
+    kdf: String,
+    #[serde(default)]
+    kdfparams: serde_json::Value,
+    #[serde(default)]
+    ciphertext: String,
+    #[serde(default)]
+    cipherparams: serde_json::Value,
+    #[serde(default)]
+    mac: String,
+}
+
+impl KeystoreInfo {
+    /// Reads the metadata of a keystore file's JSON. The stored address may
+    /// be base58 or hex (`prefix`-prefixed or, as Ethereum tools write it,
+    /// bare); it is reported as base58 with `prefix`.
+    pub fn from_json(json: &str, prefix: u8) -> Result<Self> {
+        let file: KeystoreFile =
+            serde_json::from_str(json).or_fail(TronError::Decode, "Invalid keystore JSON")?;
+        if file.version != KEYSTORE_VERSION {
+            return Err(TronError::InvalidInput(format!(
+                "Unsupported keystore version {} (expected {})",
+                file.version, KEYSTORE_VERSION
+            )));
+        }
+        let address = match file.address.as_deref().map(str::trim) {
+            None | Some("") => None,
+            Some(address) => Some(raw_address_to_tron(
+                &parse_address(address, prefix)
+                    .map_err(|e| e.context("Invalid address in keystore"))?,
+                prefix,
+            )),
+        };
+
+        let params = &file.crypto.kdfparams;
+        let number = |field: &str| {
+            params[field]
+                .as_u64()
+                .ok_or_else(|| TronError::Decode(format!("Keystore kdfparams has no {}", field)))
+        };
+        let small = |field: &str| {
+            number(field)?.try_into().map_err(|_| {
+                TronError::Decode(format!("Keystore kdfparams {} is out of range", field))
+            })
+        };
+        let dklen = || {
+            let dklen = small("dklen")?;
+            if !DKLEN.contains(&dklen) {
+                return Err(TronError::Decode(format!(
+                    "Keystore dklen must be {} to {}, got {}",
+                    DKLEN.start(),
+                    DKLEN.end(),
+                    dklen
+                )));
+            }
+            Ok(dklen)
+        };
+        let kdf = match file.crypto.kdf.as_str() {
+            "scrypt" => KeystoreKdf::Scrypt {
+                n: number("n")?,
+                r: small("r")?,
+                p: small("p")?,
+                dklen: dklen()?,
+            },
+            "pbkdf2" => KeystoreKdf::Pbkdf2 {
+                c: match small("c")? {
+                    c if c > MAX_PBKDF2_ROUNDS => {
+                        return Err(TronError::Decode(format!(
+                            "Keystore pbkdf2 c={} is above the {} round limit",
+                            c, MAX_PBKDF2_ROUNDS
+                        )))
+                    }
+                    c => c,
+                },
+                prf: params["prf"].as_str().unwrap_or("hmac-sha256").to_string(),
+                dklen: dklen()?,
+            },
+            other => KeystoreKdf::Other(other.to_string()),
+        };
+
+        Ok(KeystoreInfo {
+            id: file.id,
+            address,
+            cipher: file.crypto.cipher,
+            kdf,
+        })
+    }
+}
+
+/// The random inputs of a new keystore. The caller supplies them, as this
+/// crate has no random number generator.
+#[derive(Debug, Clone)]
+pub struct KeystoreNonces {
+    pub salt: [u8; 32],
+    pub iv: [u8; 16],
+    /// Made into a version 4 UUID for the `id` field
+    pub id: [u8; 16],
+}
+
+/// Encrypts `key` under `passphrase` as keystore JSON: scrypt with `params`,
+/// AES-128-CTR, and the key's base58 address with `prefix`.
+pub fn encrypt_key(
+    key: &SecretKey,
+    passphrase: &str,
+    params: ScryptParams,
+    nonces: &KeystoreNonces,
+    prefix: u8,
+) -> Result<serde_json::Value> {
+    let derived = scrypt(passphrase.as_bytes(), &nonces.salt, params, 32)?;
+    let mut ciphertext = Zeroizing::new(key.secret_bytes()).to_vec();
+    aes_128_ctr(
+        derived[..16].try_into().unwrap(),
+        &nonces.iv,
+        &mut ciphertext,
+    );
+    let mac = keccak256(&[&derived[16..32], &ciphertext[..]].concat());
+
+    let mut id = nonces.id;
+    id[6] = (id[6] & 0x0f) | 0x40;
+    id[8] = (id[8] & 0x3f) | 0x80;
+    let id = hex::encode(id);
+    let address = public_key_to_tron_address(&key.public_key(&Secp256k1::new()), prefix);
+    Ok(serde_json::json!({
+        "version": KEYSTORE_VERSION,
+        "id": format!("{}-{}-{}-{}-{}", &id[..8], &id[8..12], &id[12..16], &id[16..20], &id[20..]),
+        "address": address,
+        "crypto": {
+            "cipher": CIPHER,
+            "ciphertext": hex::encode(ciphertext),
+            "cipherparams": { "iv": hex::encode(nonces.iv) },
+            "kdf": "scrypt",
+            "kdfparams": {
+                "n": params.n,
+                "r": params.r,
+                "p": params.p,
+                "dklen": 32,
+                "salt": hex::encode(nonces.salt)
+            },
+            "mac": hex::encode(mac)
+        }
+    }))
+}
+
+/// Decrypts a keystore's private key. A wrong passphrase fails the MAC
+/// check; a stored address that isn't the key's (under `prefix`) is an
+/// error too.
+pub fn decrypt_key(json: &str, passphrase: &str, prefix: u8) -> Result<SecretKey> {
+    let info = KeystoreInfo::from_json(json, prefix)?;
+    let file: KeystoreFile =
+        serde_json::from_str(json).or_fail(TronError::Decode, "Invalid keystore JSON")?;
+    let crypto = &file.crypto;
+    if crypto.cipher != CIPHER {
+        return Err(TronError::InvalidInput(format!(
+            "Unsupported keystore cipher {} (expected {})",
+            crypto.cipher, CIPHER
+        )));
+    }
+    let bytes = |value: Option<&str>, field: &str| {
+        hex::decode(value.unwrap_or_default().trim_start_matches("0x"))
+            .or_fail(TronError::Decode, &format!("Invalid keystore {}", field))
+    };
+    let salt = bytes(crypto.kdfparams["salt"].as_str(), "salt")?;
+    let derived = match &info.kdf {
+        KeystoreKdf::Scrypt { n, r, p, dklen } => scrypt(
+            passphrase.as_bytes(),
+            &salt,
+            ScryptParams {
+                n: *n,
+                r: *r,
+                p: *p,
+            },
+            *dklen as usize,
+        )?,
+        KeystoreKdf::Pbkdf2 { c, prf, dklen } if prf == "hmac-sha256" => {
+            pbkdf2_hmac_sha256(passphrase.as_bytes(), &salt, *c, *dklen as usize)
+        }
+        kdf => {
+            return Err(TronError::InvalidInput(format!(
+                "Unsupported keystore KDF {}",
+                kdf
+            )))
+        }
+    };
+    let mut plaintext = Zeroizing::new(bytes(Some(&crypto.ciphertext), "ciphertext")?);
+    let mac = keccak256(&[&derived[16..32], &plaintext[..]].concat());
+    if bytes(Some(&crypto.mac), "mac")? != mac {
+        return Err(TronError::InvalidInput(
+            "Wrong keystore passphrase (MAC mismatch)".into(),
+        ));
+    }
+    let iv: [u8; 16] = bytes(crypto.cipherparams["iv"].as_str(), "iv")?
+        .try_into()
+        .map_err(|_| TronError::Decode("Keystore iv must be 16 bytes".into()))?;
+    aes_128_ctr(derived[..16].try_into().unwrap(), &iv, &mut plaintext);
+    let key = SecretKey::from_slice(&plaintext)
+        .or_fail(TronError::InvalidKey, "Keystore holds an invalid key")?;
+
+    let address = public_key_to_tron_address(&key.public_key(&Secp256k1::new()), prefix);
+    if let Some(stored) = info.address.filter(|stored| *stored != address) {
+        return Err(TronError::InvalidKey(format!(
+            "Keystore claims address {} but holds the key of {}",
+            stored, address
+        )));
+    }
+    Ok(key)
+}
+
+/// Encrypts (or, the same operation, decrypts) `data` in place with
+/// AES-128 in CTR mode, `iv` being the first big-endian counter block.
+fn aes_128_ctr(key: &[u8; 16], iv: &[u8; 16], data: &mut [u8]) {
+    let mut cipher = Ctr128BE::<Aes128>::new(key.into(), iv.into());
+    cipher.apply_keystream(data);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::TRON_ADDRESS_PREFIX;
+
+    #[test]
+    fn keystore_metadata_reads_without_a_passphrase() {
+        // The Web3 Secret Storage scrypt test vector, with its Ethereum address
+        let scrypt = r#"{
+            "crypto": {
+                "cipher": "aes-128-ctr",
+                "cipherparams": { "iv": "83dbcc02d8ccb40e466191a123791e0e" },
+                "ciphertext": "d172bf743a674da9cdad04534d56926ef8358534d458fffccd4e6ad2fbde479c",
+                "kdf": "scrypt",
+                "kdfparams": {
+                    "dklen": 32, "n": 262144, "p": 8, "r": 1,
+                    "salt": "ab0c7876052600dd703518d6fc3fe8984592145b591fc8fb5c6d43190334ba19"
+                },
+                "mac": "2103ac29920d71da29f15d75b4a16dbe95cfd7ff8faea1056c33131d846e3097"
+            },
+            "id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+            "address": "a614f803b6fd780986a42c78ec9c7f77e6ded13c",
+            "version": 3
+        }"#;
+        let info = KeystoreInfo::from_json(scrypt, TRON_ADDRESS_PREFIX).unwrap();
+        assert_eq!(
+            info.address.as_deref(),
+            Some("TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t")
+        );
+        assert_eq!(info.kdf.to_string(), "scrypt (n=262144, r=1, p=8)");
+        assert_eq!(info.cipher, "aes-128-ctr");
+
+        let pbkdf2 = r#"{
+            "Crypto": {
+                "cipher": "aes-128-ctr",
+                "kdf": "pbkdf2",
+                "kdfparams": { "c": 262144, "dklen": 32, "prf": "hmac-sha256", "salt": "ae3c" }
+            },
+            "version": 3
+        }"#;
+        let info = KeystoreInfo::from_json(pbkdf2, TRON_ADDRESS_PREFIX).unwrap();
+        assert_eq!(info.address, None);
+        assert_eq!(info.kdf.to_string(), "pbkdf2 (hmac-sha256, c=262144)");
+
+        let v1 = pbkdf2.replace("\"version\": 3", "\"version\": 1");
+        assert!(KeystoreInfo::from_json(&v1, TRON_ADDRESS_PREFIX).is_err());
+
+        // Hostile parameters are refused before any key is derived
+        let huge = pbkdf2.replace("\"dklen\": 32", "\"dklen\": 4294967295");
+        assert_eq!(
+            KeystoreInfo::from_json(&huge, TRON_ADDRESS_PREFIX)
+                .unwrap_err()
+                .to_string(),
+            "Keystore dklen must be 32 to 64, got 4294967295"
+        );
+        let short = scrypt.replace("\"dklen\": 32", "\"dklen\": 16");
+        assert!(matches!(
+            decrypt_key(&short, "testpassword", TRON_ADDRESS_PREFIX),
+            Err(TronError::Decode(_))
+        ));
+        let slow = pbkdf2.replace("\"c\": 262144", "\"c\": 4294967295");
+        assert_eq!(
+            KeystoreInfo::from_json(&slow, TRON_ADDRESS_PREFIX)
+                .unwrap_err()
+                .to_string(),
+            "Keystore pbkdf2 c=4294967295 is above the 10000000 round limit"
+        );
+    }
+
+    #[test]
+    fn aes_ctr_matches_sp_800_38a() {
+        // F.5.1 CTR-AES128.Encrypt, whose counter carries across all 128 bits
+        let key: [u8; 16] = hex::decode("2b7e151628aed2a6abf7158809cf4f3c")
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let iv: [u8; 16] = hex::decode("f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff")
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let mut data =
+            hex::decode("6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51")
+                .unwrap();
+        aes_128_ctr(&key, &iv, &mut data);
+        assert_eq!(
+            hex::encode(data),
+            "874d6191b620e3261bef6864990db6ce9806f66b7970fdff8617187bb9fffdff"
+        );
+    }
+
+    #[test]
+    fn keystores_round_trip_and_reject_wrong_passphrases() {
+        let key = SecretKey::from_slice(
+            &hex::decode("7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d")
+                .unwrap(),
+        )
+        .unwrap();
+        let nonces = KeystoreNonces {
+            salt: std::array::from_fn(|i| i as u8),
+            // The counter carries into the high half after the first block
+            iv: hex::decode("000102030405060708ffffffffffffff")
+                .unwrap()
+                .try_into()
+                .unwrap(),
+            id: [0xff; 16],
+        };
+        let params = ScryptParams { n: 16, r: 8, p: 1 };
+        let keystore =
+            encrypt_key(&key, "testpassword", params, &nonces, TRON_ADDRESS_PREFIX).unwrap();
+        assert_eq!(
+            keystore["crypto"]["ciphertext"],
+            "5d4d9220357c68969579359861479c4f4a06517e770dafad98f253c869dc7ee1"
+        );
+        assert_eq!(keystore["id"], "ffffffff-ffff-4fff-bfff-ffffffffffff");
+
+        let json = keystore.to_string();
+        let info = KeystoreInfo::from_json(&json, TRON_ADDRESS_PREFIX).unwrap();
+        assert_eq!(info.kdf.to_string(), "scrypt (n=16, r=8, p=1)");
+        assert_eq!(
+            decrypt_key(&json, "testpassword", TRON_ADDRESS_PREFIX).unwrap(),
+            key
+        );
+        assert!(matches!(
+            decrypt_key(&json, "wrong", TRON_ADDRESS_PREFIX),
+            Err(TronError::InvalidInput(_))
+        ));
+    }
+}
@@ -0,0 +1,20 @@
+//! Pure TRON helpers shared by the `tron-utils` CLI and the optional
+//! WebAssembly build: key and address handling, passphrase and mnemonic key
+//! derivation, keystore encryption, ABI encoding, transaction signing and
+//! editing, and bytecode comparison.
+//! Nothing here touches the network or the filesystem.
+//! Failures are [`error::TronError`]s, whose kind callers can match on.
+
+pub mod abi;
+pub mod address;
+pub mod bytecode;
+pub mod collection;
+pub mod error;
+pub mod hash;
+pub mod hd;
+pub mod kdf;
+pub mod keystore;
+pub mod signing;
+pub mod transaction;
+#[cfg(feature = "wasm")]
+pub mod wasm;
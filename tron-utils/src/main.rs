@@ -1,488 +1,10624 @@
 use anyhow::{anyhow, Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
 use rand::rngs::OsRng;
-use secp256k1::{Message, Secp256k1, SecretKey};
+use secp256k1::Secp256k1;
 use serde::{Deserialize, Serialize};
-use sha3::{Digest, Keccak256};
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::future::Future;
 use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tron_utils::abi::{
+    abi_value_to_json, abi_word, abi_word_to_tron_address, abi_word_to_u128, decode_address_array,
+    decode_constructor_params, decode_params, decode_revert_reason, encode_constructor_params,
+    encode_params, function_selector, get_contract_abi, parse_abi_arg, revert_error, AbiParam,
+    AbiType, AbiValue, ContractAbi,
+};
+use tron_utils::address::{
+    create2_address, create_address, deployment_address, format_address, parse_address,
+    private_key_to_tron_address, public_key_from_private_key, public_key_to_tron_address,
+    secret_key_from_entropy, tron_address_to_hex, validate_tron_address, AddressFormat,
+    TRON_ADDRESS_PREFIX,
+};
+use tron_utils::bytecode;
+use tron_utils::collection::SignatureCollection;
+use tron_utils::error::TronError;
+use tron_utils::hash::keccak256;
+use tron_utils::hd;
+use tron_utils::kdf::{argon2id, Argon2Params};
+use tron_utils::keystore::{
+    decrypt_key, encrypt_key, KeystoreInfo, KeystoreKdf, KeystoreNonces, STANDARD_SCRYPT,
+};
+use tron_utils::signing::{
+    legacy_message_digest, message_digest, recover_signer, sign_transaction_json,
+    sign_transaction_with_v_offset, verify_signature, V_OFFSET_ETHEREUM, V_OFFSET_TRON,
+};
+use tron_utils::transaction::{set_expiration, set_timestamp, transaction_id, MAX_EXPIRATION_MS};
+use zeroize::Zeroizing;
+
+/// Exit code for generic failures.
+const EXIT_FAILURE: u8 = 1;
+/// Exit code for failures caused by malformed user input (addresses, keys, parameters).
+const EXIT_INVALID_INPUT: u8 = 2;
+/// Exit code for failures talking to the TRON node.
+const EXIT_RPC: u8 = 3;
 
 #[derive(Parser)]
 #[command(name = "tron-utils")]
 #[command(about = "TRON contract deployment and interaction utilities")]
 struct Cli {
+    /// Output as JSON (errors are reported as `{"ok": false, ...}` on stdout)
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Like --json, but print the document on a single line, for log
+    /// ingestion and line-delimited JSON pipelines
+    #[arg(long, global = true, conflicts_with = "quiet")]
+    json_compact: bool,
+
+    /// Print only the result's identifier or value (e.g. the contract address
+    /// for deploy), for shell substitution; errors still go to stderr
+    #[arg(short, long, global = true, conflicts_with = "json")]
+    quiet: bool,
+
+    /// Increase verbosity (-v: progress, -vv: request summaries, -vvv: full request/response bodies)
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Emoji, banners and colors: `auto` uses them on a terminal unless NO_COLOR is set;
+    /// `never` prints plain ASCII
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto, global = true)]
+    color: ColorChoice,
+
+    /// Response layout of the node's transaction-building endpoints
+    #[arg(long, value_enum, default_value_t = ApiVersion::Auto, global = true)]
+    api_version: ApiVersion,
+
+    /// How contract calls are sent to `/wallet/trigger*contract`
+    #[arg(long, value_enum, default_value_t = CallEncoding::Split, global = true)]
+    call_encoding: CallEncoding,
+
+    /// Write every node request/response pair to this directory, for `--replay`
+    #[arg(long, value_name = "DIR", global = true, conflicts_with = "replay")]
+    record: Option<PathBuf>,
+
+    /// Answer node requests from pairs recorded with `--record` instead of the network
+    #[arg(long, value_name = "DIR", global = true)]
+    replay: Option<PathBuf>,
+
+    /// Send each broadcast up to this many times when the node can't be
+    /// reached or its reply is lost. Before a retry the txID is looked up,
+    /// so a transaction that already went through is not sent again
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..), global = true)]
+    attempts: u32,
+
+    /// Address prefix byte of the chain: 0x41 on every public TRON network;
+    /// private and forked TRON-compatible chains may use another
+    #[arg(long, value_name = "HEX", default_value = "0x41", value_parser = parse_address_prefix, global = true)]
+    address_prefix: u8,
+
+    /// Abort if a node response body exceeds this many bytes
+    #[arg(long, value_name = "BYTES", default_value_t = DEFAULT_MAX_BODY_SIZE, global = true)]
+    max_body_size: usize,
+
+    /// Explorer link template with `{txid}` and/or `{address}` placeholders, e.g.
+    /// https://explorer.example/tx/{txid} (default: TronScan for the RPC URL's network)
+    #[arg(long, value_name = "TEMPLATE", global = true)]
+    explorer_url: Option<String>,
+
+    /// TronGrid API key, sent as TRON-PRO-API-KEY for higher rate limits than
+    /// anonymous clients get (or env:NAME, to keep it off the command line)
+    #[arg(long, value_name = "KEY", value_parser = parse_env_arg::<String>, global = true)]
+    api_key: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
-#[derive(Subcommand)]
-enum Commands {
-    /// Generate a new private key and TRON address
-    GenerateKey {
-        /// Output as JSON
-        #[arg(long, default_value = "false")]
-        json: bool,
-    },
+#[derive(Subcommand)]
+enum Commands {
+    /// Generate a new private key and TRON address
+    #[command(group(clap::ArgGroup::new("key_file").args(["out_file", "keystore"])))]
+    GenerateKey {
+        /// Derive the key from this entropy (hex, at least 32 bytes) instead of
+        /// the OS random number generator. For test fixtures and advanced use only.
+        #[arg(long, value_name = "HEX")]
+        entropy: Option<String>,
+
+        /// Derive the key from a passphrase read from $KEY_PASSPHRASE, with
+        /// Argon2id (one lane) and --salt; the key is the SHA-256 of the 32-byte
+        /// tag, as with --entropy. Anyone who guesses the passphrase can
+        /// recreate the key: for recovering brain-wallet-style keys only.
+        #[arg(long, conflicts_with = "entropy", requires = "salt")]
+        from_password: bool,
+
+        /// Salt for --from-password (at least 16 bytes), e.g. an email address
+        /// and the wallet's purpose
+        #[arg(long, requires = "from_password")]
+        salt: Option<String>,
+
+        /// Argon2id memory for --from-password, in MiB
+        #[arg(long, value_name = "MIB", default_value_t = DEFAULT_ARGON2_MEMORY_MIB)]
+        argon2_memory: u32,
+
+        /// Argon2id passes over memory for --from-password
+        #[arg(long, default_value_t = DEFAULT_ARGON2_ITERATIONS)]
+        argon2_iterations: u32,
+
+        /// Write the key and address as JSON to this file (mode 0600 on Unix)
+        /// instead of printing the private key
+        #[arg(long = "out", value_name = "PATH")]
+        out_file: Option<PathBuf>,
+
+        /// Write the key to this file as an encrypted keystore (scrypt,
+        /// AES-128-CTR, mode 0600 on Unix) instead of printing it. The
+        /// passphrase is read from $KEYSTORE_PASSPHRASE or prompted for
+        #[arg(long, value_name = "PATH")]
+        keystore: Option<PathBuf>,
+
+        /// Overwrite --out or --keystore if it already exists
+        #[arg(long, requires = "key_file")]
+        force: bool,
+    },
+
+    /// Deploy the USDTMultisig contract
+    Deploy(DeployArgs),
+
+    /// Convert private key to TRON address
+    Address {
+        /// Private key (hex, with or without 0x prefix)
+        #[arg(long)]
+        private_key: String,
+
+        /// TRON RPC URL; when set, also shows the on-chain balance and activation status
+        #[arg(long)]
+        rpc_url: Option<String>,
+
+        /// Also print the address in this form
+        #[arg(long, value_enum, default_value_t = AddressFormat::Base58)]
+        format: AddressFormat,
+    },
+
+    /// Derive the TRON address of a BIP39 mnemonic's key, to check a seed
+    /// phrase before signing with it. Unknown words and a bad checksum are
+    /// rejected. A BIP39 passphrase, if the wallet uses one, is read from
+    /// MNEMONIC_PASSPHRASE
+    FromMnemonic {
+        /// BIP39 mnemonic (English words, quoted)
+        #[arg(long)]
+        mnemonic: String,
+
+        /// BIP32 path of the key
+        #[arg(
+            long,
+            default_value = hd::DEFAULT_TRON_PATH,
+            value_parser = hd::DerivationPath::parse
+        )]
+        path: hd::DerivationPath,
+
+        /// Account of the key, as m/44'/195'/ACCOUNT'/0/INDEX
+        #[arg(long, conflicts_with = "path")]
+        account: Option<u32>,
+
+        /// Address index of the key, as m/44'/195'/ACCOUNT'/0/INDEX
+        #[arg(long, conflicts_with = "path")]
+        index: Option<u32>,
+    },
+
+    /// Show which address keystore files claim to hold, and how they are
+    /// encrypted, without asking for their passphrases
+    KeystoreInspect {
+        /// Keystore (Web3 Secret Storage JSON) files
+        #[arg(long, required = true, num_args = 1..)]
+        keystore: Vec<PathBuf>,
+    },
+
+    /// Re-encrypt a keystore under a new passphrase, with a fresh scrypt salt.
+    /// The current passphrase is read from KEYSTORE_PASSPHRASE and the new one
+    /// from NEW_KEYSTORE_PASSPHRASE, or each is prompted for
+    KeystoreChangePassword {
+        /// Keystore (Web3 Secret Storage JSON) file, replaced in place
+        #[arg(long)]
+        keystore: PathBuf,
+    },
+
+    /// Export the secp256k1 public key for a private key
+    ExportPubkey {
+        /// Private key (hex, with or without 0x prefix)
+        #[arg(long)]
+        private_key: String,
+
+        /// Print the 33-byte compressed form (02/03 + x)
+        #[arg(long, conflicts_with = "uncompressed")]
+        compressed: bool,
+
+        /// Print the 65-byte uncompressed form (04 + x + y) [default]
+        #[arg(long)]
+        uncompressed: bool,
+    },
+
+    /// Verify that a transaction emitted the expected TRC20 Transfer event
+    VerifyTransfer {
+        /// TRON RPC URL (e.g., https://api.trongrid.io)
+        #[arg(long)]
+        rpc_url: String,
+
+        /// Transaction ID (hex)
+        #[arg(long)]
+        txid: String,
+
+        /// Expected recipient (TRON base58 address)
+        #[arg(long)]
+        expected_to: String,
+
+        /// Expected amount in token base units, or env:NAME (e.g., 1000000 = 1 USDT)
+        #[arg(long, required_unless_present = "expected_amount_tokens", value_parser = parse_env_arg::<u128>)]
+        expected_amount: Option<u128>,
+
+        /// Expected amount in whole tokens (e.g., 12.5), scaled by the token's decimals, or env:NAME
+        #[arg(long, conflicts_with = "expected_amount", value_parser = parse_env_arg::<String>)]
+        expected_amount_tokens: Option<String>,
+
+        /// Token decimals for --expected-amount-tokens (default: read from --usdt)
+        #[arg(long)]
+        decimals: Option<u32>,
+
+        /// Only accept events emitted by this token contract (TRON base58 address)
+        #[arg(long)]
+        usdt: Option<String>,
+    },
+
+    /// Show how a confirmed transaction ended (success, out of energy,
+    /// revert, ...) and the resources it used
+    Receipt {
+        /// TRON RPC URL (e.g., https://api.trongrid.io)
+        #[arg(long)]
+        rpc_url: String,
+
+        /// Transaction ID (hex)
+        #[arg(long)]
+        txid: String,
+    },
+
+    /// List the staked energy and bandwidth delegated to and by an account
+    /// (e.g. a deployer running on a shared energy pool), with lock expiries
+    Delegations {
+        /// TRON RPC URL (e.g., https://api.trongrid.io)
+        #[arg(long)]
+        rpc_url: String,
+
+        /// Account to look up (TRON base58 format)
+        #[arg(long)]
+        address: String,
+    },
+
+    /// Show the network's fee and limit parameters (`/wallet/getchainparameters`)
+    /// that deployment and call costs depend on
+    ChainParams {
+        /// TRON RPC URL (e.g., https://api.trongrid.io)
+        #[arg(long)]
+        rpc_url: String,
+    },
+
+    /// Show the current energy and bandwidth prices and their recent changes
+    /// (`/wallet/getenergyprices`, `/wallet/getbandwidthprices`)
+    ResourcePrices {
+        /// TRON RPC URL (e.g., https://api.trongrid.io)
+        #[arg(long)]
+        rpc_url: String,
+
+        /// How many of the latest price changes to list
+        #[arg(long, default_value_t = 5)]
+        recent: usize,
+    },
+
+    /// Call a read-only contract function and decode its return values
+    Call {
+        /// TRON RPC URL (e.g., https://api.trongrid.io)
+        #[arg(long)]
+        rpc_url: String,
+
+        /// Contract address (TRON base58 format)
+        #[arg(long)]
+        contract: String,
+
+        /// Function name or signature (e.g., getTransaction or getTransaction(uint256))
+        #[arg(long)]
+        function: String,
+
+        /// Function argument, in parameter order (repeat for each parameter;
+        /// arrays and tuples as JSON, e.g. '["T...","T..."]')
+        #[arg(long = "arg", value_name = "VALUE")]
+        args: Vec<String>,
+
+        /// Pre-encoded parameters (hex), sent as-is instead of encoding --arg
+        /// values. With a full --function signature the function need not be in the ABI.
+        #[arg(long, value_name = "HEX", conflicts_with = "args")]
+        parameter_hex: Option<String>,
+
+        /// ABI to use: a forge artifact or plain ABI JSON file (default: built-in USDTMultisig ABI)
+        #[arg(long, conflicts_with = "abi_from_chain")]
+        abi: Option<PathBuf>,
+
+        /// Fetch the ABI stored on-chain for --contract via /wallet/getcontract
+        #[arg(long)]
+        abi_from_chain: bool,
+    },
+
+    /// Decode contract calldata (selector + parameters) into readable values
+    DecodeInput {
+        /// Calldata (hex, with or without 0x prefix)
+        #[arg(long)]
+        data: String,
+
+        /// ABI to decode against: a forge artifact or plain ABI JSON file (default: built-in USDTMultisig ABI)
+        #[arg(long)]
+        abi: Option<PathBuf>,
+    },
+
+    /// Check that a deployed contract's runtime bytecode matches a forge artifact
+    VerifyBytecode {
+        /// TRON RPC URL (e.g., https://api.trongrid.io)
+        #[arg(long)]
+        rpc_url: String,
+
+        /// Contract address (TRON base58 format)
+        #[arg(long)]
+        contract: String,
+
+        /// Path to compiled contract JSON (from forge build)
+        #[arg(long, default_value = DEFAULT_CONTRACT_JSON)]
+        artifact: PathBuf,
+    },
+
+    /// List a multisig's owners (`getOwners()`), threshold and token
+    #[command(visible_alias = "get-owners")]
+    Owners {
+        /// TRON RPC URL (e.g., https://api.trongrid.io)
+        #[arg(long)]
+        rpc_url: String,
+
+        /// Multisig contract address (TRON base58 format)
+        #[arg(long)]
+        contract: String,
+    },
+
+    /// Show the zero-based position of an address in the multisig's owner list
+    OwnerIndex {
+        /// TRON RPC URL (e.g., https://api.trongrid.io)
+        #[arg(long)]
+        rpc_url: String,
+
+        /// Multisig contract address (TRON base58 format)
+        #[arg(long)]
+        contract: String,
+
+        /// Address to look up (TRON base58 format)
+        #[arg(long)]
+        address: String,
+    },
+
+    /// Show the USDT balance (`getBalance()`) of several multisig contracts
+    /// and their total; a contract that can't be read is reported, not fatal
+    Balances {
+        /// TRON RPC URL (e.g., https://api.trongrid.io)
+        #[arg(long)]
+        rpc_url: String,
+
+        /// Comma-separated multisig contract addresses
+        #[arg(long)]
+        contracts: String,
+
+        #[command(flatten)]
+        failures: FailureMode,
+    },
+
+    /// Show a multisig transaction: recipient, amount, approvals and expiry
+    #[command(visible_alias = "get-transaction")]
+    Transaction {
+        /// TRON RPC URL (e.g., https://api.trongrid.io)
+        #[arg(long)]
+        rpc_url: String,
+
+        /// Multisig contract address (TRON base58 format)
+        #[arg(long)]
+        contract: String,
+
+        /// Multisig transaction ID, counted from 0 (at most 2^64-1)
+        #[arg(long, value_parser = parse_tx_id)]
+        tx_id: u64,
+    },
+
+    /// Show which owners have approved a multisig transaction
+    Approvals {
+        /// TRON RPC URL (e.g., https://api.trongrid.io)
+        #[arg(long)]
+        rpc_url: String,
+
+        /// Multisig contract address (TRON base58 format)
+        #[arg(long)]
+        contract: String,
+
+        /// Multisig transaction ID, counted from 0 (at most 2^64-1)
+        #[arg(long, value_parser = parse_tx_id)]
+        tx_id: u64,
+    },
+
+    /// Estimate the energy and TRX each owner's approval of a pending
+    /// multisig transaction would cost, and the total to reach the threshold,
+    /// by simulating the calls
+    EstimateApprovals {
+        /// TRON RPC URL (e.g., https://api.trongrid.io)
+        #[arg(long)]
+        rpc_url: String,
+
+        /// Multisig contract address (TRON base58 format)
+        #[arg(long)]
+        contract: String,
+
+        /// Multisig transaction ID, counted from 0 (at most 2^64-1)
+        #[arg(long, value_parser = parse_tx_id)]
+        tx_id: u64,
+    },
+
+    /// Reconstruct a multisig's governance history from the blocks in
+    /// [--from-block, --to-block]: every submit, approve, revoke, execute and
+    /// cancel, in chain order, with its signer and transaction
+    History {
+        /// TRON RPC URL (e.g., https://api.trongrid.io)
+        #[arg(long)]
+        rpc_url: String,
+
+        /// Multisig contract address (TRON base58 format)
+        #[arg(long)]
+        contract: String,
+
+        /// First block to scan
+        #[arg(long)]
+        from_block: u64,
+
+        /// Last block to scan (inclusive)
+        #[arg(long)]
+        to_block: u64,
+
+        /// Print CSV instead of a table (JSON with --json)
+        #[arg(long)]
+        csv: bool,
+    },
+
+    /// Propose a USDT payout from the multisig (`submitTransaction`); the
+    /// submitting owner's approval is counted automatically
+    #[command(visible_alias = "submit-transaction")]
+    Submit {
+        /// TRON RPC URL (e.g., https://api.trongrid.io)
+        #[arg(long)]
+        rpc_url: String,
+
+        /// Multisig contract address (TRON base58 format)
+        #[arg(long)]
+        contract: String,
+
+        /// Recipient (TRON base58 address)
+        #[arg(long)]
+        to: String,
+
+        /// Amount in token base units, or env:NAME (e.g., 1000000 = 1 USDT)
+        #[arg(long, required_unless_present = "amount_tokens", value_parser = parse_env_arg::<u128>)]
+        amount: Option<u128>,
+
+        /// Amount in whole tokens (e.g., 12.5), scaled by the multisig token's decimals, or env:NAME
+        #[arg(long, conflicts_with = "amount", value_parser = parse_env_arg::<String>)]
+        amount_tokens: Option<String>,
+
+        /// Submitting owner's key
+        #[command(flatten)]
+        signer: SignerArgs,
+
+        /// Submit even if --to has never been activated on TRON
+        #[arg(long)]
+        allow_inactive: bool,
+
+        /// Wait for the submission to confirm and print the multisig
+        /// transaction ID from its TransactionSubmitted event
+        #[arg(long)]
+        wait: bool,
+
+        /// Fee limit in SUN, or env:NAME (default: 100 TRX = 100,000,000 SUN)
+        #[arg(long, default_value_t = DEFAULT_CALL_FEE_LIMIT_SUN, value_parser = parse_env_arg::<u64>)]
+        fee_limit: u64,
+
+        /// Wait for the transaction to confirm and print its energy, bandwidth and TRX cost
+        #[arg(long)]
+        cost_summary: bool,
+    },
+
+    /// Approve a pending multisig transaction (`approveTransaction`). The
+    /// approval that reaches the threshold also executes the payout
+    #[command(visible_alias = "approve-transaction")]
+    Approve {
+        /// TRON RPC URL (e.g., https://api.trongrid.io)
+        #[arg(long)]
+        rpc_url: String,
+
+        /// Multisig contract address (TRON base58 format)
+        #[arg(long)]
+        contract: String,
+
+        /// Multisig transaction ID, counted from 0 (at most 2^64-1)
+        #[arg(long, value_parser = parse_tx_id)]
+        tx_id: u64,
+
+        /// Approving owner's key; repeat --private-key to approve with several
+        #[command(flatten)]
+        signer: SignerArgs,
+
+        /// File of owner keys to approve with, one hex key per line (blank
+        /// lines and `#` comments are skipped), instead of --private-key
+        #[arg(
+            long,
+            value_name = "PATH",
+            conflicts_with_all = [
+                "private_key",
+                "private_key_env",
+                "private_key_file",
+                "keystore",
+                "mnemonic"
+            ]
+        )]
+        keys_file: Option<PathBuf>,
+
+        /// Broadcast without first asking the contract (`isOwner`) whether
+        /// the signing key is an owner
+        #[arg(long)]
+        skip_owner_check: bool,
+
+        /// Broadcast without first checking that the transaction hasn't
+        /// already executed (or been cancelled)
+        #[arg(long)]
+        skip_executed_check: bool,
+
+        /// Fee limit in SUN, or env:NAME (default: 100 TRX = 100,000,000 SUN)
+        #[arg(long, default_value_t = DEFAULT_CALL_FEE_LIMIT_SUN, value_parser = parse_env_arg::<u64>)]
+        fee_limit: u64,
+
+        /// Wait for the transaction to confirm and print its energy, bandwidth and TRX cost
+        #[arg(long)]
+        cost_summary: bool,
+    },
+
+    /// Withdraw an owner's approval of a pending multisig transaction
+    #[command(visible_alias = "revoke-approval")]
+    Revoke {
+        /// TRON RPC URL (e.g., https://api.trongrid.io)
+        #[arg(long)]
+        rpc_url: String,
+
+        /// Multisig contract address (TRON base58 format)
+        #[arg(long)]
+        contract: String,
+
+        /// Multisig transaction ID, counted from 0 (at most 2^64-1)
+        #[arg(long, value_parser = parse_tx_id)]
+        tx_id: u64,
+
+        /// Revoking owner's key
+        #[command(flatten)]
+        signer: SignerArgs,
+
+        /// Broadcast without first checking that the key has approved the
+        /// transaction and that it hasn't executed or expired
+        #[arg(long)]
+        skip_checks: bool,
+
+        /// Fee limit in SUN, or env:NAME (default: 100 TRX = 100,000,000 SUN)
+        #[arg(long, default_value_t = DEFAULT_CALL_FEE_LIMIT_SUN, value_parser = parse_env_arg::<u64>)]
+        fee_limit: u64,
+
+        /// Wait for the transaction to confirm and print its energy, bandwidth and TRX cost
+        #[arg(long)]
+        cost_summary: bool,
+    },
+
+    /// Execute a multisig transaction that has reached its threshold. Contracts
+    /// without `executeTransaction(uint256)` in their ABI execute on the
+    /// threshold-meeting approval; for those this only reports readiness.
+    Execute {
+        /// TRON RPC URL (e.g., https://api.trongrid.io)
+        #[arg(long)]
+        rpc_url: String,
+
+        /// Multisig contract address (TRON base58 format)
+        #[arg(long)]
+        contract: String,
+
+        /// Multisig transaction ID, counted from 0 (at most 2^64-1)
+        #[arg(long, value_parser = parse_tx_id)]
+        tx_id: u64,
+
+        /// Owner key; required if the contract has `executeTransaction`
+        #[command(flatten)]
+        signer: SignerArgs,
+
+        /// ABI to use: a forge artifact or plain ABI JSON file (default: built-in USDTMultisig ABI)
+        #[arg(long, conflicts_with = "abi_from_chain")]
+        abi: Option<PathBuf>,
+
+        /// Fetch the ABI stored on-chain for --contract via /wallet/getcontract
+        #[arg(long)]
+        abi_from_chain: bool,
+
+        /// Fee limit in SUN, or env:NAME (default: 100 TRX = 100,000,000 SUN)
+        #[arg(long, default_value_t = DEFAULT_CALL_FEE_LIMIT_SUN, value_parser = parse_env_arg::<u64>)]
+        fee_limit: u64,
+
+        /// Wait for the transaction to confirm and print its energy, bandwidth and TRX cost
+        #[arg(long)]
+        cost_summary: bool,
+    },
+
+    /// Turn the signing account into a native TRON multisig account: replace its
+    /// owner and active permissions with --owners (weight 1 each) and --threshold.
+    /// The network charges an account permission update fee (100 TRX on mainnet).
+    SetPermissions {
+        /// TRON RPC URL (e.g., https://api.trongrid.io)
+        #[arg(long)]
+        rpc_url: String,
+
+        /// Permission keys (comma-separated TRON base58 addresses)
+        #[arg(long)]
+        owners: String,
+
+        /// Signatures required to act for the account, or env:NAME
+        #[arg(long, value_parser = parse_env_arg::<u64>)]
+        threshold: u64,
+
+        /// Key of the account being updated (its current owner permission)
+        #[command(flatten)]
+        signer: SignerArgs,
+
+        /// Allowed operations of the active permission, as the 32-byte hex
+        /// bitmap indexed by contract type (default: the standard set, which
+        /// excludes permission updates)
+        #[arg(long, value_name = "HEX", default_value = DEFAULT_ACTIVE_OPERATIONS)]
+        operations: String,
+
+        /// Update even if the signing key is not among --owners, which hands
+        /// control of the account to the listed keys
+        #[arg(long)]
+        force: bool,
+
+        /// Wait for the transaction to confirm and print its energy, bandwidth and TRX cost
+        #[arg(long)]
+        cost_summary: bool,
+    },
+
+    /// Sign a transaction file offline and add the signature to a
+    /// signature-collection file, creating it if needed
+    SignCollection {
+        /// Unsigned transaction JSON (as built by the node, with txID and raw_data_hex)
+        #[arg(long)]
+        transaction: PathBuf,
+
+        /// Signature-collection file to create or add to
+        #[arg(long)]
+        collection: PathBuf,
+
+        /// Genesis block ID of the network, for a new collection
+        #[arg(long, conflicts_with = "rpc_url")]
+        chain_id: Option<String>,
+
+        /// TRON RPC URL to read the genesis block ID from, for a new collection
+        #[arg(long)]
+        rpc_url: Option<String>,
+
+        /// Key to sign with
+        #[command(flatten)]
+        signer: SignerArgs,
+    },
+
+    /// Merge signature-collection files for one transaction, refusing files
+    /// for another transaction, contract or chain
+    MergeSignatures {
+        /// Signature-collection files to merge
+        #[arg(long, required = true, num_args = 1..)]
+        collection: Vec<PathBuf>,
+
+        /// Where to write the merged collection
+        #[arg(long)]
+        out: PathBuf,
+
+        /// Unsigned transaction JSON to attach the merged signatures to
+        #[arg(long, requires = "signed_tx_out")]
+        transaction: Option<PathBuf>,
+
+        /// Where to write --transaction with every collected signature
+        #[arg(long, requires = "transaction")]
+        signed_tx_out: Option<PathBuf>,
+    },
+
+    /// Sign a message the way TRON wallets do (keccak256 over "\x19TRON Signed
+    /// Message:\n", its length and the message), e.g. to prove control of an
+    /// owner address to a backend
+    SignMessage {
+        /// Message to sign, as UTF-8 text
+        #[arg(long)]
+        message: String,
+
+        /// Treat --message as hex bytes (with or without 0x prefix)
+        #[arg(long)]
+        hex: bool,
+
+        /// Sign as TronWeb's original signMessage does, which always writes
+        /// the length as 32 whatever the message's length
+        #[arg(long, visible_alias = "v1")]
+        legacy: bool,
+
+        /// Key to sign with
+        #[command(flatten)]
+        signer: SignerArgs,
+
+        /// Recovery byte encoding: 27 for 27/28 as TronWeb's signMessageV2
+        /// produces, 0 for 0/1
+        #[arg(long, default_value_t = V_OFFSET_ETHEREUM, value_parser = parse_v_offset)]
+        v_offset: u8,
+    },
+
+    /// Recover the address that signed a message (as `sign-message`, TronWeb's
+    /// signMessageV2 or TronLink do), or check it is --address
+    VerifySignature {
+        /// Message that was signed, as UTF-8 text
+        #[arg(long)]
+        message: String,
+
+        /// Treat --message as hex bytes (with or without 0x prefix)
+        #[arg(long)]
+        hex: bool,
+
+        /// Check a signature made by TronWeb's original signMessage, which
+        /// always writes the length as 32 whatever the message's length
+        #[arg(long, visible_alias = "v1")]
+        legacy: bool,
+
+        /// 65-byte r || s || v signature hex (v as 0/1 or 27/28)
+        #[arg(long)]
+        signature: String,
+
+        /// Fail unless this address made the signature
+        #[arg(long)]
+        address: Option<String>,
+    },
+
+    /// Sign an unsigned transaction built by another tool (TronWeb-shaped JSON
+    /// with raw_data and raw_data_hex) without changing it
+    Sign {
+        /// Unsigned (or partly signed) transaction JSON
+        #[arg(long)]
+        tx_json: PathBuf,
+
+        /// Where to write the signed transaction (default: print it)
+        #[arg(long = "out", value_name = "PATH")]
+        out_file: Option<PathBuf>,
+
+        /// Key to sign with
+        #[command(flatten)]
+        signer: SignerArgs,
+
+        /// Recovery byte encoding: 0 for TRON (0/1), 27 for Ethereum-style 27/28
+        #[arg(long, default_value_t = V_OFFSET_TRON, value_parser = parse_v_offset)]
+        v_offset: u8,
+    },
+
+    /// Broadcast a signed transaction file (e.g. from `deploy --no-broadcast`),
+    /// refusing one signed for a different network
+    Broadcast {
+        /// TRON RPC URL (e.g., https://api.trongrid.io)
+        #[arg(long, required_unless_present = "network")]
+        rpc_url: Option<String>,
+
+        /// Named network instead of --rpc-url: mainnet, nile or shasta
+        #[arg(long, conflicts_with = "rpc_url")]
+        network: Option<String>,
+
+        /// Signed transaction JSON
+        #[arg(long)]
+        transaction: PathBuf,
+
+        /// Broadcast even if the file was signed for another network
+        #[arg(long)]
+        force: bool,
+
+        /// Wait for the transaction to confirm and print its energy, bandwidth and TRX cost
+        #[arg(long)]
+        cost_summary: bool,
+    },
+
+    /// Convert hex address to TRON base58 address
+    ToBase58 {
+        /// Hex address, `41`-prefixed or 20-byte (with or without 0x prefix)
+        #[arg(long)]
+        hex: String,
+    },
+
+    /// Decode an ABI-encoded `address[]` (e.g. `getOwners` return data or a
+    /// calldata parameter) into TRON base58 addresses
+    DecodeAddressList {
+        /// ABI-encoded array: offset word, length word, then one word per address
+        /// (hex, with or without 0x prefix)
+        #[arg(long)]
+        hex: String,
+    },
+
+    /// Predict a contract's address before it exists: a CREATE2 deployment from
+    /// --factory, or the contract a transaction (--tx-id) creates
+    ContractAddress {
+        /// CREATE2 factory contract
+        #[arg(long, requires = "salt", conflicts_with = "tx_id")]
+        factory: Option<String>,
+
+        /// CREATE2 salt (hex, up to 32 bytes, left-padded)
+        #[arg(long, value_name = "HEX")]
+        salt: Option<String>,
+
+        /// Init code: creation bytecode followed by ABI-encoded constructor arguments (hex)
+        #[arg(long, value_name = "HEX", conflicts_with = "init_code_hash")]
+        init_code: Option<String>,
+
+        /// Keccak-256 of the init code (hex), instead of --init-code
+        #[arg(long, value_name = "HEX")]
+        init_code_hash: Option<String>,
+
+        /// txID of a deployment transaction (e.g. from `deploy --no-broadcast`), or
+        /// of the transaction whose execution runs CREATE with --nonce
+        #[arg(long, value_name = "HEX")]
+        tx_id: Option<String>,
+
+        /// Owner of the deployment transaction --tx-id
+        #[arg(long, requires = "tx_id", conflicts_with = "nonce")]
+        owner: Option<String>,
+
+        /// Index of the CREATE among --tx-id's internal transactions
+        #[arg(long, requires = "tx_id")]
+        nonce: Option<u64>,
+    },
+
+    /// Convert TRON address to hex
+    ToHex {
+        /// TRON address in any form (base58, `41`-prefixed hex, 20-byte hex)
+        #[arg(long)]
+        address: String,
+
+        /// Output form
+        #[arg(long, value_enum, default_value_t = AddressFormat::Hex41)]
+        format: AddressFormat,
+    },
+}
+
+#[derive(Args)]
+struct DeployArgs {
+    /// Deployment manifest (TOML) declaring network, USDT, owners, threshold,
+    /// fee limit and contract path, instead of the individual flags
+    #[arg(long, conflicts_with_all = ["rpc_url", "usdt", "owners", "threshold", "contract_json", "fee_limit"])]
+    manifest: Option<PathBuf>,
+
+    /// TRON RPC URL (e.g., https://api.trongrid.io)
+    #[arg(long, required_unless_present = "manifest")]
+    rpc_url: Option<String>,
+
+    #[command(flatten)]
+    signer: SignerArgs,
+
+    /// USDT token address (TRON base58 format, e.g., TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t)
+    #[arg(long, required_unless_present_any = ["manifest", "constructor_hex"])]
+    usdt: Option<String>,
+
+    /// Owner addresses (comma-separated TRON base58 addresses)
+    #[arg(long, required_unless_present_any = ["manifest", "constructor_hex"])]
+    owners: Option<String>,
+
+    /// Required approval threshold, or env:NAME
+    #[arg(long, required_unless_present_any = ["manifest", "constructor_hex"], value_parser = parse_env_arg::<u64>)]
+    threshold: Option<u64>,
+
+    /// ABI-encoded constructor parameters (hex), e.g. from an earlier
+    /// deployment, deployed as given instead of encoding --usdt, --owners and
+    /// --threshold. --usdt or --threshold next to it replace just that value
+    #[arg(long, value_name = "HEX", conflicts_with_all = ["manifest", "owners"])]
+    constructor_hex: Option<String>,
+
+    /// Deploy the owners sorted by address (their 20-byte value) instead of
+    /// in the order given, for tooling that expects canonical order. This is
+    /// the order of the on-chain owner array, so index-based references to
+    /// owners follow it. Manifests can set `sort_owners = true` instead
+    #[arg(long, conflicts_with = "constructor_hex")]
+    sort_owners: bool,
+
+    /// Path to compiled contract JSON (from forge build)
+    #[arg(long, default_value = DEFAULT_CONTRACT_JSON)]
+    contract_json: PathBuf,
+
+    /// Fee limit in SUN, or env:NAME (default: 1000 TRX = 1,000,000,000 SUN)
+    #[arg(long, default_value_t = DEFAULT_FEE_LIMIT_SUN, value_parser = parse_env_arg::<u64>)]
+    fee_limit: u64,
+
+    /// Create and sign the transaction without broadcasting it, writing it to
+    /// --signed-tx-out or else printing it, for `broadcast` to send later.
+    /// Building it still needs the node; to keep the key off the network,
+    /// `sign` the transaction on another machine and then `broadcast` it
+    #[arg(long)]
+    no_broadcast: bool,
+
+    /// Write the signed transaction JSON to this path (for broadcasting later)
+    #[arg(long)]
+    signed_tx_out: Option<PathBuf>,
+
+    /// Skip deployment if this contract already exists with the same USDT, owners and threshold
+    #[arg(long, value_name = "CONTRACT")]
+    if_not_exists: Option<String>,
+
+    /// Deploy even if --fee-limit looks like a SUN/TRX mix-up or there are
+    /// more than --max-owners owners
+    #[arg(long)]
+    force: bool,
+
+    /// Owners above which deployment needs --force. Every owner adds
+    /// constructor storage and is looped over when listing owners
+    #[arg(long, default_value_t = DEFAULT_MAX_OWNERS)]
+    max_owners: usize,
+
+    /// Recovery byte encoding: 0 for TRON's raw 0/1 (what nodes accept), 27 for
+    /// Ethereum-style 27/28 (for external verifiers of --signed-tx-out)
+    #[arg(long, default_value_t = V_OFFSET_TRON, value_parser = parse_v_offset)]
+    v_offset: u8,
+
+    /// Print the exact /wallet/deploycontract and /wallet/broadcasttransaction
+    /// bodies (signature redacted) instead of signing and broadcasting. The
+    /// deploycontract call is still made, since it only builds the unsigned
+    /// transaction the broadcast body carries
+    #[arg(long, conflicts_with_all = ["no_broadcast", "signed_tx_out"])]
+    print_request: bool,
+
+    /// Seconds until the transaction expires instead of the node's ~60s, so an
+    /// offline signer has time to sign it (at most the chain maximum, ~24h)
+    #[arg(long, value_name = "SECONDS")]
+    expiration: Option<u64>,
+
+    /// Wait for the deployment to confirm and print its energy, bandwidth and TRX cost
+    #[arg(long, conflicts_with_all = ["no_broadcast", "print_request"])]
+    cost_summary: bool,
+
+    /// Add the deployed ABI to the result, so a `--json` record saved from it
+    /// describes the contract fully (`call --abi RECORD` reads it back)
+    #[arg(long)]
+    include_abi: bool,
+
+    /// Instead of deploying, print (or write to PATH) a shell script with the
+    /// fully resolved `tron-utils deploy` command and the constructor hex, for
+    /// review and change-managed re-runs. Needs no key
+    #[arg(
+        long,
+        value_name = "PATH",
+        num_args = 0..=1,
+        conflicts_with_all = ["no_broadcast", "signed_tx_out", "print_request", "cost_summary"]
+    )]
+    emit_script: Option<Option<PathBuf>>,
+}
+
+/// How a command over several targets treats one that fails.
+#[derive(Args)]
+struct FailureMode {
+    /// Stop at the first target that fails, and fail with its error
+    #[arg(long, conflicts_with = "continue_on_error")]
+    fail_fast: bool,
+
+    /// Report a target that fails and go on with the others (the default)
+    #[arg(long = "continue")]
+    continue_on_error: bool,
+}
+
+/// Where transaction signatures come from: a private key given on the command
+/// line, in an environment variable, file or keystore, derived from a mnemonic
+/// or, with the `pkcs11` feature, a key that never leaves a PKCS#11 token.
+#[derive(Args)]
+struct SignerArgs {
+    /// Private key (hex, with or without 0x prefix). `approve` takes it more
+    /// than once, to approve with each key
+    #[arg(long, conflicts_with = "mnemonic")]
+    private_key: Vec<String>,
+
+    /// Environment variable holding the private key, so it stays out of shell
+    /// history and process listings
+    #[arg(long, value_name = "VAR", conflicts_with_all = ["private_key", "mnemonic"])]
+    private_key_env: Option<String>,
+
+    /// File holding the private key (hex, with or without 0x prefix)
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["private_key", "private_key_env", "mnemonic"]
+    )]
+    private_key_file: Option<PathBuf>,
+
+    /// Keystore (Web3 Secret Storage JSON) holding the key, such as
+    /// `generate-key --keystore` writes. The passphrase is read from
+    /// KEYSTORE_PASSPHRASE or prompted for
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["private_key", "private_key_env", "private_key_file", "mnemonic"]
+    )]
+    keystore: Option<PathBuf>,
+
+    /// BIP39 mnemonic to derive the key from (English words, quoted). A
+    /// BIP39 passphrase, if the wallet uses one, is read from MNEMONIC_PASSPHRASE
+    #[arg(long)]
+    mnemonic: Option<String>,
+
+    /// BIP32 path of the key below --mnemonic
+    #[arg(
+        long,
+        requires = "mnemonic",
+        conflicts_with_all = ["private_key", "private_key_env", "private_key_file", "keystore"],
+        default_value = hd::DEFAULT_TRON_PATH,
+        value_parser = hd::DerivationPath::parse
+    )]
+    path: hd::DerivationPath,
+
+    /// Account of the key below --mnemonic, as m/44'/195'/ACCOUNT'/0/INDEX
+    #[arg(
+        long,
+        requires = "mnemonic",
+        conflicts_with_all = [
+            "path",
+            "private_key",
+            "private_key_env",
+            "private_key_file",
+            "keystore"
+        ]
+    )]
+    account: Option<u32>,
+
+    /// Address index of the key below --mnemonic, as m/44'/195'/ACCOUNT'/0/INDEX
+    #[arg(
+        long,
+        requires = "mnemonic",
+        conflicts_with_all = [
+            "path",
+            "private_key",
+            "private_key_env",
+            "private_key_file",
+            "keystore"
+        ]
+    )]
+    index: Option<u32>,
+
+    #[cfg(feature = "pkcs11")]
+    #[command(flatten)]
+    pkcs11: pkcs11::Pkcs11Args,
+}
+
+/// Environment variable holding the BIP39 passphrase of `--mnemonic`.
+const MNEMONIC_PASSPHRASE_ENV: &str = "MNEMONIC_PASSPHRASE";
+
+/// The hex private key below `mnemonic` at `path` or, when `account` or
+/// `index` is given, at m/44'/195'/ACCOUNT'/0/INDEX, with the path it used.
+/// The BIP39 passphrase, if any, comes from [`MNEMONIC_PASSPHRASE_ENV`].
+fn mnemonic_private_key(
+    mnemonic: &str,
+    path: &hd::DerivationPath,
+    account: Option<u32>,
+    index: Option<u32>,
+) -> Result<(hd::DerivationPath, String)> {
+    let path = match (account, index) {
+        (None, None) => path.clone(),
+        (account, index) => hd::DerivationPath::tron(account.unwrap_or(0), index.unwrap_or(0))?,
+    };
+    let passphrase = std::env::var(MNEMONIC_PASSPHRASE_ENV).unwrap_or_default();
+    let seed = hd::mnemonic_to_seed(mnemonic, &passphrase)?;
+    let key = hd::derive_private_key(&seed, &path)
+        .with_context(|| format!("Cannot derive {} from the mnemonic", path))?;
+    Ok((path, hex::encode(key.secret_bytes())))
+}
+
+impl SignerArgs {
+    fn signer(&self, prefix: u8) -> Result<Signer> {
+        #[cfg(feature = "pkcs11")]
+        if let Some(token_key) = self.pkcs11.open()? {
+            return Ok(Signer::Pkcs11(token_key));
+        }
+        Ok(Signer::PrivateKey(self.resolve_signing_key(prefix)?))
+    }
+
+    /// The hex private key given by `--private-key`, `--private-key-env`,
+    /// `--private-key-file` or `--keystore`, or derived from `--mnemonic`,
+    /// checked well-formed before any network work.
+    fn resolve_signing_key(&self, prefix: u8) -> Result<String> {
+        #[cfg(feature = "pkcs11")]
+        const MISSING: &str = "Pass --private-key, --private-key-env, --private-key-file, \
+                               --keystore, --mnemonic or --pkcs11-module to sign";
+        #[cfg(not(feature = "pkcs11"))]
+        const MISSING: &str = "Pass --private-key, --private-key-env, --private-key-file, \
+                               --keystore or --mnemonic to sign";
+
+        let private_key = match (self.resolve_private_key(prefix)?, &self.mnemonic) {
+            (Some(private_key), _) => private_key,
+            (None, Some(mnemonic)) => {
+                mnemonic_private_key(mnemonic, &self.path, self.account, self.index)?.1
+            }
+            (None, None) => return Err(invalid_input(MISSING)),
+        };
+        public_key_from_private_key(&private_key)?;
+        Ok(private_key)
+    }
+
+    /// The one private key given directly, from whichever of `--private-key`,
+    /// `--private-key-env`, `--private-key-file` and `--keystore` was used;
+    /// `None` if none was. Whitespace and a `0x` prefix around an env or file
+    /// key are dropped.
+    fn resolve_private_key(&self, prefix: u8) -> Result<Option<String>> {
+        let from_env = self.private_key_env.as_ref().map(|name| {
+            std::env::var(name)
+                .map_err(|_| invalid_input(format!("Environment variable {} is not set", name)))
+        });
+        let from_file = self.private_key_file.as_ref().map(|path| {
+            read_text_file(path).with_context(|| format!("Failed to read key file: {:?}", path))
+        });
+        let mut sources: Vec<Result<String>> = self
+            .private_key
+            .iter()
+            .cloned()
+            .map(Ok)
+            .chain(from_env)
+            .chain(from_file)
+            .chain(
+                self.keystore
+                    .as_deref()
+                    .map(|path| keystore_private_key(path, prefix)),
+            )
+            .collect();
+        match sources.len() {
+            0 => Ok(None),
+            1 => {
+                let key = sources.pop().expect("one source")?;
+                let key = key.trim();
+                Ok(Some(key.strip_prefix("0x").unwrap_or(key).to_string()))
+            }
+            n if self.private_key.len() == n => Err(invalid_input(format!(
+                "--private-key was given {} times, but this command signs with one key",
+                n
+            ))),
+            _ => Err(invalid_input(
+                "Pass only one of --private-key, --private-key-env, --private-key-file \
+                 and --keystore",
+            )),
+        }
+    }
+
+    /// The keys to sign with: each `--private-key` when several are given,
+    /// otherwise the one [`SignerArgs::signer`] opens.
+    fn signers(&self, prefix: u8) -> Result<Vec<Signer>> {
+        if self.private_key.len() < 2 {
+            return Ok(vec![self.signer(prefix)?]);
+        }
+        self.private_key
+            .iter()
+            .map(|key| {
+                public_key_from_private_key(key)?;
+                Ok(Signer::PrivateKey(key.clone()))
+            })
+            .collect()
+    }
+}
+
+/// An opened signing key.
+enum Signer {
+    PrivateKey(String),
+    #[cfg(feature = "pkcs11")]
+    Pkcs11(pkcs11::TokenKey),
+}
+
+impl Signer {
+    fn address(&self, prefix: u8) -> Result<String> {
+        match self {
+            Signer::PrivateKey(key) => Ok(private_key_to_tron_address(key, prefix)?),
+            #[cfg(feature = "pkcs11")]
+            Signer::Pkcs11(token_key) => {
+                Ok(public_key_to_tron_address(&token_key.public_key, prefix))
+            }
+        }
+    }
+
+    /// The hex `r || s || v` signature over `tx_id`, whichever kind of key
+    /// this is.
+    fn sign(&self, tx_id: &str, v_offset: u8) -> Result<String> {
+        match self {
+            Signer::PrivateKey(key) => Ok(sign_transaction_with_v_offset(tx_id, key, v_offset)?),
+            #[cfg(feature = "pkcs11")]
+            Signer::Pkcs11(token_key) => token_key.sign(tx_id, v_offset),
+        }
+    }
+
+    /// Signs like [`sign_transaction_json`], whichever kind of key this is.
+    fn sign_transaction_json(
+        &self,
+        transaction: &serde_json::Value,
+        tx_id: &str,
+        v_offset: u8,
+    ) -> Result<serde_json::Value> {
+        match self {
+            Signer::PrivateKey(key) => {
+                Ok(sign_transaction_json(transaction, tx_id, key, v_offset)?)
+            }
+            #[cfg(feature = "pkcs11")]
+            Signer::Pkcs11(token_key) => {
+                let signature = token_key.sign(tx_id, v_offset)?;
+                let mut signed_tx = transaction.clone();
+                signed_tx
+                    .as_object_mut()
+                    .ok_or_else(|| anyhow!("Transaction is not an object"))?
+                    .insert("signature".to_string(), serde_json::json!([signature]));
+                Ok(signed_tx)
+            }
+        }
+    }
+}
+
+/// Signing with an secp256k1 key stored on a PKCS#11 token. The token only
+/// returns `r || s`; [`signature_from_raw`] turns that into the same
+/// `r || s || v` that [`sign_transaction`] produces.
+///
+/// [`signature_from_raw`]: tron_utils::signing::signature_from_raw
+/// [`sign_transaction`]: tron_utils::signing::sign_transaction
+#[cfg(feature = "pkcs11")]
+mod pkcs11 {
+    use anyhow::{anyhow, Context, Result};
+    use clap::Args;
+    use cryptoki::context::{CInitializeArgs, CInitializeFlags, Pkcs11};
+    use cryptoki::mechanism::Mechanism;
+    use cryptoki::object::{Attribute, AttributeType, KeyType, ObjectClass, ObjectHandle};
+    use cryptoki::session::{Session, UserType};
+    use cryptoki::types::AuthPin;
+    use secp256k1::PublicKey;
+    use std::path::PathBuf;
+    use tron_utils::signing::signature_from_raw;
+
+    use super::invalid_input;
+
+    /// Environment variable holding the token's user PIN.
+    const PIN_ENV: &str = "PKCS11_PIN";
+
+    #[derive(Args, Default)]
+    pub struct Pkcs11Args {
+        /// PKCS#11 module to sign with instead of --private-key (e.g.
+        /// /usr/lib/x86_64-linux-gnu/libykcs11.so); the PIN is read from $PKCS11_PIN
+        #[arg(
+            long,
+            value_name = "PATH",
+            conflicts_with_all = ["private_key", "private_key_env", "private_key_file", "keystore"],
+            requires = "pkcs11_key_label"
+        )]
+        pkcs11_module: Option<PathBuf>,
+
+        /// Label of the token holding the key (default: the only token present)
+        #[arg(long, value_name = "LABEL", requires = "pkcs11_module")]
+        pkcs11_token: Option<String>,
+
+        /// Label (CKA_LABEL) of the secp256k1 key pair on the token
+        #[arg(long, value_name = "LABEL", requires = "pkcs11_module")]
+        pkcs11_key_label: Option<String>,
+    }
+
+    /// A logged-in session and the handle of the private key to sign with.
+    pub struct TokenKey {
+        session: Session,
+        key: ObjectHandle,
+        pub public_key: PublicKey,
+    }
+
+    impl Pkcs11Args {
+        /// Opens the configured key, or `None` if no module was given.
+        pub fn open(&self) -> Result<Option<TokenKey>> {
+            let (Some(module), Some(label)) = (&self.pkcs11_module, &self.pkcs11_key_label) else {
+                return Ok(None);
+            };
+            let pin = std::env::var(PIN_ENV)
+                .map_err(|_| invalid_input(format!("Set {} to the token's user PIN", PIN_ENV)))?;
+
+            let context = Pkcs11::new(module)
+                .with_context(|| format!("Failed to load PKCS#11 module {:?}", module))?;
+            context.initialize(CInitializeArgs::new(CInitializeFlags::OS_LOCKING_OK))?;
+
+            let mut slots = Vec::new();
+            for slot in context.get_slots_with_token()? {
+                let token_label = context.get_token_info(slot)?.label().trim().to_string();
+                if self
+                    .pkcs11_token
+                    .as_deref()
+                    .is_none_or(|want| want == token_label)
+                {
+                    slots.push(slot);
+                }
+            }
+            let slot = match slots.as_slice() {
+                [slot] => *slot,
+                [] => return Err(invalid_input("No matching PKCS#11 token found")),
+                _ => {
+                    return Err(invalid_input(
+                        "Several PKCS#11 tokens found; pick one with --pkcs11-token",
+                    ))
+                }
+            };
+
+            let session = context.open_ro_session(slot)?;
+            session
+                .login(UserType::User, Some(&AuthPin::from(pin)))
+                .context("PKCS#11 login failed")?;
+
+            let key = find_key(&session, ObjectClass::PRIVATE_KEY, label)?;
+            let public = find_key(&session, ObjectClass::PUBLIC_KEY, label)?;
+            let point = session
+                .get_attributes(public, &[AttributeType::EcPoint])?
+                .into_iter()
+                .find_map(|attribute| match attribute {
+                    Attribute::EcPoint(point) => Some(point),
+                    _ => None,
+                })
+                .ok_or_else(|| anyhow!("Public key {:?} has no EC point", label))?;
+            // CKA_EC_POINT is usually a DER OCTET STRING around the SEC1 point
+            let sec1 = match point.as_slice() {
+                [0x04, len, rest @ ..] if *len as usize == rest.len() => rest,
+                sec1 => sec1,
+            };
+            let public_key = PublicKey::from_slice(sec1)
+                .with_context(|| format!("Key {:?} is not an secp256k1 key", label))?;
+
+            Ok(Some(TokenKey {
+                session,
+                key,
+                public_key,
+            }))
+        }
+    }
+
+    fn find_key(session: &Session, class: ObjectClass, label: &str) -> Result<ObjectHandle> {
+        let template = [
+            Attribute::Class(class),
+            Attribute::KeyType(KeyType::EC),
+            Attribute::Label(label.as_bytes().to_vec()),
+        ];
+        match session.find_objects(&template)?.as_slice() {
+            [handle] => Ok(*handle),
+            [] => Err(invalid_input(format!(
+                "No EC {} labelled {:?} on the token",
+                class, label
+            ))),
+            _ => Err(invalid_input(format!(
+                "Several EC {} objects labelled {:?}",
+                class, label
+            ))),
+        }
+    }
+
+    impl TokenKey {
+        /// Signs the 32-byte transaction ID with raw ECDSA (`CKM_ECDSA`).
+        pub fn sign(&self, tx_id: &str, v_offset: u8) -> Result<String> {
+            let digest = hex::decode(tx_id).context("Invalid tx_id hex")?;
+            let raw = self
+                .session
+                .sign(&Mechanism::Ecdsa, self.key, &digest)
+                .context("PKCS#11 signing failed")?;
+            Ok(signature_from_raw(tx_id, &raw, &self.public_key, v_offset)?)
+        }
+    }
+}
+
+/// The bytes of a `--message`: the UTF-8 text itself or, with `--hex`, the
+/// bytes it spells in hex.
+fn message_bytes(message: &str, hex: bool) -> Result<Vec<u8>> {
+    if !hex {
+        return Ok(message.as_bytes().to_vec());
+    }
+    let digits = message.trim();
+    let digits = digits.strip_prefix("0x").unwrap_or(digits);
+    hex::decode(digits).map_err(|err| invalid_input(format!("Invalid --message hex: {}", err)))
+}
+
+/// The hex digest `sign-message` signs and `verify-signature` recovers
+/// from: TronWeb's `signMessageV2` one, or with `--legacy` its original
+/// `signMessage` one.
+fn signed_message_digest(message: &[u8], legacy: bool) -> String {
+    hex::encode(if legacy {
+        legacy_message_digest(message)
+    } else {
+        message_digest(message)
+    })
+}
+
+fn parse_v_offset(value: &str) -> Result<u8, String> {
+    match value.parse() {
+        Ok(offset @ (V_OFFSET_TRON | V_OFFSET_ETHEREUM)) => Ok(offset),
+        _ => Err(format!(
+            "expected {} or {}",
+            V_OFFSET_TRON, V_OFFSET_ETHEREUM
+        )),
+    }
+}
+
+/// A value given as is or, as `env:NAME`, read from environment variable
+/// `NAME`, so CI pipelines can inject amounts and limits from their secrets.
+/// Either way it is parsed and validated the same.
+fn parse_env_arg<T: std::str::FromStr>(value: &str) -> Result<T, String>
+where
+    T::Err: std::fmt::Display,
+{
+    let resolved;
+    let value = match value.strip_prefix("env:") {
+        Some(name) => {
+            resolved = std::env::var(name)
+                .map_err(|_| format!("environment variable {} is not set", name))?;
+            resolved.trim()
+        }
+        None => value,
+    };
+    value.parse().map_err(|err: T::Err| err.to_string())
+}
+
+/// `--tx-id`: a decimal multisig transaction ID. The contract's IDs are
+/// uint256 counters, but anything past 64 bits can't have been submitted.
+fn parse_tx_id(value: &str) -> Result<u64, String> {
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return Err("expected a decimal transaction ID, e.g. 0".to_string());
+    }
+    value.parse().map_err(|_| {
+        format!(
+            "transaction IDs above {} (2^64-1) are not supported; the multisig counts them up from 0",
+            u64::MAX
+        )
+    })
+}
+
+/// `--address-prefix`: one byte of hex, with or without `0x`.
+fn parse_address_prefix(value: &str) -> Result<u8, String> {
+    let digits = value.strip_prefix("0x").unwrap_or(value);
+    match u8::from_str_radix(digits, 16) {
+        Ok(prefix) if digits.len() == 2 => Ok(prefix),
+        _ => Err("expected one byte of hex, e.g. 0x41".to_string()),
+    }
+}
+
+/// Default `--contract-json`, relative to the `tron-utils` directory.
+const DEFAULT_CONTRACT_JSON: &str = "../out/Multisig.sol/USDTMultisig.json";
+/// Default `--fee-limit`: 1000 TRX.
+const DEFAULT_FEE_LIMIT_SUN: u64 = 1_000_000_000;
+
+/// Default fee limit for calls into a deployed multisig (100 TRX).
+const DEFAULT_CALL_FEE_LIMIT_SUN: u64 = 100_000_000;
+
+/// Default `--max-owners`.
+const DEFAULT_MAX_OWNERS: usize = 20;
+/// Rough constructor energy per owner: two new storage slots (`isOwner` and
+/// the `owners` entry) at 20,000 energy each, plus the loop around them.
+const ENERGY_PER_OWNER: u64 = 41_000;
+
+/// A deployment spec read from `deploy --manifest`, e.g.:
+///
+/// ```toml
+/// network = "nile"            # or rpc_url = "https://..."
+/// usdt = "TXYZopYRdj2D9XRtbG411XZZ3kM5VkAeBf"
+/// owners = ["T...", "T...", "T..."]
+/// threshold = 2
+/// fee_limit = 1000000000      # SUN, optional
+/// contract_json = "out/Multisig.sol/USDTMultisig.json"  # optional, relative to the manifest
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DeployManifest {
+    network: Option<String>,
+    rpc_url: Option<String>,
+    usdt: String,
+    owners: Vec<String>,
+    threshold: u64,
+    fee_limit: Option<u64>,
+    contract_json: Option<PathBuf>,
+    #[serde(default)]
+    sort_owners: bool,
+}
+
+/// Fully resolved deployment parameters, from a manifest or from flags.
+#[derive(Debug)]
+struct DeployPlan {
+    rpc_url: String,
+    usdt: String,
+    owners: Vec<String>,
+    threshold: u64,
+    fee_limit: u64,
+    contract_json: PathBuf,
+    /// Encoded constructor parameters to deploy instead of encoding the
+    /// fields above, which are then decoded from them (`--constructor-hex`)
+    constructor_params: Option<String>,
+    /// Whether `owners` was sorted by address (`--sort-owners`)
+    owners_sorted: bool,
+}
+
+impl DeployPlan {
+    fn from_args(args: &DeployArgs, prefix: u8) -> Result<Self> {
+        if let Some(params) = &args.constructor_hex {
+            return DeployPlan::from_constructor_hex(args, params, prefix);
+        }
+        let Some(path) = &args.manifest else {
+            let owners = args.owners.as_deref().unwrap_or_default();
+            let plan = DeployPlan {
+                rpc_url: args.rpc_url.clone().unwrap_or_default(),
+                usdt: args.usdt.clone().unwrap_or_default(),
+                owners: owners.split(',').map(|s| s.trim().to_string()).collect(),
+                threshold: args.threshold.unwrap_or_default(),
+                fee_limit: args.fee_limit,
+                contract_json: args.contract_json.clone(),
+                constructor_params: None,
+                owners_sorted: false,
+            };
+            return Ok(plan.sorted(args.sort_owners, prefix));
+        };
+
+        let data =
+            read_text_file(path).with_context(|| format!("Failed to read manifest: {:?}", path))?;
+        let manifest: DeployManifest = toml::from_str(&data)
+            .map_err(|e| invalid_input(format!("Invalid manifest {:?}: {}", path, e)))?;
+        let base = path.parent().unwrap_or_else(|| std::path::Path::new(""));
+        Ok(DeployPlan::from_manifest(manifest, base, prefix)?.sorted(args.sort_owners, prefix))
+    }
+
+    /// Relative `contract_json` paths are resolved against `base`, the manifest's directory.
+    fn from_manifest(manifest: DeployManifest, base: &std::path::Path, prefix: u8) -> Result<Self> {
+        let rpc_url = match (manifest.rpc_url, manifest.network.as_deref()) {
+            (Some(_), Some(_)) => {
+                return Err(invalid_input(
+                    "Manifest sets both network and rpc_url; use one",
+                ))
+            }
+            (Some(url), None) => url,
+            (None, Some(network)) => network_rpc_url(network)?.to_string(),
+            (None, None) => return Err(invalid_input("Manifest needs network or rpc_url")),
+        };
+
+        let plan = DeployPlan {
+            rpc_url,
+            usdt: manifest.usdt,
+            owners: manifest.owners,
+            threshold: manifest.threshold,
+            fee_limit: manifest.fee_limit.unwrap_or(DEFAULT_FEE_LIMIT_SUN),
+            contract_json: base.join(
+                manifest
+                    .contract_json
+                    .unwrap_or_else(|| PathBuf::from(DEFAULT_CONTRACT_JSON)),
+            ),
+            constructor_params: None,
+            owners_sorted: false,
+        };
+        Ok(plan.sorted(manifest.sort_owners, prefix))
+    }
+
+    /// The plan with its owners sorted by address if `sort` is set.
+    /// Unparseable owners sort first and fail in `validate`.
+    fn sorted(mut self, sort: bool, prefix: u8) -> Self {
+        if sort {
+            self.owners
+                .sort_by_cached_key(|owner| parse_address(owner, prefix).ok());
+            self.owners_sorted = true;
+        }
+        self
+    }
+
+    /// A plan deploying `params_hex` (checked against the constructor of the
+    /// ABI sent with the deployment), with `--usdt`/`--threshold` swapped in.
+    /// Both are static words, so the rest of the encoding stays as given.
+    fn from_constructor_hex(args: &DeployArgs, params_hex: &str, prefix: u8) -> Result<Self> {
+        let abi = ContractAbi::from_json(&serde_json::from_str(get_contract_abi())?)?;
+        let mut values = decode_constructor_params(&abi, params_hex)?;
+        if let Some(usdt) = &args.usdt {
+            values[0] = AbiValue::address(usdt, prefix).context("Invalid USDT address")?;
+        }
+        if let Some(threshold) = args.threshold {
+            values[2] = AbiValue::uint(threshold as u128);
+        }
+
+        let [AbiValue::Address(usdt), AbiValue::Array(owners), AbiValue::Uint(threshold)] =
+            values.as_slice()
+        else {
+            return Err(anyhow!("Unexpected constructor parameter types"));
+        };
+        let threshold = u64::try_from(abi_word_to_u128(&hex::encode(threshold))?)
+            .map_err(|_| invalid_input("Constructor threshold does not fit in 64 bits"))?;
+        let owners = owners
+            .iter()
+            .map(|owner| match owner {
+                AbiValue::Address(raw) => Ok(format_address(raw, AddressFormat::Base58, prefix)),
+                _ => Err(anyhow!("Unexpected constructor owner type")),
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(DeployPlan {
+            rpc_url: args.rpc_url.clone().unwrap_or_default(),
+            usdt: format_address(usdt, AddressFormat::Base58, prefix),
+            owners,
+            threshold,
+            fee_limit: args.fee_limit,
+            contract_json: args.contract_json.clone(),
+            constructor_params: Some(encode_params(&values)),
+            owners_sorted: false,
+        })
+    }
+
+    /// Checks everything that can be checked offline, so a bad spec fails
+    /// before any network call.
+    fn validate(&self, prefix: u8) -> Result<()> {
+        tron_address_to_hex(&self.usdt, prefix).context("Invalid USDT address")?;
+
+        let mut seen = HashSet::new();
+        for owner in &self.owners {
+            let hex = tron_address_to_hex(owner, prefix)
+                .with_context(|| format!("Invalid owner address {:?}", owner))?;
+            if !seen.insert(hex) {
+                return Err(invalid_input(format!("Duplicate owner: {}", owner)));
+            }
+        }
+
+        if self.threshold == 0 || self.threshold as usize > self.owners.len() {
+            return Err(invalid_input(
+                "Invalid threshold: must be > 0 and <= number of owners",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// RPC endpoint for a named TRON network.
+fn network_rpc_url(network: &str) -> Result<&'static str> {
+    match network {
+        "mainnet" => Ok("https://api.trongrid.io"),
+        "nile" => Ok("https://nile.trongrid.io"),
+        "shasta" => Ok("https://api.shasta.trongrid.io"),
+        _ => Err(invalid_input(format!(
+            "Unknown network {:?} (expected mainnet, nile or shasta)",
+            network
+        ))),
+    }
+}
+
+/// The TRON network an RPC URL points at, recognised from TronGrid host names.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Network {
+    Mainnet,
+    Nile,
+    Shasta,
+    /// A private node or an unrecognised provider
+    Unknown,
+}
+
+/// Tether's USDT (TRC20) contract on mainnet.
+const MAINNET_USDT: &str = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t";
+
+impl Network {
+    fn from_rpc_url(rpc_url: &str) -> Self {
+        let host = rpc_url
+            .split("://")
+            .last()
+            .unwrap_or_default()
+            .split(['/', ':'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if host.contains("nile") {
+            Network::Nile
+        } else if host.contains("shasta") {
+            Network::Shasta
+        } else if host == "api.trongrid.io" || host.ends_with(".tronstack.io") {
+            Network::Mainnet
+        } else {
+            Network::Unknown
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Network::Mainnet => "MAINNET (real funds)",
+            Network::Nile => "NILE TESTNET",
+            Network::Shasta => "SHASTA TESTNET",
+            Network::Unknown => "UNKNOWN NETWORK",
+        }
+    }
+
+    /// A warning when `usdt` looks meant for a different network: TRON
+    /// addresses carry the same prefix on every network, so nothing else
+    /// catches a mainnet token address on a testnet or vice versa. `usdt`
+    /// may be in any address format.
+    fn usdt_mismatch(self, usdt: &str, prefix: u8) -> Option<String> {
+        let is_mainnet_usdt = parse_address(usdt, prefix).ok()
+            == parse_address(MAINNET_USDT, TRON_ADDRESS_PREFIX).ok();
+        match self {
+            Network::Nile | Network::Shasta if is_mainnet_usdt => Some(format!(
+                "{} is mainnet USDT but the target is {}; the testnet has a different (or no) token at that address.",
+                usdt,
+                self.label()
+            )),
+            Network::Mainnet if !is_mainnet_usdt => Some(format!(
+                "Deploying to mainnet with USDT {} rather than Tether's {}; make sure this token is intended.",
+                usdt, MAINNET_USDT
+            )),
+            _ => None,
+        }
+    }
+
+    /// TronScan link templates for transactions and addresses.
+    fn explorer_templates(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            Network::Mainnet => Some((
+                "https://tronscan.org/#/transaction/{txid}",
+                "https://tronscan.org/#/address/{address}",
+            )),
+            Network::Nile => Some((
+                "https://nile.tronscan.org/#/transaction/{txid}",
+                "https://nile.tronscan.org/#/address/{address}",
+            )),
+            Network::Shasta => Some((
+                "https://shasta.tronscan.org/#/transaction/{txid}",
+                "https://shasta.tronscan.org/#/address/{address}",
+            )),
+            Network::Unknown => None,
+        }
+    }
+}
+
+/// Something to link to on a block explorer.
+#[derive(Clone, Copy)]
+enum ExplorerLink<'a> {
+    Transaction(&'a str),
+    Address(&'a str),
+}
+
+/// The explorer URL for `link`, from `--explorer-url` if given and otherwise
+/// the default for `rpc_url`'s network. `None` if the template has no
+/// placeholder for this kind of link, or the network has no known explorer.
+fn explorer_link(template: Option<&str>, rpc_url: &str, link: ExplorerLink) -> Option<String> {
+    let (placeholder, value) = match link {
+        ExplorerLink::Transaction(txid) => ("{txid}", txid),
+        ExplorerLink::Address(address) => ("{address}", address),
+    };
+    let template = match template {
+        Some(template) => template,
+        None => {
+            let (transaction, address) = Network::from_rpc_url(rpc_url).explorer_templates()?;
+            match link {
+                ExplorerLink::Transaction(_) => transaction,
+                ExplorerLink::Address(_) => address,
+            }
+        }
+    };
+    template
+        .contains(placeholder)
+        .then(|| template.replace(placeholder, value))
+}
+
+/// The parts of a forge artifact the CLI uses. Artifacts run to megabytes,
+/// mostly AST and metadata, so they are streamed and everything else is
+/// skipped rather than held in memory.
+#[derive(Debug, Deserialize)]
+struct ContractJson {
+    bytecode: BytecodeObject,
+    #[serde(rename = "deployedBytecode")]
+    deployed_bytecode: Option<BytecodeObject>,
+}
+
+impl ContractJson {
+    fn load(path: &std::path::Path) -> Result<Self> {
+        read_json_file(path, "contract JSON")
+    }
+}
+
+/// Streams `path` into `T`, reading only what `T` keeps. A syntax error
+/// names the line and column and shows the text there.
+fn read_json_file<T: serde::de::DeserializeOwned>(path: &std::path::Path, what: &str) -> Result<T> {
+    let file =
+        open_text_file(path).with_context(|| format!("Failed to read {}: {:?}", what, path))?;
+    serde_json::from_reader(file).map_err(|err| {
+        // The file was streamed, so the offending line is read again
+        let snippet = open_text_file(path)
+            .ok()
+            .and_then(|file| json_error_snippet(file, &err));
+        let message = format!("Failed to parse {} {:?}: {}", what, path, err);
+        match snippet {
+            Some(snippet) => anyhow!("{}\n{}", message, snippet),
+            None => anyhow!(message),
+        }
+    })
+}
+
+/// The byte order mark some Windows editors write at the start of UTF-8 files.
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+
+/// Opens `path` for streaming, past any UTF-8 byte order mark.
+fn open_text_file(path: &std::path::Path) -> std::io::Result<std::io::BufReader<fs::File>> {
+    use std::io::BufRead;
+
+    let mut reader = std::io::BufReader::new(fs::File::open(path)?);
+    if reader.fill_buf()?.starts_with(UTF8_BOM) {
+        reader.consume(UTF8_BOM.len());
+    }
+    Ok(reader)
+}
+
+/// Reads `path` without any UTF-8 byte order mark or the whitespace around
+/// its contents, such as a trailing CRLF.
+fn read_text_file(path: &std::path::Path) -> std::io::Result<String> {
+    let text = fs::read_to_string(path)?;
+    let text = text.strip_prefix('\u{feff}').unwrap_or(&text);
+    Ok(text.trim().to_string())
+}
+
+/// Characters of context shown either side of a JSON syntax error.
+const SNIPPET_RADIUS: usize = 40;
+
+/// The line of `source` a JSON syntax error is on, trimmed to
+/// [`SNIPPET_RADIUS`] around the error with a caret under its column.
+/// `None` for errors that aren't about the text (e.g. a missing field).
+fn json_error_snippet(source: impl std::io::BufRead, err: &serde_json::Error) -> Option<String> {
+    use serde_json::error::Category;
+
+    if !matches!(err.classify(), Category::Syntax | Category::Eof) || err.line() == 0 {
+        return None;
+    }
+    let text = source.lines().nth(err.line() - 1)?.ok()?;
+    let chars: Vec<char> = text.chars().collect();
+    let column = err.column().saturating_sub(1).min(chars.len());
+    let start = column.saturating_sub(SNIPPET_RADIUS);
+    let end = (column + SNIPPET_RADIUS).min(chars.len());
+    let lead = if start > 0 { "…" } else { "" };
+    let trail = if end < chars.len() { "…" } else { "" };
+    let excerpt: String = chars[start..end].iter().collect();
+    let gutter = err.line().to_string();
+    Some(format!(
+        "{} | {}{}{}\n{} | {}^",
+        gutter,
+        lead,
+        excerpt,
+        trail,
+        " ".repeat(gutter.len()),
+        " ".repeat(lead.chars().count() + column - start)
+    ))
+}
+
+/// The ABI of an ABI file: a plain ABI array, or the `abi` (forge) or
+/// `entrys` (node) member of an object, skipping the object's other members.
+struct AbiFile(serde_json::Value);
+
+impl<'de> Deserialize<'de> for AbiFile {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct AbiVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for AbiVisitor {
+            type Value = AbiFile;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("an ABI array or an object with an `abi` array")
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(self, seq: A) -> Result<AbiFile, A::Error> {
+                let entries = Vec::deserialize(serde::de::value::SeqAccessDeserializer::new(seq))?;
+                Ok(AbiFile(serde_json::Value::Array(entries)))
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> Result<AbiFile, A::Error> {
+                let mut abi = serde_json::Map::new();
+                while let Some(key) = map.next_key::<String>()? {
+                    if key == "abi" || key == "entrys" {
+                        abi.insert(key, map.next_value()?);
+                    } else {
+                        map.next_value::<serde::de::IgnoredAny>()?;
+                    }
+                }
+                Ok(AbiFile(serde_json::Value::Object(abi)))
+            }
+        }
+
+        deserializer.deserialize_any(AbiVisitor)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BytecodeObject {
+    object: String,
+    /// Byte ranges of immutable variables, keyed by AST id (runtime code only)
+    #[serde(rename = "immutableReferences", default)]
+    immutable_references: HashMap<String, Vec<ImmutableReference>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImmutableReference {
+    start: usize,
+    length: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct DeployContractRequest {
+    owner_address: String,
+    fee_limit: u64,
+    call_value: u64,
+    consume_user_resource_percent: u64,
+    origin_energy_limit: u64,
+    abi: String,
+    bytecode: String,
+    parameter: String,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BroadcastResponse {
+    result: Option<bool>,
+    #[allow(dead_code)]
+    txid: Option<String>,
+    code: Option<String>,
+    message: Option<String>,
+}
+
+/// How transaction-building endpoints (`/wallet/deploycontract`,
+/// `/wallet/triggersmartcontract`, ...) shape their responses. This differs
+/// between java-tron versions, Shasta/Nile and private nodes.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum ApiVersion {
+    /// Detect the layout from each response
+    Auto,
+    /// Transaction nested under a `transaction` key
+    Legacy,
+    /// Transaction fields (`txID`, `raw_data`, ...) at the root
+    Current,
+}
+
+/// How contract calls are encoded in `/wallet/triggersmartcontract` and
+/// `/wallet/triggerconstantcontract` requests. Nodes differ in which form
+/// they accept.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum CallEncoding {
+    /// `function_selector` signature plus hex `parameter`; the node computes the selector
+    Split,
+    /// A single `data` field: the 4-byte selector followed by the encoded parameters
+    Data,
+}
+
+/// Node connection settings shared by every command.
+#[derive(Clone)]
+struct ClientOptions {
+    api_version: ApiVersion,
+    call_encoding: CallEncoding,
+    transport: std::sync::Arc<dyn Transport>,
+    /// `--explorer-url`; see [`explorer_link`]
+    explorer_url: Option<String>,
+    /// `--attempts`: tries per broadcast; see [`TronClient::broadcast_with_retry`]
+    broadcast_attempts: u32,
+    /// `--address-prefix`: the chain's address prefix byte
+    address_prefix: u8,
+}
+
+type BoxFuture<'a, T> = std::pin::Pin<Box<dyn Future<Output = T> + 'a>>;
+
+/// One target's result in a command over several targets (`balances`).
+struct TargetOutcome<'a, T> {
+    target: &'a str,
+    result: Result<T>,
+}
+
+/// Runs `query` on every target concurrently on the current task and
+/// returns their outcomes in target order. With `fail_fast`, the first
+/// failure to come back is returned instead and the other queries are
+/// dropped.
+async fn query_targets<'a, T>(
+    targets: &'a [String],
+    fail_fast: bool,
+    query: impl Fn(&'a str) -> BoxFuture<'a, Result<T>>,
+) -> Result<Vec<TargetOutcome<'a, T>>> {
+    let mut pending: Vec<Option<BoxFuture<'a, Result<T>>>> =
+        targets.iter().map(|target| Some(query(target))).collect();
+    let mut results: Vec<Option<Result<T>>> = targets.iter().map(|_| None).collect();
+    std::future::poll_fn(|cx| {
+        for ((future, result), target) in pending.iter_mut().zip(&mut results).zip(targets) {
+            if let Some(running) = future {
+                if let std::task::Poll::Ready(value) = running.as_mut().poll(cx) {
+                    *future = None;
+                    match value {
+                        Err(err) if fail_fast => {
+                            return std::task::Poll::Ready(Err(err.context(target.clone())))
+                        }
+                        value => *result = Some(value),
+                    }
+                }
+            }
+        }
+        match pending.iter().all(Option::is_none) {
+            true => std::task::Poll::Ready(Ok(())),
+            false => std::task::Poll::Pending,
+        }
+    })
+    .await?;
+    Ok(targets
+        .iter()
+        .zip(results)
+        .map(|(target, result)| TargetOutcome {
+            target,
+            result: result.expect("every query completed"),
+        })
+        .collect())
+}
+
+/// A node's reply: HTTP status and raw body.
+#[derive(Clone, Debug, PartialEq)]
+struct RawResponse {
+    status: u16,
+    body: String,
+    /// A `Retry-After` header in seconds, sent with HTTP 429. The HTTP-date
+    /// form isn't used by TronGrid and is ignored
+    retry_after: Option<std::time::Duration>,
+}
+
+/// Carries a JSON request to a node endpoint. `TronClient` handles logging and
+/// parsing; implementations only move bytes, so recording and replay can be
+/// swapped in without touching the commands.
+trait Transport: Send + Sync {
+    /// POSTs `body` to `path` (e.g. `/wallet/getaccount`) under `rpc_url`.
+    fn post<'a>(
+        &'a self,
+        rpc_url: &'a str,
+        path: &'a str,
+        body: &'a serde_json::Value,
+    ) -> BoxFuture<'a, Result<RawResponse>>;
+}
+
+/// Default `--max-body-size`: far above any real node response, low enough
+/// that a misbehaving endpoint can't exhaust memory.
+const DEFAULT_MAX_BODY_SIZE: usize = 8 * 1024 * 1024;
+
+/// The network.
+struct HttpTransport {
+    client: reqwest::Client,
+    /// Responses longer than this are abandoned mid-stream
+    max_body_size: usize,
+    /// `--api-key`
+    api_key: Option<String>,
+}
+
+impl Transport for HttpTransport {
+    fn post<'a>(
+        &'a self,
+        rpc_url: &'a str,
+        path: &'a str,
+        body: &'a serde_json::Value,
+    ) -> BoxFuture<'a, Result<RawResponse>> {
+        Box::pin(async move {
+            let url = format!("{}{}", rpc_url, path);
+            let mut request = self.client.post(&url).json(body);
+            if let Some(key) = &self.api_key {
+                request = request.header("TRON-PRO-API-KEY", key);
+            }
+            let mut response = request.send().await?;
+            let too_large = || {
+                anyhow::Error::from(TronError::Rpc(format!(
+                    "Response from {} exceeds --max-body-size ({} bytes)",
+                    url, self.max_body_size
+                )))
+            };
+            if response
+                .content_length()
+                .is_some_and(|len| len > self.max_body_size as u64)
+            {
+                return Err(too_large());
+            }
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok()?.trim().parse().ok())
+                .map(std::time::Duration::from_secs);
+
+            let mut bytes = Vec::new();
+            while let Some(chunk) = response.chunk().await? {
+                if bytes.len() + chunk.len() > self.max_body_size {
+                    return Err(too_large());
+                }
+                bytes.extend_from_slice(&chunk);
+            }
+            Ok(RawResponse {
+                status: response.status().as_u16(),
+                body: String::from_utf8_lossy(&bytes).into_owned(),
+                retry_after,
+            })
+        })
+    }
+}
+
+/// One recorded request/response pair, stored as `NNNN.json` in the
+/// `--record` directory. The node URL is not part of it, so a capture replays
+/// against any `--rpc-url`.
+#[derive(Serialize, Deserialize)]
+struct Exchange {
+    path: String,
+    request: serde_json::Value,
+    status: u16,
+    response: String,
+}
+
+/// Forwards to `inner` and writes each exchange to `dir`.
+struct RecordingTransport {
+    inner: std::sync::Arc<dyn Transport>,
+    dir: PathBuf,
+    count: std::sync::atomic::AtomicUsize,
+}
+
+impl Transport for RecordingTransport {
+    fn post<'a>(
+        &'a self,
+        rpc_url: &'a str,
+        path: &'a str,
+        body: &'a serde_json::Value,
+    ) -> BoxFuture<'a, Result<RawResponse>> {
+        Box::pin(async move {
+            let response = self.inner.post(rpc_url, path, body).await?;
+            let index = self.count.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let exchange = Exchange {
+                path: path.to_string(),
+                request: body.clone(),
+                status: response.status,
+                response: response.body.clone(),
+            };
+            fs::create_dir_all(&self.dir)
+                .with_context(|| format!("Failed to create record directory: {:?}", self.dir))?;
+            let file = self.dir.join(format!("{:04}.json", index + 1));
+            fs::write(&file, serde_json::to_string_pretty(&exchange)?)
+                .with_context(|| format!("Failed to write recording: {:?}", file))?;
+            Ok(response)
+        })
+    }
+}
+
+/// Serves exchanges recorded in `dir`. A request is answered by the first
+/// unused exchange with the same path and body, so repeated identical
+/// requests (e.g. polling) get their responses in recorded order.
+struct ReplayTransport {
+    dir: PathBuf,
+    /// Loaded on the first request; `None` marks a used exchange
+    exchanges: tokio::sync::OnceCell<std::sync::Mutex<Vec<Option<Exchange>>>>,
+}
+
+impl ReplayTransport {
+    fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            exchanges: tokio::sync::OnceCell::new(),
+        }
+    }
+
+    fn load(dir: &std::path::Path) -> Result<Vec<Option<Exchange>>> {
+        let mut files: Vec<PathBuf> = fs::read_dir(dir)
+            .with_context(|| format!("Failed to read replay directory: {:?}", dir))?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<std::io::Result<_>>()?;
+        files.retain(|f| f.extension().is_some_and(|ext| ext == "json"));
+        files.sort();
+        files
+            .iter()
+            .map(|file| {
+                let data = read_text_file(file)
+                    .with_context(|| format!("Failed to read recording: {:?}", file))?;
+                let exchange = serde_json::from_str(&data)
+                    .with_context(|| format!("Failed to parse recording: {:?}", file))?;
+                Ok(Some(exchange))
+            })
+            .collect()
+    }
+}
+
+impl Transport for ReplayTransport {
+    fn post<'a>(
+        &'a self,
+        _rpc_url: &'a str,
+        path: &'a str,
+        body: &'a serde_json::Value,
+    ) -> BoxFuture<'a, Result<RawResponse>> {
+        Box::pin(async move {
+            let exchanges = self
+                .exchanges
+                .get_or_try_init(|| async { Self::load(&self.dir).map(std::sync::Mutex::new) })
+                .await?;
+            let mut exchanges = exchanges.lock().expect("replay exchanges poisoned");
+            let slot = exchanges
+                .iter_mut()
+                .find(|slot| {
+                    slot.as_ref()
+                        .is_some_and(|e| e.path == path && &e.request == body)
+                })
+                .ok_or_else(|| {
+                    anyhow!(
+                        "No recorded response left in {:?} for POST {} with body {}",
+                        self.dir,
+                        path,
+                        body
+                    )
+                })?;
+            let exchange = slot.take().expect("slot is unused");
+            Ok(RawResponse {
+                status: exchange.status,
+                body: exchange.response,
+                retry_after: None,
+            })
+        })
+    }
+}
+
+/// Per-key single-flight cache: concurrent lookups of the same key share one
+/// in-flight request, and a successful result is reused for the session.
+/// Failed lookups are not cached, so the next caller retries.
+struct SingleFlight<V> {
+    cells: std::sync::Mutex<HashMap<String, std::sync::Arc<tokio::sync::OnceCell<V>>>>,
+}
+
+impl<V: Clone> SingleFlight<V> {
+    fn new() -> Self {
+        Self {
+            cells: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    async fn get_or_try_init<F, Fut>(&self, key: &str, init: F) -> Result<V>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<V>>,
+    {
+        let cell = self
+            .cells
+            .lock()
+            .expect("single-flight cache poisoned")
+            .entry(key.to_string())
+            .or_default()
+            .clone();
+        cell.get_or_try_init(init).await.cloned()
+    }
+}
+
+/// Pause before a broadcast is retried, long enough for a block (3s) to be
+/// likely to include a transaction the lost reply had accepted.
+const BROADCAST_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// HTTP 429 Too Many Requests.
+const STATUS_RATE_LIMITED: u16 = 429;
+
+/// Times a rate-limited request is retried before giving up.
+const RATE_LIMIT_RETRIES: u32 = 3;
+
+/// Wait before retrying a rate-limited request that has no `Retry-After`.
+const RATE_LIMIT_DELAY: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Longest `Retry-After` honoured, so a misconfigured proxy can't stall a run.
+const MAX_RETRY_AFTER: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Thin wrapper over the TRON node's HTTP API.
+struct TronClient {
+    rpc_url: String,
+    out: Output,
+    options: ClientOptions,
+    /// Token decimals by token address
+    token_decimals: SingleFlight<u32>,
+    /// On-chain ABIs by contract address
+    contract_abis: SingleFlight<ContractAbi>,
+    /// `/wallet/getchainparameters`, fetched once per session
+    chain_parameters: SingleFlight<HashMap<String, i64>>,
+    /// Multisig configurations by contract address (hex)
+    multisig_infos: SingleFlight<MultisigInfo>,
+    /// Genesis block ID, fetched once per session
+    chain_id: SingleFlight<String>,
+    /// txIDs of the transactions built this session, see [`distinct_tx_id`]
+    built_tx_ids: std::sync::Mutex<HashSet<String>>,
+}
+
+impl TronClient {
+    fn new(rpc_url: &str, out: &Output, options: &ClientOptions) -> Self {
+        Self {
+            rpc_url: rpc_url.trim_end_matches('/').to_string(),
+            out: *out,
+            options: options.clone(),
+            token_decimals: SingleFlight::new(),
+            contract_abis: SingleFlight::new(),
+            chain_parameters: SingleFlight::new(),
+            multisig_infos: SingleFlight::new(),
+            chain_id: SingleFlight::new(),
+            built_tx_ids: std::sync::Mutex::new(HashSet::new()),
+        }
+    }
+
+    fn explorer_link(&self, link: ExplorerLink) -> Option<String> {
+        explorer_link(self.options.explorer_url.as_deref(), &self.rpc_url, link)
+    }
+
+    /// Broadcasts `signed_tx`, retrying up to `--attempts` times when the
+    /// request fails without a usable reply. The reply may have been lost
+    /// after the node accepted the transaction, and as transactions are
+    /// identified by their txID a retry first asks for that txID: if it
+    /// confirmed, or the node calls the resend a duplicate, the broadcast
+    /// already succeeded and the node's success reply is stood in for.
+    async fn broadcast_with_retry(
+        &self,
+        signed_tx: &serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let tx_id = signed_tx.get("txID").and_then(|v| v.as_str());
+        let attempts = if tx_id.is_some() {
+            self.options.broadcast_attempts
+        } else {
+            1
+        };
+        let accepted = || serde_json::json!({ "result": true, "txid": tx_id });
+        let mut attempt = 1;
+        loop {
+            if attempt > 1 {
+                let tx_id = tx_id.unwrap_or_default();
+                let info = self.get_tx_info(tx_id).await.unwrap_or_default();
+                if info.as_object().is_some_and(|fields| !fields.is_empty()) {
+                    self.out.phase(format!(
+                        "Transaction {} is already on chain; not broadcasting it again",
+                        tx_id
+                    ));
+                    return Ok(accepted());
+                }
+            }
+            match self.post("/wallet/broadcasttransaction", signed_tx).await {
+                Ok(response) if attempt > 1 && response["code"] == "DUP_TRANSACTION_ERROR" => {
+                    self.out
+                        .phase("The node already has the transaction from an earlier attempt");
+                    return Ok(accepted());
+                }
+                Ok(response) => return Ok(response),
+                Err(err) if attempt < attempts => {
+                    self.out.warn(format!(
+                        "⚠️  Broadcast attempt {} of {} failed: {:#}; retrying",
+                        attempt, attempts, err
+                    ));
+                    tokio::time::sleep(BROADCAST_RETRY_DELAY).await;
+                    attempt += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// POSTs a JSON body to `path` (e.g. `/wallet/getaccount`) and parses the JSON response.
+    async fn post(&self, path: &str, body: &impl Serialize) -> Result<serde_json::Value> {
+        let url = format!("{}{}", self.rpc_url, path);
+        self.out.detail(format!("→ POST {}", url));
+        if self.out.verbosity >= 3 {
+            self.out
+                .trace(serde_json::to_string_pretty(body).unwrap_or_default());
+        }
+
+        let body = serde_json::to_value(body)?;
+        let mut retries = 0;
+        let response_text = loop {
+            let response = self
+                .options
+                .transport
+                .post(&self.rpc_url, path, &body)
+                .await?;
+            self.out.detail(format!(
+                "← {} ({} bytes)",
+                response.status,
+                response.body.len()
+            ));
+            self.out.trace(&response.body);
+            if response.status != STATUS_RATE_LIMITED {
+                break response.body;
+            }
+            // A rate-limited request wasn't processed (not even a
+            // broadcast), so it is safe to send again once allowed
+            if retries == RATE_LIMIT_RETRIES {
+                return Err(TronError::Rpc(format!(
+                    "{} is rate limiting requests (HTTP 429), still after {} retries. \
+                     Public endpoints limit anonymous clients; pass --api-key with a TronGrid API key",
+                    self.rpc_url, RATE_LIMIT_RETRIES
+                ))
+                .into());
+            }
+            let delay = response
+                .retry_after
+                .unwrap_or(RATE_LIMIT_DELAY)
+                .min(MAX_RETRY_AFTER);
+            self.out
+                .phase(format!("Rate limited, retrying in {}s", delay.as_secs()));
+            tokio::time::sleep(delay).await;
+            retries += 1;
+        };
+
+        serde_json::from_str(&response_text).map_err(|err| {
+            TronError::Rpc(format!(
+                "Failed to parse response: {}: {}",
+                response_text, err
+            ))
+            .into()
+        })
+    }
+}
+
+/// The node calls behind deploying and following multisig transactions.
+/// `TronClient` implements it over HTTP; tests substitute canned responses.
+/// Methods return the node's JSON, so interpreting it (errors, response
+/// layouts) stays in the shared code paths.
+trait TronRpc: Send + Sync {
+    /// `/wallet/getcontract` for a base58 address.
+    fn get_contract<'a>(&'a self, contract: &'a str) -> BoxFuture<'a, Result<serde_json::Value>>;
+
+    /// Chain parameters (`getEnergyFee`, `getMaxFeeLimit`, ...) by key.
+    fn chain_parameters(&self) -> BoxFuture<'_, Result<HashMap<String, i64>>>;
+
+    /// `/wallet/deploycontract`: builds an unsigned deployment transaction.
+    fn deploy_contract<'a>(
+        &'a self,
+        request: &'a DeployContractRequest,
+    ) -> BoxFuture<'a, Result<serde_json::Value>>;
+
+    /// `/wallet/triggerconstantcontract` on a hex (`41...`) contract address.
+    fn trigger_constant<'a>(
+        &'a self,
+        contract_hex: &'a str,
+        function_selector: &'a str,
+        parameter: &'a str,
+    ) -> BoxFuture<'a, Result<serde_json::Value>>;
+
+    /// `/wallet/broadcasttransaction`.
+    fn broadcast<'a>(
+        &'a self,
+        signed_tx: &'a serde_json::Value,
+    ) -> BoxFuture<'a, Result<serde_json::Value>>;
+
+    /// `/wallet/gettransactioninfobyid`.
+    fn get_tx_info<'a>(&'a self, txid: &'a str) -> BoxFuture<'a, Result<serde_json::Value>>;
+
+    /// Token, owners and threshold of a multisig ([`fetch_multisig_info`]).
+    /// The contract can't change them after deployment, so `TronClient`
+    /// reads them once per session however many commands need them.
+    fn multisig_info<'a>(&'a self, contract: &'a str) -> BoxFuture<'a, Result<MultisigInfo>>;
+
+    /// The genesis block ID (lowercase hex), which identifies the network.
+    fn chain_id(&self) -> BoxFuture<'_, Result<String>>;
+
+    /// The network's address prefix byte, for encoding and checking base58
+    /// addresses.
+    fn address_prefix(&self) -> u8;
+}
+
+impl TronRpc for TronClient {
+    fn get_contract<'a>(&'a self, contract: &'a str) -> BoxFuture<'a, Result<serde_json::Value>> {
+        let body = serde_json::json!({ "value": contract, "visible": true });
+        Box::pin(async move { self.post("/wallet/getcontract", &body).await })
+    }
+
+    fn chain_parameters(&self) -> BoxFuture<'_, Result<HashMap<String, i64>>> {
+        Box::pin(self.chain_parameters.get_or_try_init("", || async {
+            let response = self
+                .post("/wallet/getchainparameters", &serde_json::json!({}))
+                .await?;
+            let params = response
+                .get("chainParameter")
+                .and_then(|p| p.as_array())
+                .ok_or_else(|| anyhow!("No chainParameter in response"))?;
+            // Parameters with a zero value are returned without a `value` field
+            Ok(params
+                .iter()
+                .filter_map(|p| {
+                    let key = p.get("key")?.as_str()?.to_string();
+                    let value = p.get("value").and_then(|v| v.as_i64()).unwrap_or(0);
+                    Some((key, value))
+                })
+                .collect())
+        }))
+    }
+
+    fn deploy_contract<'a>(
+        &'a self,
+        request: &'a DeployContractRequest,
+    ) -> BoxFuture<'a, Result<serde_json::Value>> {
+        Box::pin(self.post("/wallet/deploycontract", request))
+    }
+
+    fn trigger_constant<'a>(
+        &'a self,
+        contract_hex: &'a str,
+        function_selector: &'a str,
+        parameter: &'a str,
+    ) -> BoxFuture<'a, Result<serde_json::Value>> {
+        // View calls need a caller; the contract itself is always a valid address
+        let body = contract_call_body(
+            contract_hex,
+            contract_hex,
+            function_selector,
+            parameter,
+            self.options.call_encoding,
+        );
+        Box::pin(async move { self.post("/wallet/triggerconstantcontract", &body).await })
+    }
+
+    fn broadcast<'a>(
+        &'a self,
+        signed_tx: &'a serde_json::Value,
+    ) -> BoxFuture<'a, Result<serde_json::Value>> {
+        Box::pin(self.broadcast_with_retry(signed_tx))
+    }
+
+    fn get_tx_info<'a>(&'a self, txid: &'a str) -> BoxFuture<'a, Result<serde_json::Value>> {
+        let body = serde_json::json!({ "value": txid });
+        Box::pin(async move { self.post("/wallet/gettransactioninfobyid", &body).await })
+    }
+
+    fn multisig_info<'a>(&'a self, contract: &'a str) -> BoxFuture<'a, Result<MultisigInfo>> {
+        Box::pin(async move {
+            let key = tron_address_to_hex(contract, self.address_prefix())?;
+            self.multisig_infos
+                .get_or_try_init(&key, || fetch_multisig_info(self, contract))
+                .await
+        })
+    }
+
+    fn chain_id(&self) -> BoxFuture<'_, Result<String>> {
+        Box::pin(self.chain_id.get_or_try_init("", || async {
+            let genesis = self
+                .post("/wallet/getblockbynum", &serde_json::json!({ "num": 0 }))
+                .await?;
+            genesis
+                .get("blockID")
+                .and_then(|v| v.as_str())
+                .map(str::to_lowercase)
+                .ok_or_else(|| anyhow!("No blockID in genesis block"))
+        }))
+    }
+
+    fn address_prefix(&self) -> u8 {
+        self.options.address_prefix
+    }
+}
+
+/// On-chain state of an account, from `/wallet/getaccount`.
+struct AccountInfo {
+    activated: bool,
+    balance_sun: u64,
+}
+
+/// Configuration of a deployed USDTMultisig, read from its view functions.
+#[derive(Clone)]
+struct MultisigInfo {
+    usdt: String,
+    owners: Vec<String>,
+    threshold: u64,
+}
+
+/// A decoded TRC20 `Transfer(address,address,uint256)` event.
+#[derive(Debug, PartialEq)]
+struct TransferEvent {
+    token: String,
+    from: String,
+    to: String,
+    amount: u128,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to decorate output. `auto` follows https://no-color.org: a
+    /// non-empty `NO_COLOR` turns decoration off, as does a non-terminal stdout.
+    fn decorate(self) -> bool {
+        use std::io::IsTerminal;
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+                    && std::io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+/// Renders a message for the terminal. Decorated, result and warning lines
+/// are colored by their marker; plain, emoji become ASCII and banner rules
+/// are dropped. Returns `None` if nothing is left to print.
+fn style_text(text: &str, decorated: bool) -> Option<String> {
+    const GREEN: &str = "\x1b[32m";
+    const YELLOW: &str = "\x1b[33m";
+    const RED: &str = "\x1b[31m";
+    const RESET: &str = "\x1b[0m";
+
+    let mut lines = Vec::new();
+    for line in text.split('\n') {
+        if decorated {
+            let color = if line.contains('✅') {
+                Some(GREEN)
+            } else if line.contains('⚠') {
+                Some(YELLOW)
+            } else if line.starts_with("Error") {
+                Some(RED)
+            } else {
+                None
+            };
+            lines.push(match color {
+                Some(color) if !line.is_empty() => format!("{}{}{}", color, line, RESET),
+                _ => line.to_string(),
+            });
+            continue;
+        }
+
+        if !line.is_empty() && line.chars().all(|c| c == '━') {
+            continue;
+        }
+        let mut plain = String::with_capacity(line.len());
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '⚠' => {
+                    plain.push_str("Warning: ");
+                    while chars.next_if(|&c| c == '\u{fe0f}' || c == ' ').is_some() {}
+                }
+                'ℹ' => {
+                    plain.push_str("Note: ");
+                    while chars.next_if(|&c| c == '\u{fe0f}' || c == ' ').is_some() {}
+                }
+                '✓' => plain.push_str("[x]"),
+                '✗' => plain.push_str("[ ]"),
+                '—' | '━' => plain.push('-'),
+                '•' => plain.push('*'),
+                '→' => plain.push_str("->"),
+                '←' => plain.push_str("<-"),
+                '\u{fe0f}' => {}
+                c if c.is_ascii() => plain.push(c),
+                // Pictographs (✅, 📡, 🔐, ...) and their padding
+                _ => while chars.next_if_eq(&' ').is_some() {},
+            }
+        }
+        lines.push(plain);
+    }
+    if !text.is_empty() && lines.iter().all(String::is_empty) {
+        return None;
+    }
+    Some(lines.join("\n"))
+}
+
+/// Routes command output to either human-readable text or a single JSON document.
+///
+/// Results go to stdout; progress and diagnostics go to stderr, gated by `-v`.
+#[derive(Clone, Copy)]
+struct Output {
+    json: bool,
+    /// `--json-compact`: JSON on one line instead of indented
+    compact: bool,
+    /// `--quiet`: result lines are replaced by [`quiet_value`]
+    quiet: bool,
+    verbosity: u8,
+    /// Emoji, banners and colors (`--color`)
+    decorated: bool,
+}
+
+impl Output {
+    /// Prints a line of the command's result. Suppressed in `--json` and
+    /// `--quiet` mode so stdout only ever carries the final JSON document or value.
+    fn line(&self, text: impl std::fmt::Display) {
+        if !self.json && !self.quiet {
+            if let Some(text) = style_text(&text.to_string(), self.decorated) {
+                println!("{}", text);
+            }
+        }
+    }
+
+    /// Progress messages (`-v`).
+    fn phase(&self, text: impl std::fmt::Display) {
+        self.diagnostic(1, text);
+    }
+
+    /// Request/response summaries (`-vv`).
+    fn detail(&self, text: impl std::fmt::Display) {
+        self.diagnostic(2, text);
+    }
+
+    /// Full request/response bodies (`-vvv`), printed verbatim.
+    fn trace(&self, text: impl std::fmt::Display) {
+        if self.verbosity >= 3 {
+            eprintln!("{}", text);
+        }
+    }
+
+    /// Warnings, always printed to stderr (even with `--json`).
+    fn warn(&self, text: impl std::fmt::Display) {
+        if let Some(text) = style_text(&text.to_string(), self.decorated) {
+            eprintln!("{}", text);
+        }
+    }
+
+    fn diagnostic(&self, level: u8, text: impl std::fmt::Display) {
+        if self.verbosity >= level {
+            if let Some(text) = style_text(&text.to_string(), self.decorated) {
+                eprintln!("{}", text);
+            }
+        }
+    }
+
+    fn emit_json(&self, value: &serde_json::Value) {
+        if self.compact {
+            println!("{}", value);
+        } else {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+            );
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let out = Output {
+        json: cli.json || cli.json_compact,
+        compact: cli.json_compact,
+        quiet: cli.quiet,
+        verbosity: cli.verbose,
+        decorated: cli.color.decorate(),
+    };
+
+    let http: std::sync::Arc<dyn Transport> = std::sync::Arc::new(HttpTransport {
+        client: reqwest::Client::new(),
+        max_body_size: cli.max_body_size,
+        api_key: cli.api_key,
+    });
+    let transport: std::sync::Arc<dyn Transport> = match (cli.record, cli.replay) {
+        (_, Some(dir)) => std::sync::Arc::new(ReplayTransport::new(dir)),
+        (Some(dir), None) => std::sync::Arc::new(RecordingTransport {
+            inner: http,
+            dir,
+            count: Default::default(),
+        }),
+        (None, None) => http,
+    };
+    let options = ClientOptions {
+        api_version: cli.api_version,
+        call_encoding: cli.call_encoding,
+        transport,
+        explorer_url: cli.explorer_url,
+        broadcast_attempts: cli.attempts,
+        address_prefix: cli.address_prefix,
+    };
+
+    let quiet_key = cli.command.quiet_key();
+    match run(cli.command, &out, &options).await {
+        Ok(result) => {
+            if out.json {
+                out.emit_json(&success_envelope(result));
+            } else if out.quiet {
+                if let Some(value) = quiet_value(&result, quiet_key, options.address_prefix) {
+                    println!("{}", value);
+                }
+            }
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            let code = error_code(&err);
+            if out.json {
+                out.emit_json(&serde_json::json!({
+                    "ok": false,
+                    "error": format!("{:#}", err),
+                    "code": code
+                }));
+            } else {
+                out.warn(format!("Error: {:?}", err));
+            }
+            ExitCode::from(code)
+        }
+    }
+}
+
+async fn run(
+    command: Commands,
+    out: &Output,
+    options: &ClientOptions,
+) -> Result<serde_json::Value> {
+    let prefix = options.address_prefix;
+    match command {
+        Commands::GenerateKey {
+            entropy,
+            from_password,
+            salt,
+            argon2_memory,
+            argon2_iterations,
+            out_file,
+            keystore,
+            force,
+        } => {
+            let destination = match (out_file.as_deref(), keystore.as_deref()) {
+                (Some(path), _) => KeyDestination::File(path),
+                (None, Some(path)) => KeyDestination::Keystore(path),
+                (None, None) => KeyDestination::Print,
+            };
+            let source = match (entropy.as_deref(), salt.as_deref()) {
+                (Some(entropy), _) => KeySource::Entropy(entropy),
+                (None, Some(salt)) if from_password => KeySource::Password {
+                    salt,
+                    memory_mib: argon2_memory,
+                    iterations: argon2_iterations,
+                },
+                _ => KeySource::Random,
+            };
+            generate_private_key(out, &source, destination, force, prefix)
+        }
+        Commands::Deploy(args) => deploy_contract(out, options, &args).await,
+        Commands::Address {
+            private_key,
+            rpc_url,
+            format,
+        } => {
+            let address = private_key_to_tron_address(&private_key, prefix)?;
+            let formatted = format_address(&parse_address(&address, prefix)?, format, prefix);
+            out.line(format!("TRON Address: {}", address));
+            if format != AddressFormat::Base58 {
+                out.line(format!(
+                    "{:<13} {}",
+                    format!("{}:", address_format_label(format)),
+                    formatted
+                ));
+            }
+            let Some(rpc_url) = rpc_url else {
+                return Ok(serde_json::json!({ "address": address, "formatted": formatted }));
+            };
+
+            let account = get_account(&TronClient::new(&rpc_url, out, options), &address).await?;
+            out.line(format!(
+                "Balance:      {} TRX",
+                format_trx(account.balance_sun)
+            ));
+            if account.activated {
+                out.line("Activated:    yes");
+            } else {
+                out.line("Activated:    no");
+                out.line("\n⚠️  This account is not activated on-chain. Send it some TRX before deploying.");
+            }
+            Ok(serde_json::json!({
+                "address": address,
+                "formatted": formatted,
+                "balanceSun": account.balance_sun,
+                "activated": account.activated
+            }))
+        }
+        Commands::FromMnemonic {
+            mnemonic,
+            path,
+            account,
+            index,
+        } => {
+            let (path, private_key) = mnemonic_private_key(&mnemonic, &path, account, index)?;
+            let address = private_key_to_tron_address(&private_key, prefix)?;
+            out.line(format!("TRON Address: {}", address));
+            out.line(format!("Path:         {}", path));
+            Ok(serde_json::json!({ "address": address, "path": path.to_string() }))
+        }
+        Commands::ExportPubkey {
+            private_key,
+            compressed,
+            uncompressed: _,
+        } => {
+            let public_key = public_key_from_private_key(&private_key)?;
+            let (format, public_key_hex) = if compressed {
+                ("compressed", hex::encode(public_key.serialize()))
+            } else {
+                (
+                    "uncompressed",
+                    hex::encode(public_key.serialize_uncompressed()),
+                )
+            };
+            out.line(format!("Public Key: {}", public_key_hex));
+            out.line(format!("Format:     {}", format));
+            Ok(serde_json::json!({
+                "publicKey": public_key_hex,
+                "format": format,
+                "address": public_key_to_tron_address(&public_key, prefix)
+            }))
+        }
+        Commands::VerifyTransfer {
+            rpc_url,
+            txid,
+            expected_to,
+            expected_amount,
+            expected_amount_tokens,
+            decimals,
+            usdt,
+        } => {
+            let client = TronClient::new(&rpc_url, out, options);
+            let expected_amount = resolve_amount(
+                &client,
+                expected_amount,
+                expected_amount_tokens.as_deref(),
+                decimals,
+                usdt.as_deref(),
+            )
+            .await?;
+            verify_transfer(
+                out,
+                &client,
+                &txid,
+                &expected_to,
+                expected_amount,
+                usdt.as_deref(),
+            )
+            .await
+        }
+        Commands::Receipt { rpc_url, txid } => {
+            let client = TronClient::new(&rpc_url, out, options);
+            show_receipt(out, &client, &txid).await
+        }
+        Commands::Delegations { rpc_url, address } => {
+            let client = TronClient::new(&rpc_url, out, options);
+            show_delegations(out, &client, &address).await
+        }
+        Commands::ChainParams { rpc_url } => {
+            show_chain_params(out, &TronClient::new(&rpc_url, out, options)).await
+        }
+        Commands::ResourcePrices { rpc_url, recent } => {
+            show_resource_prices(out, &TronClient::new(&rpc_url, out, options), recent).await
+        }
+        Commands::Call {
+            rpc_url,
+            contract,
+            function,
+            args,
+            parameter_hex,
+            abi,
+            abi_from_chain,
+        } => {
+            let client = TronClient::new(&rpc_url, out, options);
+            let abi = if abi_from_chain {
+                fetch_contract_abi(&client, &contract).await?
+            } else {
+                load_abi_file(abi.as_deref())?
+            };
+            match parameter_hex {
+                Some(parameter) => {
+                    call_contract_raw(out, &client, &abi, &contract, &function, &parameter).await
+                }
+                None => call_contract(out, &client, &abi, &contract, &function, &args).await,
+            }
+        }
+        Commands::DecodeInput { data, abi } => {
+            decode_input(out, &load_abi_file(abi.as_deref())?, &data, prefix)
+        }
+        Commands::VerifyBytecode {
+            rpc_url,
+            contract,
+            artifact,
+        } => {
+            let client = TronClient::new(&rpc_url, out, options);
+            verify_bytecode(out, &client, &contract, &artifact).await
+        }
+        Commands::Owners { rpc_url, contract } => {
+            validate_tron_address(&contract, prefix).context("Invalid contract address")?;
+            let client = TronClient::new(&rpc_url, out, options);
+            let info = client.multisig_info(&contract).await?;
+            out.line(format!("👥 Owners of {}", contract));
+            for (i, owner) in info.owners.iter().enumerate() {
+                out.line(format!("   {}. {}", i, owner));
+            }
+            out.line(format!(
+                "   Threshold: {} of {}",
+                info.threshold,
+                info.owners.len()
+            ));
+            out.line(format!("   USDT:      {}", info.usdt));
+            Ok(serde_json::json!({
+                "contract": contract,
+                "owners": info.owners,
+                "threshold": info.threshold,
+                "usdt": info.usdt
+            }))
+        }
+        Commands::OwnerIndex {
+            rpc_url,
+            contract,
+            address,
+        } => {
+            let address_hex = tron_address_to_hex(&address, prefix)?;
+            let client = TronClient::new(&rpc_url, out, options);
+            let owners = decode_address_array(
+                &trigger_constant(&client, &contract, "getOwners()", "").await?,
+                prefix,
+            )?;
+            let index = owners.iter().position(|owner| {
+                tron_address_to_hex(owner, prefix).ok().as_deref() == Some(&*address_hex)
+            });
+
+            match index {
+                Some(index) => out.line(format!("Owner index: {} (of {})", index, owners.len())),
+                None => out.line(format!("{} is not an owner", address)),
+            }
+            Ok(serde_json::json!({
+                "address": address,
+                "isOwner": index.is_some(),
+                "index": index,
+                "ownerCount": owners.len()
+            }))
+        }
+        Commands::Balances {
+            rpc_url,
+            contracts,
+            failures,
+        } => {
+            let contracts: Vec<String> = contracts
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+            let client = TronClient::new(&rpc_url, out, options);
+            show_balances(out, &client, &contracts, failures.fail_fast).await
+        }
+        Commands::Transaction {
+            rpc_url,
+            contract,
+            tx_id,
+        } => {
+            let client = TronClient::new(&rpc_url, out, options);
+            show_multisig_transaction(out, &client, &contract, tx_id).await
+        }
+        Commands::Approvals {
+            rpc_url,
+            contract,
+            tx_id,
+        } => {
+            let client = TronClient::new(&rpc_url, out, options);
+            show_approvals(out, &client, &contract, tx_id).await
+        }
+        Commands::EstimateApprovals {
+            rpc_url,
+            contract,
+            tx_id,
+        } => {
+            let client = TronClient::new(&rpc_url, out, options);
+            estimate_approvals(out, &client, &contract, tx_id).await
+        }
+        Commands::History {
+            rpc_url,
+            contract,
+            from_block,
+            to_block,
+            csv,
+        } => {
+            let client = TronClient::new(&rpc_url, out, options);
+            show_history(out, &client, &contract, from_block, to_block, csv).await
+        }
+        Commands::Submit {
+            rpc_url,
+            contract,
+            to,
+            amount,
+            amount_tokens,
+            signer,
+            allow_inactive,
+            wait,
+            fee_limit,
+            cost_summary,
+        } => {
+            let client = TronClient::new(&rpc_url, out, options);
+            let payout = Payout {
+                contract: &contract,
+                to: &to,
+                amount,
+                amount_tokens: amount_tokens.as_deref(),
+            };
+            let mut result =
+                submit_transaction(out, &client, &payout, &signer, allow_inactive, fee_limit)
+                    .await?;
+            if wait {
+                result = with_submitted_tx_id(out, &client, result, &contract).await?;
+            }
+            with_cost_summary(out, &client, result, cost_summary).await
+        }
+        Commands::Approve {
+            rpc_url,
+            contract,
+            tx_id,
+            signer,
+            keys_file,
+            skip_owner_check,
+            skip_executed_check,
+            fee_limit,
+            cost_summary,
+        } => {
+            let client = TronClient::new(&rpc_url, out, options);
+            let checks = ApprovalChecks {
+                owner: !skip_owner_check,
+                not_executed: !skip_executed_check,
+            };
+            let signers = match &keys_file {
+                Some(path) => read_keys_file(path)?
+                    .into_iter()
+                    .map(Signer::PrivateKey)
+                    .collect(),
+                None => signer.signers(prefix)?,
+            };
+            if let [signer] = signers.as_slice() {
+                let result =
+                    approve_transaction(out, &client, &contract, tx_id, signer, checks, fee_limit)
+                        .await?;
+                return with_cost_summary(out, &client, result, cost_summary).await;
+            }
+            let approvals = ApprovalRun {
+                contract: &contract,
+                tx_id,
+                checks,
+                fee_limit,
+                cost_summary,
+            };
+            approve_with_each(out, &client, &approvals, &signers).await
+        }
+        Commands::Revoke {
+            rpc_url,
+            contract,
+            tx_id,
+            signer,
+            skip_checks,
+            fee_limit,
+            cost_summary,
+        } => {
+            let client = TronClient::new(&rpc_url, out, options);
+            let signer = signer.signer(prefix)?;
+            let result = revoke_approval(
+                out,
+                &client,
+                &contract,
+                tx_id,
+                &signer,
+                !skip_checks,
+                fee_limit,
+            )
+            .await?;
+            with_cost_summary(out, &client, result, cost_summary).await
+        }
+        Commands::Execute {
+            rpc_url,
+            contract,
+            tx_id,
+            signer,
+            abi,
+            abi_from_chain,
+            fee_limit,
+            cost_summary,
+        } => {
+            let client = TronClient::new(&rpc_url, out, options);
+            let abi = if abi_from_chain {
+                fetch_contract_abi(&client, &contract).await?
+            } else {
+                load_abi_file(abi.as_deref())?
+            };
+            let result =
+                execute_transaction(out, &client, &abi, &contract, tx_id, &signer, fee_limit)
+                    .await?;
+            with_cost_summary(out, &client, result, cost_summary).await
+        }
+        Commands::SetPermissions {
+            rpc_url,
+            owners,
+            threshold,
+            signer,
+            operations,
+            force,
+            cost_summary,
+        } => {
+            let client = TronClient::new(&rpc_url, out, options);
+            let owners: Vec<String> = owners.split(',').map(|s| s.trim().to_string()).collect();
+            let result = set_permissions(
+                out,
+                &client,
+                &owners,
+                threshold,
+                &signer,
+                &operations,
+                force,
+            )
+            .await?;
+            with_cost_summary(out, &client, result, cost_summary).await
+        }
+        Commands::SignCollection {
+            transaction,
+            collection,
+            chain_id,
+            rpc_url,
+            signer,
+        } => {
+            let client = rpc_url.map(|url| TronClient::new(&url, out, options));
+            let target = CollectionTarget {
+                transaction: &transaction,
+                collection: &collection,
+                chain_id: chain_id.as_deref(),
+            };
+            sign_collection(out, client.as_ref(), &target, &signer, prefix).await
+        }
+        Commands::MergeSignatures {
+            collection,
+            out: out_path,
+            transaction,
+            signed_tx_out,
+        } => merge_signatures(
+            out,
+            &collection,
+            &out_path,
+            transaction.as_deref().zip(signed_tx_out.as_deref()),
+            prefix,
+        ),
+        Commands::SignMessage {
+            message,
+            hex,
+            legacy,
+            signer,
+            v_offset,
+        } => {
+            let digest = signed_message_digest(&message_bytes(&message, hex)?, legacy);
+            let signer = signer.signer(prefix)?;
+            let address = signer.address(prefix)?;
+            let signature = signer.sign(&digest, v_offset)?;
+            out.line(format!("Address:   {}", address));
+            out.line(format!("Signature: {}", signature));
+            Ok(serde_json::json!({ "address": address, "signature": signature }))
+        }
+        Commands::VerifySignature {
+            message,
+            hex,
+            legacy,
+            signature,
+            address,
+        } => {
+            let digest = signed_message_digest(&message_bytes(&message, hex)?, legacy);
+            let signer = recover_signer(&digest, &signature, prefix)?;
+            if let Some(address) = &address {
+                verify_signature(&digest, &signature, address, prefix)?;
+                out.line(format!("✅ Signed by {}", signer));
+            } else {
+                out.line(format!("Signer: {}", signer));
+            }
+            Ok(serde_json::json!({ "signer": signer }))
+        }
+        Commands::Sign {
+            tx_json,
+            out_file,
+            signer,
+            v_offset,
+        } => {
+            let transaction: serde_json::Value = read_json_file(&tx_json, "transaction")?;
+            let signed_tx = sign_external_transaction(
+                out,
+                &transaction,
+                &signer.signer(prefix)?,
+                v_offset,
+                prefix,
+            )?;
+            match &out_file {
+                Some(path) => {
+                    fs::write(path, serde_json::to_string_pretty(&signed_tx)?).with_context(
+                        || format!("Failed to write signed transaction: {:?}", path),
+                    )?;
+                    out.phase(format!(
+                        "💾 Signed transaction written to {}",
+                        path.display()
+                    ));
+                }
+                None => out.line(serde_json::to_string_pretty(&signed_tx)?),
+            }
+            Ok(serde_json::json!({
+                "transaction": signed_tx["txID"],
+                "signedTransaction": signed_tx
+            }))
+        }
+        Commands::Broadcast {
+            rpc_url,
+            network,
+            transaction,
+            force,
+            cost_summary,
+        } => {
+            let rpc_url = match (rpc_url, network) {
+                (Some(rpc_url), _) => rpc_url,
+                (None, Some(network)) => network_rpc_url(&network)?.to_string(),
+                (None, None) => unreachable!("clap requires --rpc-url or --network"),
+            };
+            let client = TronClient::new(&rpc_url, out, options);
+            let signed_tx: serde_json::Value = read_json_file(&transaction, "signed transaction")?;
+            let result = broadcast_signed(out, &client, signed_tx, force).await?;
+            with_cost_summary(out, &client, result, cost_summary).await
+        }
+        Commands::KeystoreInspect { keystore } => inspect_keystores(out, &keystore, prefix),
+        Commands::KeystoreChangePassword { keystore } => {
+            let current = keystore_passphrase()?;
+            let new = new_keystore_passphrase(NEW_KEYSTORE_PASSPHRASE_ENV)?;
+            change_keystore_passphrase(out, &keystore, &current, &new, prefix)
+        }
+        Commands::ToBase58 { hex } => {
+            let address =
+                format_address(&parse_address(&hex, prefix)?, AddressFormat::Base58, prefix);
+            out.line(format!("TRON Address: {}", address));
+            Ok(serde_json::json!({ "address": address }))
+        }
+        Commands::DecodeAddressList { hex } => {
+            let addresses = decode_address_array(hex.trim().trim_start_matches("0x"), prefix)?;
+            out.line(format!("📋 Addresses ({}):", addresses.len()));
+            for (i, address) in addresses.iter().enumerate() {
+                out.line(format!("  {}. {}", i + 1, address));
+            }
+            Ok(serde_json::json!({ "addresses": addresses }))
+        }
+        Commands::ContractAddress {
+            factory,
+            salt,
+            init_code,
+            init_code_hash,
+            tx_id,
+            owner,
+            nonce,
+        } => {
+            let (address, scheme) = match (&factory, &tx_id) {
+                (Some(factory), _) => {
+                    let code_hash = match (&init_code, &init_code_hash) {
+                        (Some(code), _) => keccak256(&parse_hex_arg("--init-code", code)?),
+                        (None, Some(hash)) => fixed_hex_arg("--init-code-hash", hash, false)?,
+                        (None, None) => {
+                            return Err(invalid_input(
+                                "CREATE2 needs --init-code or --init-code-hash",
+                            ))
+                        }
+                    };
+                    let salt = fixed_hex_arg("--salt", salt.as_deref().unwrap_or_default(), true)?;
+                    (
+                        create2_address(
+                            &parse_address(factory, prefix)?,
+                            &salt,
+                            &code_hash,
+                            prefix,
+                        ),
+                        "CREATE2",
+                    )
+                }
+                (None, Some(tx_id)) => {
+                    let tx_id = fixed_hex_arg("--tx-id", tx_id, false)?;
+                    match (&owner, nonce) {
+                        (Some(owner), _) => (
+                            deployment_address(&tx_id, &parse_address(owner, prefix)?, prefix),
+                            "deployment",
+                        ),
+                        (None, Some(nonce)) => (create_address(&tx_id, nonce), "CREATE"),
+                        (None, None) => {
+                            return Err(invalid_input("--tx-id needs --owner or --nonce"))
+                        }
+                    }
+                }
+                (None, None) => return Err(invalid_input("Pass --factory (CREATE2) or --tx-id")),
+            };
+            let address = format_address(&address, AddressFormat::Base58, prefix);
+            out.line(format!("Contract address ({}): {}", scheme, address));
+            Ok(serde_json::json!({ "address": address, "scheme": scheme }))
+        }
+        Commands::ToHex { address, format } => {
+            let hex = format_address(&parse_address(&address, prefix)?, format, prefix);
+            out.line(format!("{}: {}", address_format_label(format), hex));
+            Ok(serde_json::json!({ "hex": hex }))
+        }
+    }
+}
+
+/// Decodes a hex command-line value (with or without 0x prefix).
+fn parse_hex_arg(flag: &str, value: &str) -> Result<Vec<u8>> {
+    hex::decode(value.trim_start_matches("0x"))
+        .map_err(|e| invalid_input(format!("{} is not valid hex: {}", flag, e)))
+}
+
+/// Decodes a 32-byte hex value; with `pad`, shorter values are left-padded.
+fn fixed_hex_arg(flag: &str, value: &str, pad: bool) -> Result<[u8; 32]> {
+    let bytes = parse_hex_arg(flag, value)?;
+    if bytes.len() > 32 || (!pad && bytes.len() != 32) {
+        return Err(invalid_input(format!(
+            "{} must be {}32 bytes, got {}",
+            flag,
+            if pad { "at most " } else { "" },
+            bytes.len()
+        )));
+    }
+    let mut word = [0u8; 32];
+    word[32 - bytes.len()..].copy_from_slice(&bytes);
+    Ok(word)
+}
+
+/// Result keys `--quiet` looks for, most specific first. Commands without any
+/// (e.g. the verify commands, whose answer is the exit status) print nothing.
+const QUIET_KEYS: [&str; 9] = [
+    "outputs",
+    "inputs",
+    "addresses",
+    "contractAddress",
+    "transaction",
+    "publicKey",
+    "formatted",
+    "hex",
+    "address",
+];
+
+impl Commands {
+    /// The result field `--quiet` prints, for commands whose value is not
+    /// the first of [`QUIET_KEYS`] in their result.
+    fn quiet_key(&self) -> Option<&'static str> {
+        match self {
+            Commands::Owners { .. } => Some("owners"),
+            Commands::OwnerIndex { .. } => Some("index"),
+            Commands::Transaction { .. } => Some("executed"),
+            Commands::Approvals { .. } => Some("approvals"),
+            Commands::Balances { .. } => Some("contracts"),
+            Commands::ChainParams { .. } => Some("economic"),
+            _ => None,
+        }
+    }
+}
+
+/// What `--quiet` prints for a command result: the command's `quiet_key`
+/// field, else the first of [`QUIET_KEYS`] present. Lists print one entry
+/// per line: decoded `outputs`/`inputs`, `addresses` and `owners` by value,
+/// `approvals` as the owners who approved, `contracts` as each balance (empty
+/// where it failed), and `economic` chain parameters as `key=value`. A null value
+/// (`owner-index` for a non-owner) prints nothing. A deployment whose
+/// contract address the node didn't report prints its transaction ID instead.
+fn quiet_value(
+    result: &serde_json::Value,
+    quiet_key: Option<&'static str>,
+    prefix: u8,
+) -> Option<String> {
+    let plain = |value: &serde_json::Value| match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let lines = |lines: Vec<String>| Some(lines.join("\n"));
+    let (key, value) = quiet_key
+        .iter()
+        .chain(&QUIET_KEYS)
+        .find_map(|&key| result.get(key).map(|value| (key, value)))?;
+    match (key, value) {
+        (_, serde_json::Value::Null) => None,
+        ("approvals", serde_json::Value::Array(approvals)) => lines(
+            approvals
+                .iter()
+                .filter(|approval| approval["approved"] == true)
+                .map(|approval| plain(&approval["owner"]))
+                .collect(),
+        ),
+        ("contracts", serde_json::Value::Array(rows)) => lines(
+            rows.iter()
+                .map(|row| row.get("balanceTokens").map(plain).unwrap_or_default())
+                .collect(),
+        ),
+        ("economic", serde_json::Value::Object(params)) => lines(
+            params
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, plain(value)))
+                .collect(),
+        ),
+        ("outputs" | "inputs", serde_json::Value::Array(params)) => {
+            lines(params.iter().map(|param| plain(&param["value"])).collect())
+        }
+        ("addresses" | "owners", serde_json::Value::Array(addresses)) => {
+            lines(addresses.iter().map(plain).collect())
+        }
+        ("contractAddress", serde_json::Value::String(address))
+            if validate_tron_address(address, prefix).is_err() =>
+        {
+            result.get("transaction").map(plain)
+        }
+        _ => Some(plain(value)),
+    }
+}
+
+/// Wraps a successful command result in the `--json` envelope. Object results
+/// are flattened next to `"ok"` so consumers keep reading the same keys.
+fn success_envelope(result: serde_json::Value) -> serde_json::Value {
+    let mut envelope = serde_json::Map::new();
+    envelope.insert("ok".to_string(), serde_json::Value::Bool(true));
+    match result {
+        serde_json::Value::Object(fields) => envelope.extend(fields),
+        serde_json::Value::Null => {}
+        other => {
+            envelope.insert("result".to_string(), other);
+        }
+    }
+    serde_json::Value::Object(envelope)
+}
+
+/// An error for input the user can fix, as opposed to a failed operation.
+fn invalid_input(msg: impl Into<String>) -> anyhow::Error {
+    TronError::InvalidInput(msg.into()).into()
+}
+
+/// Maps an error to the process exit code (also reported as `code` in `--json` mode).
+fn error_code(err: &anyhow::Error) -> u8 {
+    for cause in err.chain() {
+        // Undecodable data can be the node's fault, and a revert or rejected
+        // broadcast the chain's verdict, rather than the user's input
+        match cause.downcast_ref::<TronError>() {
+            Some(TronError::Rpc(_)) => return EXIT_RPC,
+            Some(TronError::Decode(_) | TronError::Revert(_) | TronError::Broadcast(_)) => {}
+            Some(_) => return EXIT_INVALID_INPUT,
+            None => {}
+        }
+        if cause.is::<hex::FromHexError>()
+            || cause.is::<bs58::decode::Error>()
+            || cause.is::<secp256k1::Error>()
+        {
+            return EXIT_INVALID_INPUT;
+        }
+        if cause.is::<reqwest::Error>() {
+            return EXIT_RPC;
+        }
+    }
+    EXIT_FAILURE
+}
+
+/// Where `generate-key` gets its key from.
+enum KeySource<'a> {
+    /// The OS random number generator (the default and the only secure choice).
+    Random,
+    /// `--entropy` hex.
+    Entropy(&'a str),
+    /// `--from-password`: Argon2id of $KEY_PASSPHRASE under `salt`.
+    Password {
+        salt: &'a str,
+        memory_mib: u32,
+        iterations: u32,
+    },
+}
+
+/// Environment variable holding the passphrase for `--from-password`, so it
+/// stays out of the shell history and process list.
+const PASSPHRASE_ENV: &str = "KEY_PASSPHRASE";
+const DEFAULT_ARGON2_MEMORY_MIB: u32 = 256;
+const DEFAULT_ARGON2_ITERATIONS: u32 = 3;
+/// The work factor `--from-password` won't go below: a guess must cost at
+/// least this much memory and this many passes.
+const MIN_ARGON2_MEMORY_MIB: u32 = 64;
+const MIN_ARGON2_ITERATIONS: u32 = 3;
+const MIN_PASSWORD_SALT_BYTES: usize = 16;
+/// Passphrases shorter than this get an extra warning.
+const WEAK_PASSPHRASE_CHARS: usize = 20;
+
+/// The key for `--from-password`, warning about how guessable it is.
+fn password_secret_key(
+    out: &Output,
+    salt: &str,
+    memory_mib: u32,
+    iterations: u32,
+) -> Result<secp256k1::SecretKey> {
+    if memory_mib < MIN_ARGON2_MEMORY_MIB || iterations < MIN_ARGON2_ITERATIONS {
+        return Err(invalid_input(format!(
+            "--from-password needs at least --argon2-memory {} and --argon2-iterations {}",
+            MIN_ARGON2_MEMORY_MIB, MIN_ARGON2_ITERATIONS
+        )));
+    }
+    if salt.len() < MIN_PASSWORD_SALT_BYTES {
+        return Err(invalid_input(format!(
+            "--salt must be at least {} bytes, got {}",
+            MIN_PASSWORD_SALT_BYTES,
+            salt.len()
+        )));
+    }
+    let passphrase = std::env::var(PASSPHRASE_ENV)
+        .ok()
+        .filter(|p| !p.is_empty())
+        .ok_or_else(|| invalid_input(format!("Set {} to the passphrase", PASSPHRASE_ENV)))?;
+
+    out.warn("⚠️  Key derived from a passphrase: anyone who guesses it (and the salt) can recreate the key and take its funds. Passphrases people choose are far easier to guess than random keys; use the default generator for new wallets.");
+    let length = passphrase.chars().count();
+    if length < WEAK_PASSPHRASE_CHARS {
+        out.warn(format!(
+            "⚠️  The passphrase is only {} characters. Short passphrases are brute-forced despite Argon2; use at least {} characters of random words.",
+            length, WEAK_PASSPHRASE_CHARS
+        ));
+    }
+
+    out.phase(format!(
+        "🔑 Deriving key with Argon2id ({} MiB, {} passes)...",
+        memory_mib, iterations
+    ));
+    let params = Argon2Params {
+        memory_kib: memory_mib
+            .checked_mul(1024)
+            .ok_or_else(|| invalid_input("--argon2-memory is too large"))?,
+        iterations,
+        parallelism: 1,
+    };
+    let tag = argon2id(passphrase.as_bytes(), salt.as_bytes(), params, 32)?;
+    Ok(secret_key_from_entropy(&tag)?)
+}
+
+/// Where `generate-key` puts the new key.
+#[derive(Clone, Copy)]
+enum KeyDestination<'a> {
+    /// Printed with the address.
+    Print,
+    /// `--out`: a plaintext JSON file.
+    File(&'a std::path::Path),
+    /// `--keystore`: an encrypted keystore file.
+    Keystore(&'a std::path::Path),
+}
+
+/// Environment variable holding the passphrase for `--keystore`; without it
+/// the passphrase is prompted for on the terminal.
+const KEYSTORE_PASSPHRASE_ENV: &str = "KEYSTORE_PASSPHRASE";
+
+/// Passphrases that new keystores refuse.
+const MIN_KEYSTORE_PASSPHRASE_CHARS: usize = 8;
+
+/// Environment variable holding the new passphrase for
+/// `keystore-change-password`.
+const NEW_KEYSTORE_PASSPHRASE_ENV: &str = "NEW_KEYSTORE_PASSPHRASE";
+
+/// The passphrase for a new keystore, from `env` or typed twice at the
+/// terminal.
+fn new_keystore_passphrase(env: &str) -> Result<Zeroizing<String>> {
+    let passphrase = match std::env::var(env) {
+        Ok(passphrase) => Zeroizing::new(passphrase),
+        Err(_) => {
+            let passphrase = Zeroizing::new(prompt_passphrase("New keystore passphrase: ", env)?);
+            if *prompt_passphrase("Repeat passphrase: ", env)? != *passphrase {
+                return Err(invalid_input("The passphrases don't match"));
+            }
+            passphrase
+        }
+    };
+    if passphrase.chars().count() < MIN_KEYSTORE_PASSPHRASE_CHARS {
+        return Err(invalid_input(format!(
+            "The keystore passphrase must be at least {} characters",
+            MIN_KEYSTORE_PASSPHRASE_CHARS
+        )));
+    }
+    Ok(passphrase)
+}
+
+/// The passphrase of an existing keystore, from [`KEYSTORE_PASSPHRASE_ENV`]
+/// or typed at the terminal.
+fn keystore_passphrase() -> Result<Zeroizing<String>> {
+    Ok(Zeroizing::new(
+        match std::env::var(KEYSTORE_PASSPHRASE_ENV) {
+            Ok(passphrase) => passphrase,
+            Err(_) => prompt_passphrase("Keystore passphrase: ", KEYSTORE_PASSPHRASE_ENV)?,
+        },
+    ))
+}
+
+/// The hex private key in keystore `path`, opened with the passphrase from
+/// [`KEYSTORE_PASSPHRASE_ENV`] or typed at the terminal.
+fn keystore_private_key(path: &std::path::Path, prefix: u8) -> Result<String> {
+    let json =
+        read_text_file(path).with_context(|| format!("Failed to read keystore: {:?}", path))?;
+    let mut key = decrypt_key(&json, &keystore_passphrase()?, prefix)
+        .with_context(|| format!("Cannot open keystore {:?}", path))?;
+    let private_key = hex::encode(key.secret_bytes());
+    key.non_secure_erase();
+    Ok(private_key)
+}
+
+/// `keystore-change-password`: re-encrypts keystore `path` under `new`, with
+/// a fresh salt and IV. The new file is written beside the old one and
+/// renamed over it, so an interruption leaves one or the other intact.
+fn change_keystore_passphrase(
+    out: &Output,
+    path: &std::path::Path,
+    current: &str,
+    new: &str,
+    prefix: u8,
+) -> Result<serde_json::Value> {
+    if current == new {
+        return Err(invalid_input(
+            "The new passphrase is the same as the current one",
+        ));
+    }
+    let json =
+        read_text_file(path).with_context(|| format!("Failed to read keystore: {:?}", path))?;
+    out.phase("🔓 Decrypting the keystore...");
+    let mut key = decrypt_key(&json, current, prefix)
+        .with_context(|| format!("Cannot open keystore {:?}", path))?;
+    let address = public_key_to_tron_address(&key.public_key(&Secp256k1::new()), prefix);
+
+    out.phase("🔐 Encrypting the key with scrypt...");
+    let nonces = KeystoreNonces {
+        salt: rand::random(),
+        iv: rand::random(),
+        id: rand::random(),
+    };
+    let keystore = encrypt_key(&key, new, STANDARD_SCRYPT, &nonces, prefix);
+    key.non_secure_erase();
+    let keystore = serde_json::to_string_pretty(&keystore?)?;
+
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(format!(".{}.tmp", hex::encode(rand::random::<[u8; 4]>())));
+    let temp = PathBuf::from(temp);
+    write_secret_file(&temp, &keystore, false)
+        .and_then(|()| {
+            fs::rename(&temp, path).with_context(|| format!("Failed to replace {:?}", path))
+        })
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&temp);
+        })?;
+
+    out.line(format!("✅ Passphrase changed for {}", path.display()));
+    out.line(format!("Address: {}", address));
+    Ok(serde_json::json!({
+        "keystore": path.display().to_string(),
+        "address": address
+    }))
+}
+
+/// Reads a line from the terminal with echo turned off, so the passphrase
+/// isn't shown and stdin stays free for other input. Without a terminal the
+/// error points at `env`, the variable that supplies it instead.
+#[cfg(unix)]
+fn prompt_passphrase(prompt: &str, env: &str) -> Result<String> {
+    use std::io::{BufRead, Write};
+    use std::os::unix::io::AsRawFd;
+
+    let no_terminal = || {
+        invalid_input(format!(
+            "No terminal to prompt for the passphrase on; set {}",
+            env
+        ))
+    };
+    let mut tty = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .map_err(|_| no_terminal())?;
+    let fd = tty.as_raw_fd();
+    let mut original = std::mem::MaybeUninit::<libc::termios>::uninit();
+    // SAFETY: `fd` is an open terminal and `original` is written before use
+    if unsafe { libc::tcgetattr(fd, original.as_mut_ptr()) } != 0 {
+        return Err(no_terminal());
+    }
+    // SAFETY: initialized by the successful tcgetattr above
+    let original = unsafe { original.assume_init() };
+    let mut silent = original;
+    silent.c_lflag &= !libc::ECHO;
+    silent.c_lflag |= libc::ECHONL;
+
+    write!(tty, "{}", prompt)?;
+    tty.flush()?;
+    // SAFETY: `fd` stays open for the duration of both calls
+    unsafe { libc::tcsetattr(fd, libc::TCSAFLUSH, &silent) };
+    let mut line = String::new();
+    let read = std::io::BufReader::new(&tty).read_line(&mut line);
+    // SAFETY: as above
+    unsafe { libc::tcsetattr(fd, libc::TCSAFLUSH, &original) };
+    read.context("Failed to read the passphrase")?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+#[cfg(not(unix))]
+fn prompt_passphrase(_prompt: &str, env: &str) -> Result<String> {
+    Err(invalid_input(format!(
+        "Set {} to the keystore passphrase",
+        env
+    )))
+}
+
+/// Generates a key and prints it, or writes it to a file (plaintext or
+/// keystore) and prints only the address, so the private key never reaches
+/// the terminal.
+fn generate_private_key(
+    out: &Output,
+    source: &KeySource,
+    destination: KeyDestination,
+    force: bool,
+    prefix: u8,
+) -> Result<serde_json::Value> {
+    if out.quiet && matches!(destination, KeyDestination::Print) {
+        // Only the address would be printed and the new key lost
+        return Err(invalid_input(
+            "--quiet prints only the address; pass --out to save the private key",
+        ));
+    }
+    let secret_key = match *source {
+        KeySource::Entropy(entropy) => {
+            let bytes =
+                hex::decode(entropy.trim_start_matches("0x")).context("Invalid entropy hex")?;
+            let key = secret_key_from_entropy(&bytes)?;
+            out.warn("⚠️  Key derived from --entropy: anyone who knows that input can recreate it. Use only for testing or with entropy you generated securely.");
+            key
+        }
+        KeySource::Password {
+            salt,
+            memory_mib,
+            iterations,
+        } => password_secret_key(out, salt, memory_mib, iterations)?,
+        KeySource::Random => Secp256k1::new().generate_keypair(&mut OsRng).0,
+    };
+
+    let private_key_hex = hex::encode(secret_key.secret_bytes());
+    let address = private_key_to_tron_address(&private_key_hex, prefix)?;
+
+    if let KeyDestination::Keystore(path) = destination {
+        let passphrase = new_keystore_passphrase(KEYSTORE_PASSPHRASE_ENV)?;
+        out.phase("🔐 Encrypting the key with scrypt...");
+        let nonces = KeystoreNonces {
+            salt: rand::random(),
+            iv: rand::random(),
+            id: rand::random(),
+        };
+        let keystore = encrypt_key(&secret_key, &passphrase, STANDARD_SCRYPT, &nonces, prefix)?;
+        write_secret_file(path, &serde_json::to_string_pretty(&keystore)?, force)?;
+        #[cfg(not(unix))]
+        out.warn("⚠️  File permissions are not restricted on this platform; protect the keystore yourself.");
+
+        out.line("🔑 New TRON Wallet Generated");
+        out.line(format!("Address:     {}", address));
+        out.line(format!("Keystore:    {}", path.display()));
+        out.line("\n⚠️  Keep the passphrase safe: without it the key can't be recovered.");
+        return Ok(serde_json::json!({
+            "address": address,
+            "keystore": path.display().to_string()
+        }));
+    }
+    if let KeyDestination::File(path) = destination {
+        let key_json = serde_json::json!({
+            "privateKey": private_key_hex,
+            "address": address
+        });
+        write_secret_file(path, &serde_json::to_string_pretty(&key_json)?, force)?;
+        #[cfg(not(unix))]
+        out.warn("⚠️  File permissions are not restricted on this platform; protect the key file yourself.");
+
+        out.line("🔑 New TRON Wallet Generated");
+        out.line(format!("Address:     {}", address));
+        out.line(format!("Key file:    {}", path.display()));
+        return Ok(serde_json::json!({
+            "address": address,
+            "keyFile": path.display().to_string()
+        }));
+    }
+
+    out.line("🔑 New TRON Wallet Generated");
+    out.line("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    out.line(format!("Private Key: {}", private_key_hex));
+    out.line(format!("Address:     {}", address));
+    out.line("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    out.line("\n⚠️  IMPORTANT: Save your private key securely! Never share it!");
+
+    Ok(serde_json::json!({
+        "privateKey": private_key_hex,
+        "address": address
+    }))
+}
+
+/// Writes `contents` to a new file readable only by the owner (0600 on Unix).
+/// An existing file is an error unless `force`, in which case it is
+/// truncated and its permissions tightened.
+fn write_secret_file(path: &std::path::Path, contents: &str, force: bool) -> Result<()> {
+    use std::io::Write;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true);
+    if force {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut file = options.open(path).map_err(|err| {
+        if err.kind() == std::io::ErrorKind::AlreadyExists {
+            invalid_input(format!(
+                "{} already exists; pass --force to overwrite it",
+                path.display()
+            ))
+        } else {
+            anyhow::Error::new(err).context(format!("Failed to create {:?}", path))
+        }
+    })?;
+    // `mode` only applies to newly created files
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    file.write_all(contents.as_bytes())
+        .with_context(|| format!("Failed to write {:?}", path))
+}
+
+async fn deploy_contract(
+    out: &Output,
+    options: &ClientOptions,
+    args: &DeployArgs,
+) -> Result<serde_json::Value> {
+    let prefix = options.address_prefix;
+    if let Some(path) = &args.emit_script {
+        return emit_deploy_script(out, args, path.as_deref(), prefix);
+    }
+
+    out.phase("🚀 Deploying USDTMultisig contract to TRON...\n");
+
+    // Open the signing key and get deployer address
+    let signer = args.signer.signer(prefix)?;
+    let deployer = signer.address(prefix)?;
+
+    let plan = DeployPlan::from_args(args, prefix)?;
+    plan.validate(prefix)?;
+    check_owner_count(out, plan.owners.len(), args.max_owners, args.force)?;
+
+    let network = Network::from_rpc_url(&plan.rpc_url);
+    if network == Network::Mainnet {
+        out.warn("🌐 Deploying to TRON MAINNET — this spends real TRX and the contract will hold real USDT.");
+    }
+    if let Some(mismatch) = network.usdt_mismatch(&plan.usdt, prefix) {
+        out.warn(format!("⚠️  {}", mismatch));
+    }
+
+    out.line("📋 Deployment plan");
+    out.line(format!(
+        "   Network:   {} — {}",
+        network.label(),
+        plan.rpc_url
+    ));
+    out.line(format!("   Deployer:  {}", deployer));
+    out.line(format!("   USDT:      {}", plan.usdt));
+    for (i, owner) in plan.owners.iter().enumerate() {
+        out.line(format!("   Owner {}:   {}", i + 1, owner));
+    }
+    out.line(format!(
+        "   Threshold: {} of {}",
+        plan.threshold,
+        plan.owners.len()
+    ));
+    let client = TronClient::new(&plan.rpc_url, out, options);
+    let owner_energy = plan.owners.len() as u64 * ENERGY_PER_OWNER;
+    let energy_fee = match client.chain_parameters().await {
+        Ok(params) => params.get("getEnergyFee").copied().filter(|fee| *fee > 0),
+        Err(err) => {
+            out.detail(format!("Skipping the owner cost in TRX: {:#}", err));
+            None
+        }
+    };
+    let owner_cost = match energy_fee {
+        Some(fee) => format!(
+            " (~{} TRX at {} SUN/energy)",
+            format_trx(owner_energy.saturating_mul(fee as u64)),
+            fee
+        ),
+        None => String::new(),
+    };
+    out.line(format!(
+        "   Owners:    ~{} energy to store {} owners{}",
+        owner_energy,
+        plan.owners.len(),
+        owner_cost
+    ));
+    out.line(format!("   Fee limit: {} TRX", format_trx(plan.fee_limit)));
+    out.line(format!("   Contract:  {}\n", plan.contract_json.display()));
+
+    let explorer_url = options.explorer_url.as_deref();
+    submit_deployment(
+        out,
+        &client,
+        options.api_version,
+        explorer_url,
+        &plan,
+        args,
+        &signer,
+    )
+    .await
+}
+
+/// `--emit-script`: the plan as a script instead of a deployment.
+fn emit_deploy_script(
+    out: &Output,
+    args: &DeployArgs,
+    path: Option<&std::path::Path>,
+    prefix: u8,
+) -> Result<serde_json::Value> {
+    let plan = DeployPlan::from_args(args, prefix)?;
+    plan.validate(prefix)?;
+    check_owner_count(out, plan.owners.len(), args.max_owners, args.force)?;
+    if let Some(mismatch) = Network::from_rpc_url(&plan.rpc_url).usdt_mismatch(&plan.usdt, prefix) {
+        out.warn(format!("⚠️  {}", mismatch));
+    }
+    let owner_list: Vec<&str> = plan.owners.iter().map(String::as_str).collect();
+    let constructor_hex = match &plan.constructor_params {
+        Some(params) => params.clone(),
+        None => encode_constructor_params(&plan.usdt, &owner_list, plan.threshold, prefix)?,
+    };
+    let contract = ContractJson::load(&plan.contract_json)?;
+    let bytecode = hex::decode(contract.bytecode.object.trim_start_matches("0x"))
+        .context("Contract bytecode is not hex")?;
+    let bytecode_hash = hex::encode(keccak256(&bytecode));
+    let script = deploy_script(&plan, args, &constructor_hex, &bytecode_hash);
+
+    match path {
+        Some(path) => {
+            fs::write(path, &script)
+                .with_context(|| format!("Failed to write deploy script: {:?}", path))?;
+            out.line(format!("💾 Deploy script written to {}", path.display()));
+        }
+        None => out.line(script.trim_end()),
+    }
+    Ok(serde_json::json!({
+        "script": script,
+        "constructorHex": constructor_hex,
+        "bytecodeKeccak256": bytecode_hash
+    }))
+}
+
+/// Environment variable the emitted deploy script reads the deployer key from.
+const SCRIPT_KEY_ENV: &str = "TRON_PRIVATE_KEY";
+
+/// A `sh` script running the deployment `plan` describes with every value
+/// spelled out, so it doesn't depend on a manifest or on defaults that may
+/// change. The key is left to [`SCRIPT_KEY_ENV`]; global options such as
+/// `--api-version` are not carried over.
+fn deploy_script(
+    plan: &DeployPlan,
+    args: &DeployArgs,
+    constructor_hex: &str,
+    bytecode_hash: &str,
+) -> String {
+    let mut flags = vec![("--rpc-url", plan.rpc_url.clone())];
+    match &plan.constructor_params {
+        Some(params) => flags.push(("--constructor-hex", params.clone())),
+        None => {
+            flags.push(("--usdt", plan.usdt.clone()));
+            flags.push(("--owners", plan.owners.join(",")));
+            flags.push(("--threshold", plan.threshold.to_string()));
+        }
+    }
+    flags.push(("--contract-json", plan.contract_json.display().to_string()));
+    flags.push(("--fee-limit", plan.fee_limit.to_string()));
+    if args.max_owners != DEFAULT_MAX_OWNERS {
+        flags.push(("--max-owners", args.max_owners.to_string()));
+    }
+    if let Some(existing) = &args.if_not_exists {
+        flags.push(("--if-not-exists", existing.clone()));
+    }
+    if let Some(seconds) = args.expiration {
+        flags.push(("--expiration", seconds.to_string()));
+    }
+    if args.v_offset != V_OFFSET_TRON {
+        flags.push(("--v-offset", args.v_offset.to_string()));
+    }
+
+    let mut lines = vec![
+        "#!/bin/sh".to_string(),
+        "# USDTMultisig deployment generated by `tron-utils deploy --emit-script`".to_string(),
+        format!(
+            "# Network:    {}",
+            Network::from_rpc_url(&plan.rpc_url).label()
+        ),
+        format!("# USDT:       {}", plan.usdt),
+        format!("# Threshold:  {} of {}", plan.threshold, plan.owners.len()),
+    ];
+    lines.extend(
+        plan.owners
+            .iter()
+            .map(|owner| format!("#   owner     {}", owner)),
+    );
+    lines.push(format!("# Constructor parameters: {}", constructor_hex));
+    lines.push(format!("# Bytecode keccak256:     {}", bytecode_hash));
+    lines.push(format!(
+        "# Set {} to the deployer's key before running.",
+        SCRIPT_KEY_ENV
+    ));
+    lines.push("set -eu".to_string());
+    lines.push("tron-utils deploy \\".to_string());
+    for (flag, value) in flags {
+        lines.push(format!("  {} {} \\", flag, shell_quote(&value)));
+    }
+    if plan.owners_sorted {
+        lines.push("  --sort-owners \\".to_string());
+    }
+    if args.force {
+        lines.push("  --force \\".to_string());
+    }
+    if args.include_abi {
+        lines.push("  --include-abi \\".to_string());
+    }
+    lines.push(format!("  --private-key \"${}\"", SCRIPT_KEY_ENV));
+    lines.join("\n") + "\n"
+}
+
+/// `value` as one `sh` word: as-is if it only has characters the shell
+/// leaves alone, otherwise single-quoted.
+fn shell_quote(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:,=@%+".contains(c));
+    if plain {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+/// Everything after planning: the `--if-not-exists` check, building, signing
+/// and (unless `--no-broadcast`) broadcasting the deployment through `client`.
+async fn submit_deployment(
+    out: &Output,
+    client: &dyn TronRpc,
+    api_version: ApiVersion,
+    explorer_url: Option<&str>,
+    plan: &DeployPlan,
+    args: &DeployArgs,
+    signer: &Signer,
+) -> Result<serde_json::Value> {
+    let prefix = client.address_prefix();
+    let deployer = signer.address(prefix)?;
+    let owner_list: Vec<&str> = plan.owners.iter().map(String::as_str).collect();
+
+    if let Some(existing) = &args.if_not_exists {
+        out.phase(format!(
+            "\n🔎 Checking for existing deployment at {}...",
+            existing
+        ));
+        if contract_exists(client, existing).await? {
+            let info = client.multisig_info(existing).await?;
+            let changes = deployment_diff(plan, &info, prefix)?;
+            if !changes.is_empty() {
+                return Err(anyhow!(
+                    "Contract {} exists but differs from the requested configuration:\n  {}\n\
+                     A deployed multisig is immutable: editing the manifest cannot change it. \
+                     Deploy a new contract for this configuration and move funds to it.",
+                    existing,
+                    changes.join("\n  ")
+                ));
+            }
+
+            out.line(format!("✅ Already deployed: {} (no changes)", existing));
+            return Ok(serde_json::json!({
+                "contractAddress": existing,
+                "alreadyDeployed": true
+            }));
+        }
+        out.phase("No contract found, deploying");
+    }
+
+    check_fee_limit(out, client, plan.fee_limit, args.force).await?;
+
+    // Load contract bytecode
+    let contract = ContractJson::load(&plan.contract_json)?;
+    let bytecode = &contract.bytecode.object;
+    out.detail(format!("Bytecode length: {} bytes", bytecode.len() / 2));
+
+    // Encode constructor parameters
+    let params = match &plan.constructor_params {
+        Some(params) => params.clone(),
+        None => encode_constructor_params(&plan.usdt, &owner_list, plan.threshold, prefix)?,
+    };
+    out.detail(format!("Constructor params: {}", params));
+
+    // Contract ABI (simplified for deployment)
+    let abi = get_contract_abi();
+
+    // Build deploy request
+    let deployer_hex = tron_address_to_hex(&deployer, prefix)?;
+    let request = DeployContractRequest {
+        owner_address: deployer_hex.clone(),
+        fee_limit: plan.fee_limit,
+        call_value: 0,
+        consume_user_resource_percent: 100,
+        origin_energy_limit: 10000000,
+        abi: abi.to_string(),
+        bytecode: bytecode.clone(),
+        parameter: params,
+        name: "USDTMultisig".to_string(),
+    };
+
+    if args.print_request {
+        print_request_body(
+            out,
+            "/wallet/deploycontract",
+            &serde_json::to_value(&request)?,
+        );
+    }
+
+    out.phase("\n📡 Creating deployment transaction...");
+
+    // Create deployment transaction
+    let response = client.deploy_contract(&request).await?;
+    let mut transaction = parse_transaction_response(&response, api_version)?;
+
+    let mut tx_id = transaction
+        .get("txID")
+        .and_then(|v| v.as_str())
+        .map(String::from)
+        .ok_or_else(|| anyhow!("No txID in response"))?;
+
+    if let Some(seconds) = args.expiration {
+        tx_id = extend_expiration(out, client, &mut transaction, seconds).await?;
+    }
+    out.phase(format!("Transaction ID: {}", tx_id));
+
+    // Get contract address from response
+    let mut contract_address = response
+        .get("contract_address")
+        .and_then(|v| v.as_str())
+        .map(|hex| {
+            // Convert hex address (41...) to base58
+            parse_address(hex, prefix)
+                .map(|raw| format_address(&raw, AddressFormat::Base58, prefix))
+                .unwrap_or_else(|_| hex.to_string())
+        })
+        .unwrap_or_else(|| "(Check TronScan for contract address)".to_string());
+    if args.expiration.is_some() {
+        // The address derives from the txID, so the node's no longer applies
+        let tx_id_bytes: [u8; 32] = hex::decode(&tx_id)?
+            .try_into()
+            .map_err(|_| anyhow!("txID is not 32 bytes"))?;
+        let address = deployment_address(&tx_id_bytes, &parse_address(&deployer, prefix)?, prefix);
+        contract_address = format_address(&address, AddressFormat::Base58, prefix);
+    }
+
+    if args.print_request {
+        // The body `broadcast_transaction` would post, minus the signature
+        let mut redacted = transaction.clone();
+        redacted["signature"] = serde_json::json!(["<redacted>"]);
+        print_request_body(out, "/wallet/broadcasttransaction", &redacted);
+        return Ok(serde_json::json!({
+            "transaction": tx_id,
+            "contractAddress": contract_address,
+            "broadcast": false,
+            "requests": {
+                "/wallet/deploycontract": request,
+                "/wallet/broadcasttransaction": redacted
+            }
+        }));
+    }
+
+    // Sign transaction
+    out.phase("\n🔐 Signing transaction...");
+    let signed_tx = signer.sign_transaction_json(&transaction, &tx_id, args.v_offset)?;
+    check_signed_by(&signed_tx, &tx_id, &deployer, prefix)?;
+
+    let mut printed = None;
+    if args.signed_tx_out.is_some() || args.no_broadcast {
+        let exported = bind_to_chain(&signed_tx, &client.chain_id().await?)?;
+        match &args.signed_tx_out {
+            Some(path) => {
+                fs::write(path, serde_json::to_string_pretty(&exported)?)
+                    .with_context(|| format!("Failed to write signed transaction: {:?}", path))?;
+                out.phase(format!(
+                    "💾 Signed transaction written to {}",
+                    path.display()
+                ));
+            }
+            None => {
+                out.line(serde_json::to_string_pretty(&exported)?);
+                printed = Some(exported);
+            }
+        }
+    }
+
+    if args.no_broadcast {
+        out.line("✅ Deployment transaction signed (not broadcast)");
+        out.line("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        out.line(format!("Transaction: {}", tx_id));
+        out.line(format!("Contract:    {}", contract_address));
+        out.line("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        match args.expiration {
+            Some(seconds) => out.line(format!(
+                "\n⚠️  The transaction expires {}s after creation; broadcast it before then.",
+                seconds
+            )),
+            None => out.line("\n⚠️  The transaction expires about a minute after creation; broadcast it before then (or rebuild with --expiration)."),
+        }
+        let mut result = serde_json::json!({
+            "transaction": tx_id,
+            "contractAddress": contract_address,
+            "broadcast": false,
+            "ownersSorted": plan.owners_sorted
+        });
+        if let Some(signed_tx) = printed {
+            result["signedTransaction"] = signed_tx;
+        }
+        if args.include_abi {
+            result["abi"] = serde_json::from_str(abi)?;
+        }
+        return Ok(result);
+    }
+
+    // Broadcast transaction
+    if args.v_offset != V_OFFSET_TRON {
+        out.warn("⚠️  Broadcasting with --v-offset 27; TRON nodes expect 0 and will likely reject the signature.");
+    }
+    out.phase("📤 Broadcasting transaction...");
+    broadcast_transaction(client, &signed_tx).await?;
+
+    out.line("✅ Contract deployed successfully!");
+    out.line("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    out.line(format!("Transaction: {}", tx_id));
+    out.line(format!("Contract:    {}", contract_address));
+    out.line("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    if let Some(url) = explorer_link(
+        explorer_url,
+        &plan.rpc_url,
+        ExplorerLink::Transaction(&tx_id),
+    ) {
+        out.line(format!("\nView transaction: {}", url));
+    }
+    if validate_tron_address(&contract_address, prefix).is_ok() {
+        let link = ExplorerLink::Address(&contract_address);
+        if let Some(url) = explorer_link(explorer_url, &plan.rpc_url, link) {
+            out.line(format!("View contract:    {}", url));
+        }
+    }
+
+    let mut result = serde_json::json!({
+        "transaction": tx_id,
+        "contractAddress": contract_address,
+        "broadcast": true,
+        "ownersSorted": plan.owners_sorted
+    });
+    if args.include_abi {
+        result["abi"] = serde_json::from_str(abi)?;
+    }
+    with_cost_summary(out, client, result, args.cost_summary).await
+}
+
+/// Prints a node request body for `--print-request`, for comparison with what
+/// other clients (e.g. TronWeb) send.
+fn print_request_body(out: &Output, path: &str, body: &serde_json::Value) {
+    out.line(format!("POST {}", path));
+    out.line(serde_json::to_string_pretty(body).unwrap_or_else(|_| body.to_string()));
+}
+
+/// Pulls the unsigned transaction out of a transaction-building API response
+/// (`/wallet/deploycontract`, `/wallet/triggersmartcontract`, ...).
+///
+/// Errors are reported either as `result.result == false` with a hex-encoded
+/// `result.message`, or as a top-level `Error` string; both are checked
+/// regardless of `api_version`, which only selects where the transaction lives.
+fn parse_transaction_response(
+    response: &serde_json::Value,
+    api_version: ApiVersion,
+) -> Result<serde_json::Value> {
+    // Check for errors in response
+    if let Some(result) = response.get("result") {
+        if result.get("result") == Some(&serde_json::json!(false)) {
+            let msg = result
+                .get("message")
+                .and_then(|m| m.as_str())
+                .map(decode_hex_message)
+                .unwrap_or_else(|| "Unknown error".to_string());
+            return Err(anyhow!("Failed to create transaction: {}", msg));
+        }
+    }
+
+    // Check for Error field (some API versions use this)
+    if let Some(error) = response.get("Error") {
+        return Err(anyhow!("API Error: {}", error));
+    }
+
+    let nested = response.get("transaction");
+    let root = response.get("txID").map(|_| response);
+    let transaction = match api_version {
+        ApiVersion::Auto => nested.or(root),
+        ApiVersion::Legacy => nested,
+        ApiVersion::Current => root,
+    };
+
+    transaction.cloned().ok_or_else(|| {
+        anyhow!(
+            "No transaction in response (--api-version {:?}). Full response:\n{}",
+            api_version,
+            serde_json::to_string_pretty(response).unwrap_or_default()
+        )
+    })
+}
+
+/// Chain parameter capping how far ahead an expiration may be, in ms. Nodes
+/// that don't report it enforce [`MAX_EXPIRATION_MS`].
+const MAX_EXPIRATION_PARAM: &str = "getMaxExpiration";
+
+/// Sets `transaction`'s expiration to `seconds` after its creation timestamp,
+/// updating `raw_data`, `raw_data_hex` and `txID` together. Returns the new
+/// txID, which is what must be signed.
+async fn extend_expiration(
+    out: &Output,
+    client: &dyn TronRpc,
+    transaction: &mut serde_json::Value,
+    seconds: u64,
+) -> Result<String> {
+    let max_ms = match client.chain_parameters().await {
+        Ok(params) => params
+            .get(MAX_EXPIRATION_PARAM)
+            .copied()
+            .filter(|max| *max > 0)
+            .unwrap_or(MAX_EXPIRATION_MS),
+        Err(err) => {
+            out.detail(format!("Using the default expiration limit: {:#}", err));
+            MAX_EXPIRATION_MS
+        }
+    };
+    let window_ms = seconds.saturating_mul(1000);
+    if seconds == 0 || window_ms > max_ms as u64 {
+        return Err(invalid_input(format!(
+            "--expiration must be between 1 and {} seconds",
+            max_ms / 1000
+        )));
+    }
+
+    let raw_data_hex = transaction
+        .get("raw_data_hex")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow!("No raw_data_hex in transaction"))?;
+    // The node stamps `timestamp` with its own clock, which the expiry is checked against
+    let created_ms = match transaction["raw_data"]
+        .get("timestamp")
+        .and_then(|v| v.as_i64())
+    {
+        Some(timestamp) => timestamp,
+        None => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .context("System clock is before 1970")?
+            .as_millis() as i64,
+    };
+    let expiration = created_ms + window_ms as i64;
+    let raw_data_hex = set_expiration(raw_data_hex, expiration)?;
+    let tx_id = transaction_id(&raw_data_hex)?;
+
+    transaction["raw_data"]["expiration"] = expiration.into();
+    transaction["raw_data_hex"] = raw_data_hex.into();
+    transaction["txID"] = tx_id.clone().into();
+    out.detail(format!(
+        "Expiration extended by {}s to {} ms",
+        seconds, expiration
+    ));
+    Ok(tx_id)
+}
+
+/// Makes `transaction`'s txID one not in `built`, and records it there.
+/// The node stamps transactions to the millisecond against the same
+/// reference block, so identical calls built in quick succession (a batch of
+/// equal payouts) can come back byte for byte the same, and the second
+/// broadcast is refused as a duplicate. A repeat has its timestamp moved on
+/// a millisecond at a time and is rehashed until its txID is new.
+fn distinct_tx_id(
+    out: &Output,
+    built: &mut HashSet<String>,
+    transaction: &mut serde_json::Value,
+) -> Result<String> {
+    let mut tx_id = transaction
+        .get("txID")
+        .and_then(|v| v.as_str())
+        .map(String::from)
+        .ok_or_else(|| anyhow!("No txID in response"))?;
+    if built.contains(&tx_id) {
+        let original = tx_id.clone();
+        let mut raw_data_hex = transaction
+            .get("raw_data_hex")
+            .and_then(|v| v.as_str())
+            .map(String::from)
+            .ok_or_else(|| anyhow!("No raw_data_hex in transaction {}", original))?;
+        let mut timestamp = transaction["raw_data"]
+            .get("timestamp")
+            .and_then(|v| v.as_i64())
+            .ok_or_else(|| anyhow!("No timestamp in transaction {}", original))?;
+        while built.contains(&tx_id) {
+            timestamp += 1;
+            raw_data_hex = set_timestamp(&raw_data_hex, timestamp)?;
+            tx_id = transaction_id(&raw_data_hex)?;
+        }
+        transaction["raw_data"]["timestamp"] = timestamp.into();
+        transaction["raw_data_hex"] = raw_data_hex.into();
+        transaction["txID"] = tx_id.clone().into();
+        out.detail(format!(
+            "Transaction {} was already built this session; restamped as {}",
+            original, tx_id
+        ));
+    }
+    built.insert(tx_id.clone());
+    Ok(tx_id)
+}
+
+/// Refuses more than `max_owners` owners unless `force`, in which case it
+/// only warns.
+fn check_owner_count(out: &Output, owners: usize, max_owners: usize, force: bool) -> Result<()> {
+    if owners <= max_owners {
+        return Ok(());
+    }
+    let message = format!(
+        "{} owners is more than --max-owners {}. Each owner adds about {} energy to the deployment, \
+         and very large owner lists can exceed the block energy limit.",
+        owners, max_owners, ENERGY_PER_OWNER
+    );
+    if !force {
+        return Err(invalid_input(format!(
+            "{} Pass --force or a higher --max-owners if this is intended.",
+            message
+        )));
+    }
+    out.warn(format!("⚠️  {}", message));
+    Ok(())
+}
+
+/// Fee limits above this (in SUN) almost always mean SUN and TRX were mixed up.
+const SUSPICIOUS_FEE_LIMIT_SUN: u64 = 10_000 * 1_000_000;
+
+/// Refuses a fee limit above [`SUSPICIOUS_FEE_LIMIT_SUN`] unless `force` is
+/// set, and one above the chain's `getMaxFeeLimit`, which the node would
+/// reject anyway. Chain parameters are best-effort: if they can't be fetched
+/// only the fixed threshold applies.
+async fn check_fee_limit(
+    out: &Output,
+    client: &dyn TronRpc,
+    fee_limit: u64,
+    force: bool,
+) -> Result<()> {
+    match client.chain_parameters().await {
+        Ok(params) => {
+            if let Some(max) = params.get("getMaxFeeLimit").copied() {
+                if fee_limit as i128 > max as i128 {
+                    return Err(invalid_input(format!(
+                        "--fee-limit {} SUN exceeds the network maximum of {} SUN ({} TRX)",
+                        fee_limit,
+                        max,
+                        format_trx(max.max(0) as u64)
+                    )));
+                }
+            }
+            if let Some(energy_fee) = params.get("getEnergyFee").copied().filter(|fee| *fee > 0) {
+                out.detail(format!(
+                    "Fee limit covers up to {} energy at {} SUN/energy",
+                    fee_limit / energy_fee as u64,
+                    energy_fee
+                ));
+            }
+        }
+        Err(err) => out.detail(format!("Skipping chain fee checks: {:#}", err)),
+    }
+
+    if fee_limit > SUSPICIOUS_FEE_LIMIT_SUN {
+        let message = format!(
+            "--fee-limit is {} SUN = {} TRX, which is unusually high. --fee-limit is in SUN (1 TRX = 1,000,000 SUN).",
+            fee_limit,
+            format_trx(fee_limit)
+        );
+        if !force {
+            return Err(invalid_input(format!(
+                "{} Pass --force if this is intended.",
+                message
+            )));
+        }
+        out.warn(format!("⚠️  {}", message));
+    }
+    Ok(())
+}
+
+async fn broadcast_transaction(client: &dyn TronRpc, signed_tx: &serde_json::Value) -> Result<()> {
+    let broadcast_response: BroadcastResponse =
+        serde_json::from_value(client.broadcast(signed_tx).await?)
+            .context("Failed to parse broadcast response")?;
+
+    if broadcast_response.result != Some(true) {
+        let code = broadcast_response.code.unwrap_or_default();
+        let msg = broadcast_response
+            .message
+            .map(|m| decode_hex_message(&m))
+            .unwrap_or_else(|| "Unknown error".to_string());
+        return Err(broadcast_error(&code, &msg));
+    }
+    Ok(())
+}
+
+/// Checks every signature of `signed_tx` recovers to `signer` over `tx_id`,
+/// so a signing fault is caught here rather than by the node as `SIGERROR`.
+fn check_signed_by(
+    signed_tx: &serde_json::Value,
+    tx_id: &str,
+    signer: &str,
+    prefix: u8,
+) -> Result<()> {
+    let signatures = signed_tx["signature"]
+        .as_array()
+        .filter(|signatures| !signatures.is_empty())
+        .ok_or_else(|| anyhow!("Signed transaction has no signature"))?;
+    for signature in signatures {
+        let signature = signature
+            .as_str()
+            .ok_or_else(|| anyhow!("Signature is not a string: {}", signature))?;
+        verify_signature(tx_id, signature, signer, prefix).map_err(|err| {
+            anyhow!(
+                "The signature doesn't verify against the signing key, so it is \
+                 not broadcast (the node would reject it as SIGERROR): {}",
+                err
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// Member of exported transaction files holding the genesis block ID of the
+/// network they were built for. Nodes don't know it, so it is removed again
+/// before broadcast.
+const CHAIN_ID_FIELD: &str = "chainId";
+
+/// `transaction` with [`CHAIN_ID_FIELD`] set to `chain_id`, for writing out.
+fn bind_to_chain(transaction: &serde_json::Value, chain_id: &str) -> Result<serde_json::Value> {
+    let mut bound = transaction.clone();
+    bound
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("Transaction is not an object"))?
+        .insert(CHAIN_ID_FIELD.to_string(), chain_id.into());
+    Ok(bound)
+}
+
+/// Broadcasts a signed transaction file after checking its txID and that
+/// the chain it was bound to is the one `client` points at. Files without
+/// a chain ID (from other tools) are sent with a warning.
+async fn broadcast_signed(
+    out: &Output,
+    client: &dyn TronRpc,
+    mut signed_tx: serde_json::Value,
+    force: bool,
+) -> Result<serde_json::Value> {
+    let fields = signed_tx
+        .as_object_mut()
+        .ok_or_else(|| invalid_input("Signed transaction is not an object"))?;
+    let bound_chain = match fields.remove(CHAIN_ID_FIELD) {
+        Some(serde_json::Value::String(chain_id)) => Some(chain_id),
+        Some(other) => return Err(invalid_input(format!("Invalid chainId: {}", other))),
+        None => None,
+    };
+    let tx_id = verified_tx_id(&signed_tx)?;
+    let signatures = signed_tx.get("signature").and_then(|v| v.as_array());
+    if signatures.is_none_or(|s| s.is_empty()) {
+        return Err(invalid_input("Transaction is not signed"));
+    }
+
+    let chain_id = client.chain_id().await?;
+    match bound_chain {
+        Some(bound) if !bound.eq_ignore_ascii_case(&chain_id) => {
+            let message = format!(
+                "Transaction was signed for chain {}, but the node is on chain {}",
+                bound, chain_id
+            );
+            if !force {
+                return Err(invalid_input(format!(
+                    "{}. Pass --force to broadcast it anyway.",
+                    message
+                )));
+            }
+            out.warn(format!("⚠️  {}", message));
+        }
+        Some(_) => {}
+        None => out.warn("⚠️  The transaction names no chainId; it cannot be checked against the node's network."),
+    }
+
+    out.phase("📤 Broadcasting transaction...");
+    broadcast_transaction(client, &signed_tx).await?;
+    out.line("✅ Transaction broadcast");
+    out.line(format!("Transaction: {}", tx_id));
+    Ok(serde_json::json!({
+        "transaction": tx_id,
+        "chainId": chain_id,
+        "broadcast": true
+    }))
+}
+
+/// Turns a broadcast rejection into an error, spelling out the likely causes
+/// for codes whose raw message doesn't explain them.
+fn broadcast_error(code: &str, msg: &str) -> anyhow::Error {
+    let hint = match code {
+        "SIGERROR" | "SIGOBJ_NOT_MATCH" => Some(
+            "The node rejected the signature. Likely causes:\n\
+             \x20 • the private key does not belong to the transaction's owner address (e.g. the wrong deployer key)\n\
+             \x20 • the txID that was signed is not the hash of this raw_data\n\
+             \x20 • raw_data was modified after signing (the txID must be recomputed and the transaction re-signed)",
+        ),
+        "TRANSACTION_EXPIRATION_ERROR" => Some(
+            "The transaction expired before it reached the node (about a minute after it was built, \
+             unless --expiration was given). Run the command again to build and sign a fresh one.",
+        ),
+        _ => None,
+    };
+    let message = match hint {
+        Some(hint) => format!("Broadcast failed [{}]: {}\n\n{}", code, msg, hint),
+        None => format!("Broadcast failed [{}]: {}", code, msg),
+    };
+    TronError::Broadcast(message).into()
+}
+
+async fn get_account(client: &TronClient, address: &str) -> Result<AccountInfo> {
+    let response = client
+        .post(
+            "/wallet/getaccount",
+            &serde_json::json!({ "address": address, "visible": true }),
+        )
+        .await?;
+
+    if let Some(error) = response.get("Error") {
+        return Err(anyhow!("API Error: {}", error));
+    }
+
+    // Accounts that have never received TRX come back as an empty object
+    Ok(AccountInfo {
+        activated: response.get("address").is_some(),
+        balance_sun: response
+            .get("balance")
+            .and_then(|b| b.as_u64())
+            .unwrap_or(0),
+    })
+}
+
+/// One `/wallet/getdelegatedresourcev2` entry: TRX `from` staked (Stake
+/// 2.0) whose energy and bandwidth go to `to`. Expiry times are ms since the
+/// epoch; until then the delegation is locked and cannot be reclaimed, and 0
+/// means it was never locked.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(default)]
+struct DelegatedResource {
+    from: String,
+    to: String,
+    frozen_balance_for_energy: u64,
+    frozen_balance_for_bandwidth: u64,
+    expire_time_for_energy: i64,
+    expire_time_for_bandwidth: i64,
+}
+
+impl DelegatedResource {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "from": self.from,
+            "to": self.to,
+            "energySun": self.frozen_balance_for_energy,
+            "energyLockedUntil": self.expire_time_for_energy,
+            "bandwidthSun": self.frozen_balance_for_bandwidth,
+            "bandwidthLockedUntil": self.expire_time_for_bandwidth
+        })
+    }
+}
+
+/// The entries of a `/wallet/getdelegatedresourcev2` response; the node
+/// omits `delegatedResource` when there are none.
+fn parse_delegated_resources(response: &serde_json::Value) -> Result<Vec<DelegatedResource>> {
+    if let Some(error) = response.get("Error") {
+        return Err(anyhow!("API Error: {}", error));
+    }
+    match response.get("delegatedResource") {
+        Some(entries) => {
+            serde_json::from_value(entries.clone()).context("Invalid delegated resource list")
+        }
+        None => Ok(Vec::new()),
+    }
+}
+
+/// "locked until ..." for a delegation expiry, or "unlocked".
+fn delegation_lock(expire_ms: i64) -> String {
+    match expire_ms {
+        0 => "unlocked".to_string(),
+        ms => format!("locked until {} UTC", format_utc(ms)),
+    }
+}
+
+async fn delegated_resources(
+    client: &TronClient,
+    from: &str,
+    to: &str,
+) -> Result<Vec<DelegatedResource>> {
+    let response = client
+        .post(
+            "/wallet/getdelegatedresourcev2",
+            &serde_json::json!({ "fromAddress": from, "toAddress": to, "visible": true }),
+        )
+        .await?;
+    parse_delegated_resources(&response)
+}
+
+fn print_delegations(
+    out: &Output,
+    title: &str,
+    entries: &[DelegatedResource],
+    counterparty: impl Fn(&DelegatedResource) -> String,
+) {
+    let energy: u64 = entries.iter().map(|e| e.frozen_balance_for_energy).sum();
+    let bandwidth: u64 = entries.iter().map(|e| e.frozen_balance_for_bandwidth).sum();
+    out.line(format!(
+        "{}: {} TRX for energy, {} TRX for bandwidth",
+        title,
+        format_trx(energy),
+        format_trx(bandwidth)
+    ));
+    for entry in entries {
+        out.line(format!("   {}", counterparty(entry)));
+        if entry.frozen_balance_for_energy > 0 {
+            out.line(format!(
+                "      Energy:    {} TRX ({})",
+                format_trx(entry.frozen_balance_for_energy),
+                delegation_lock(entry.expire_time_for_energy)
+            ));
+        }
+        if entry.frozen_balance_for_bandwidth > 0 {
+            out.line(format!(
+                "      Bandwidth: {} TRX ({})",
+                format_trx(entry.frozen_balance_for_bandwidth),
+                delegation_lock(entry.expire_time_for_bandwidth)
+            ));
+        }
+    }
+}
+
+/// Lists the delegations `address` receives and makes. The node's account
+/// index names the counterparties, then each pair is read on its own.
+async fn show_delegations(
+    out: &Output,
+    client: &TronClient,
+    address: &str,
+) -> Result<serde_json::Value> {
+    validate_tron_address(address, client.address_prefix()).context("Invalid --address")?;
+    let index = client
+        .post(
+            "/wallet/getdelegatedresourceaccountindexv2",
+            &serde_json::json!({ "value": address, "visible": true }),
+        )
+        .await?;
+    if let Some(error) = index.get("Error") {
+        return Err(anyhow!("API Error: {}", error));
+    }
+    let accounts = |field: &str| -> Vec<String> {
+        index[field]
+            .as_array()
+            .map(|list| {
+                list.iter()
+                    .filter_map(|a| a.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let mut incoming = Vec::new();
+    for from in accounts("fromAccounts") {
+        incoming.extend(delegated_resources(client, &from, address).await?);
+    }
+    let mut outgoing = Vec::new();
+    for to in accounts("toAccounts") {
+        outgoing.extend(delegated_resources(client, address, &to).await?);
+    }
+
+    print_delegations(out, "Incoming", &incoming, |entry| {
+        format!("from {}", entry.from)
+    });
+    print_delegations(out, "Outgoing", &outgoing, |entry| {
+        format!("to {}", entry.to)
+    });
+    Ok(serde_json::json!({
+        "address": address,
+        "incoming": incoming.iter().map(DelegatedResource::to_json).collect::<Vec<_>>(),
+        "outgoing": outgoing.iter().map(DelegatedResource::to_json).collect::<Vec<_>>()
+    }))
+}
+
+/// What a chain parameter's value counts, for [`show_chain_params`].
+#[derive(Clone, Copy)]
+enum ParamUnit {
+    SunPerEnergy,
+    SunPerByte,
+    Sun,
+    Millis,
+    Count,
+}
+
+/// The chain parameters behind transaction costs and limits, as
+/// `chain-params` lists them.
+const ECONOMIC_PARAMS: [(&str, &str, ParamUnit); 7] = [
+    ("getEnergyFee", "Energy fee", ParamUnit::SunPerEnergy),
+    ("getTransactionFee", "Bandwidth fee", ParamUnit::SunPerByte),
+    ("getMaxFeeLimit", "Max fee limit", ParamUnit::Sun),
+    (MAX_EXPIRATION_PARAM, "Max expiration", ParamUnit::Millis),
+    ("getCreateAccountFee", "Account creation", ParamUnit::Sun),
+    (
+        "getCreateNewAccountFeeInSystemContract",
+        "Account creation (system contract)",
+        ParamUnit::Sun,
+    ),
+    (
+        TOTAL_SIGN_NUM_PARAM,
+        "Keys per permission",
+        ParamUnit::Count,
+    ),
+];
+
+fn format_chain_param(value: i64, unit: ParamUnit) -> String {
+    let sun = || format_trx(value.max(0) as u64);
+    match unit {
+        ParamUnit::SunPerEnergy => format!("{} SUN per energy", value),
+        ParamUnit::SunPerByte => format!("{} SUN per byte", value),
+        ParamUnit::Sun => format!("{} SUN ({} TRX)", value, sun()),
+        ParamUnit::Millis if value % 3_600_000 == 0 => {
+            format!("{} ms ({}h)", value, value / 3_600_000)
+        }
+        ParamUnit::Millis => format!("{} ms ({}s)", value, value / 1000),
+        ParamUnit::Count => value.to_string(),
+    }
+}
+
+/// Prints the [`ECONOMIC_PARAMS`] of the network, and returns every chain
+/// parameter for `--json`.
+async fn show_chain_params(out: &Output, client: &dyn TronRpc) -> Result<serde_json::Value> {
+    let params = client.chain_parameters().await?;
+    out.line("📊 Chain parameters");
+    let width = ECONOMIC_PARAMS
+        .iter()
+        .map(|(_, label, _)| label.len() + 1)
+        .max()
+        .unwrap_or_default();
+    let mut economic = serde_json::Map::new();
+    for (key, label, unit) in ECONOMIC_PARAMS {
+        let value = match params.get(key) {
+            Some(&value) => {
+                economic.insert(key.to_string(), value.into());
+                format_chain_param(value, unit)
+            }
+            None => "not reported".to_string(),
+        };
+        out.line(format!("   {:<width$} {}", format!("{}:", label), value));
+    }
+    out.line(format!(
+        "\n{} parameters in all; --json lists every one",
+        params.len()
+    ));
+    Ok(serde_json::json!({
+        "economic": economic,
+        "all": params.into_iter().collect::<std::collections::BTreeMap<_, _>>()
+    }))
+}
+
+/// A resource price, in SUN, in effect from `since_ms` on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PricePoint {
+    since_ms: i64,
+    sun: u64,
+}
+
+/// Parses the `prices` string of the price-history endpoints:
+/// `timestamp:price` pairs separated by commas, oldest first, the first
+/// stamped 0 for the price at genesis.
+fn parse_price_history(prices: &str) -> Result<Vec<PricePoint>> {
+    let points = prices
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (since, sun) = entry
+                .split_once(':')
+                .ok_or_else(|| anyhow!("Invalid price entry: {:?}", entry))?;
+            Ok(PricePoint {
+                since_ms: since
+                    .parse()
+                    .with_context(|| format!("Invalid timestamp in price entry {:?}", entry))?,
+                sun: sun
+                    .parse()
+                    .with_context(|| format!("Invalid price in price entry {:?}", entry))?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if points.is_empty() {
+        return Err(anyhow!("No prices in the price history"));
+    }
+    Ok(points)
+}
+
+/// The price history at `path`, e.g. `/wallet/getenergyprices`.
+async fn fetch_price_history(client: &TronClient, path: &str) -> Result<Vec<PricePoint>> {
+    let response = client.post(path, &serde_json::json!({})).await?;
+    let prices = response
+        .get("prices")
+        .and_then(|p| p.as_str())
+        .ok_or_else(|| anyhow!("No prices in {} response", path))?;
+    parse_price_history(prices)
+}
+
+/// Prints the current price of one resource and its last `recent` changes,
+/// and returns them for `--json`.
+fn print_price_history(
+    out: &Output,
+    title: &str,
+    unit: ParamUnit,
+    points: &[PricePoint],
+    recent: usize,
+) -> serde_json::Value {
+    let since = |point: &PricePoint| match point.since_ms {
+        0 => "genesis".to_string(),
+        ms => format_utc(ms),
+    };
+    let current = points.last().expect("price history is not empty");
+    out.line(format!(
+        "{}: {} (since {})",
+        title,
+        format_chain_param(current.sun as i64, unit),
+        since(current)
+    ));
+
+    let changes: Vec<(&PricePoint, &PricePoint)> = points.iter().zip(&points[1..]).collect();
+    let shown = &changes[changes.len().saturating_sub(recent)..];
+    if !shown.is_empty() {
+        out.line(format!("   Last {} change(s):", shown.len()));
+    }
+    for (before, after) in shown.iter().rev() {
+        let percent = match before.sun {
+            0 => String::new(),
+            old => format!(
+                " ({:+.0}%)",
+                (after.sun as f64 - old as f64) * 100.0 / old as f64
+            ),
+        };
+        out.line(format!(
+            "     {}  {} → {}{}",
+            since(after),
+            before.sun,
+            after.sun,
+            percent
+        ));
+    }
+
+    let history: Vec<serde_json::Value> = points[points.len().saturating_sub(recent + 1)..]
+        .iter()
+        .map(|point| serde_json::json!({ "since": point.since_ms, "sun": point.sun }))
+        .collect();
+    serde_json::json!({
+        "current": current.sun,
+        "since": current.since_ms,
+        "history": history
+    })
+}
+
+/// Prints the current energy and bandwidth prices with their latest
+/// changes, which move deployment and call costs between estimates.
+async fn show_resource_prices(
+    out: &Output,
+    client: &TronClient,
+    recent: usize,
+) -> Result<serde_json::Value> {
+    let (energy, bandwidth) = tokio::try_join!(
+        fetch_price_history(client, "/wallet/getenergyprices"),
+        fetch_price_history(client, "/wallet/getbandwidthprices"),
+    )?;
+    let energy = print_price_history(out, "⚡ Energy", ParamUnit::SunPerEnergy, &energy, recent);
+    out.line("");
+    let bandwidth = print_price_history(
+        out,
+        "📶 Bandwidth",
+        ParamUnit::SunPerByte,
+        &bandwidth,
+        recent,
+    );
+    Ok(serde_json::json!({ "energy": energy, "bandwidth": bandwidth }))
+}
+
+/// Why a confirmed transaction failed. `resMessage` is hex: ABI-encoded
+/// revert data, or plain text from the node (e.g. `REVERT opcode executed`)
+/// when the revert data is only in `contractResult`.
+fn revert_reason(info: &serde_json::Value) -> Option<String> {
+    let res_message = info
+        .get("resMessage")
+        .and_then(|m| m.as_str())
+        .and_then(|m| hex::decode(m).ok());
+    let contract_result = info
+        .pointer("/contractResult/0")
+        .and_then(|r| r.as_str())
+        .and_then(|r| hex::decode(r).ok());
+
+    let decoded = [&res_message, &contract_result]
+        .into_iter()
+        .flatten()
+        .find_map(|data| decode_revert_reason(data));
+    decoded.or_else(|| {
+        String::from_utf8(res_message?)
+            .ok()
+            .filter(|text| !text.is_empty())
+    })
+}
+
+/// The `receipt` of a `gettransactioninfobyid` response. Resource fields
+/// the node leaves out are zero.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct TransactionReceipt {
+    /// `SUCCESS`, `REVERT`, `OUT_OF_ENERGY`, ...; absent for plain transfers
+    result: Option<String>,
+    /// Energy paid from the caller's staked energy
+    energy_usage: u64,
+    /// SUN burned for energy
+    energy_fee: u64,
+    /// Energy paid by the contract's deployer
+    origin_energy_usage: u64,
+    energy_usage_total: u64,
+    /// Extra energy charged under TRON's dynamic energy model for heavily
+    /// used contracts, included in `energy_usage_total`
+    energy_penalty_total: u64,
+    net_usage: u64,
+    net_fee: u64,
+}
+
+impl TransactionReceipt {
+    fn from_info(info: &serde_json::Value) -> Result<Self> {
+        match info.get("receipt") {
+            Some(receipt) => serde_json::from_value(receipt.clone()).context("Invalid receipt"),
+            None => Ok(Self::default()),
+        }
+    }
+}
+
+/// How a confirmed transaction ended.
+#[derive(Debug, PartialEq)]
+enum TxOutcome {
+    Success,
+    /// The fee limit was used up before execution finished
+    OutOfEnergy,
+    /// With the decoded reason, if the contract gave one
+    Reverted(Option<String>),
+    /// Execution hit the block's CPU time limit
+    OutOfTime,
+    /// Any other receipt result, as reported
+    Unknown(String),
+}
+
+impl TxOutcome {
+    fn classify(info: &serde_json::Value, receipt: &TransactionReceipt) -> Self {
+        match receipt.result.as_deref() {
+            None | Some("SUCCESS") => TxOutcome::Success,
+            Some("OUT_OF_ENERGY") => TxOutcome::OutOfEnergy,
+            Some("REVERT") => TxOutcome::Reverted(revert_reason(info)),
+            Some("OUT_OF_TIME") => TxOutcome::OutOfTime,
+            Some(other) => match revert_reason(info) {
+                // The node reports require() failures under other codes too
+                Some(reason) => TxOutcome::Reverted(Some(reason)),
+                None => TxOutcome::Unknown(other.to_string()),
+            },
+        }
+    }
+
+    /// The receipt result this outcome was read from.
+    fn code(&self) -> &str {
+        match self {
+            TxOutcome::Success => "SUCCESS",
+            TxOutcome::OutOfEnergy => "OUT_OF_ENERGY",
+            TxOutcome::Reverted(_) => "REVERT",
+            TxOutcome::OutOfTime => "OUT_OF_TIME",
+            TxOutcome::Unknown(code) => code,
+        }
+    }
+
+    /// What to do about a failure, where there is something to do.
+    fn advice(&self) -> Option<&'static str> {
+        match self {
+            TxOutcome::OutOfEnergy => Some(
+                "The fee limit ran out before the call finished. Retry with a higher --fee-limit, \
+                 or stake TRX for energy so less has to be burned.",
+            ),
+            TxOutcome::OutOfTime => Some(
+                "The call exceeded the per-transaction CPU time limit; a higher fee limit won't help.",
+            ),
+            _ => None,
+        }
+    }
+
+    /// The error for a transaction that did not succeed.
+    fn error(&self, txid: &str) -> anyhow::Error {
+        let message = match self {
+            TxOutcome::Reverted(Some(reason)) => {
+                format!("Transaction {} reverted: {}", txid, reason)
+            }
+            other => format!("Transaction {} did not succeed: {}", txid, other.code()),
+        };
+        let message = match self.advice() {
+            Some(advice) => format!("{}\n{}", message, advice),
+            None => message,
+        };
+        match self {
+            TxOutcome::Reverted(_) => TronError::Revert(message).into(),
+            _ => anyhow!(message),
+        }
+    }
+}
+
+async fn show_receipt(out: &Output, client: &dyn TronRpc, txid: &str) -> Result<serde_json::Value> {
+    let info = client.get_tx_info(txid).await?;
+    if info.as_object().is_none_or(|fields| fields.is_empty()) {
+        return Err(anyhow!(
+            "Transaction {} not found (it may not be confirmed yet)",
+            txid
+        ));
+    }
+    let receipt = TransactionReceipt::from_info(&info)?;
+    let outcome = TxOutcome::classify(&info, &receipt);
+    let reason = match &outcome {
+        TxOutcome::Reverted(reason) => reason.clone(),
+        _ => None,
+    };
+    let fee = info.get("fee").and_then(|f| f.as_u64()).unwrap_or(0);
+
+    match &outcome {
+        TxOutcome::Success => out.line(format!("✅ Transaction {} succeeded", txid)),
+        other => out.line(format!("❌ Transaction {}: {}", txid, other.code())),
+    }
+    if let Some(reason) = &reason {
+        out.line(format!("Reason:    {}", reason));
+    }
+    let penalty = match receipt.energy_penalty_total {
+        0 => String::new(),
+        penalty => format!(", including {} dynamic-energy penalty", penalty),
+    };
+    out.line(format!(
+        "Energy:    {} ({} staked, {} from the deployer, {} SUN burned{})",
+        receipt.energy_usage_total,
+        receipt.energy_usage,
+        receipt.origin_energy_usage,
+        receipt.energy_fee,
+        penalty
+    ));
+    out.line(format!(
+        "Bandwidth: {} ({} SUN burned)",
+        receipt.net_usage, receipt.net_fee
+    ));
+    out.line(format!("Fee:       {} TRX", format_trx(fee)));
+    if let Some(advice) = outcome.advice() {
+        out.line(format!("\n💡 {}", advice));
+    }
+
+    Ok(serde_json::json!({
+        "transaction": txid,
+        "outcome": outcome.code(),
+        "reason": reason,
+        "energyUsageTotal": receipt.energy_usage_total,
+        "energyUsage": receipt.energy_usage,
+        "originEnergyUsage": receipt.origin_energy_usage,
+        "energyFee": receipt.energy_fee,
+        "energyPenaltyTotal": receipt.energy_penalty_total,
+        "netUsage": receipt.net_usage,
+        "netFee": receipt.net_fee,
+        "fee": fee
+    }))
+}
+
+/// How often [`confirmed_cost`] asks for the transaction's info.
+const CONFIRMATION_POLL: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// Polls after which [`confirmed_cost`] gives up: about a minute, or 20
+/// blocks, which is well past the usual one or two.
+const CONFIRMATION_POLLS: u32 = 20;
+
+/// Whether staked energy and bandwidth paid for a transaction: `"fully"` if
+/// nothing was burned, `"no"` if everything was.
+fn staked_coverage(receipt: &TransactionReceipt) -> &'static str {
+    let staked_bandwidth = receipt.net_usage > 0 && receipt.net_fee == 0;
+    if receipt.energy_fee == 0 && receipt.net_fee == 0 {
+        "fully"
+    } else if receipt.energy_usage > 0 || staked_bandwidth {
+        "partly"
+    } else {
+        "no"
+    }
+}
+
+/// The cost footer every broadcasting command prints with `--cost-summary`,
+/// in one layout so the costs of different operations can be compared.
+fn print_cost_summary(out: &Output, receipt: &TransactionReceipt, fee: u64) {
+    out.line("\n💸 Cost");
+    out.line(format!(
+        "   Energy:    {} ({} staked, {} from the deployer)",
+        receipt.energy_usage_total, receipt.energy_usage, receipt.origin_energy_usage
+    ));
+    out.line(format!("   Bandwidth: {}", receipt.net_usage));
+    out.line(format!(
+        "   Burned:    {} TRX ({} for energy, {} for bandwidth)",
+        format_trx(fee),
+        format_trx(receipt.energy_fee),
+        format_trx(receipt.net_fee)
+    ));
+    out.line(format!(
+        "   Covered by staked resources: {}",
+        staked_coverage(receipt)
+    ));
+}
+
+/// Waits for `txid` to confirm, prints its cost footer and returns the same
+/// figures for JSON output. A transaction that confirmed but failed still
+/// cost something, so the footer is printed before its error is returned.
+async fn confirmed_cost(
+    out: &Output,
+    client: &dyn TronRpc,
+    txid: &str,
+) -> Result<serde_json::Value> {
+    let info = wait_for_confirmation(out, client, txid).await?;
+    let receipt = TransactionReceipt::from_info(&info)?;
+    let fee = info.get("fee").and_then(|f| f.as_u64()).unwrap_or(0);
+    print_cost_summary(out, &receipt, fee);
+    let outcome = TxOutcome::classify(&info, &receipt);
+    if outcome != TxOutcome::Success {
+        return Err(outcome.error(txid));
+    }
+    Ok(serde_json::json!({
+        "energyUsageTotal": receipt.energy_usage_total,
+        "energyUsage": receipt.energy_usage,
+        "originEnergyUsage": receipt.origin_energy_usage,
+        "energyFee": receipt.energy_fee,
+        "netUsage": receipt.net_usage,
+        "netFee": receipt.net_fee,
+        "fee": fee,
+        "coveredByStake": staked_coverage(&receipt)
+    }))
+}
+
+/// Polls until broadcast transaction `txid` is in a block, for up to
+/// [`CONFIRMATION_POLLS`] polls, and returns its transaction info.
+async fn wait_for_confirmation(
+    out: &Output,
+    client: &dyn TronRpc,
+    txid: &str,
+) -> Result<serde_json::Value> {
+    out.phase("⏳ Waiting for confirmation...");
+    let mut info = serde_json::Value::Null;
+    for poll in 0..CONFIRMATION_POLLS {
+        info = client.get_tx_info(txid).await?;
+        if info.as_object().is_some_and(|fields| !fields.is_empty()) {
+            break;
+        }
+        if poll + 1 < CONFIRMATION_POLLS {
+            tokio::time::sleep(CONFIRMATION_POLL).await;
+        }
+    }
+    if info.as_object().is_none_or(|fields| fields.is_empty()) {
+        return Err(anyhow!(
+            "Transaction {} was broadcast but not confirmed within {}s; check it later with `receipt`",
+            txid,
+            CONFIRMATION_POLL.as_secs() * u64::from(CONFIRMATION_POLLS)
+        ));
+    }
+    Ok(info)
+}
+
+/// Adds the `cost` of the command's broadcast `transaction` to `result` if
+/// `enabled`. Results without a transaction (nothing was sent) pass through.
+async fn with_cost_summary(
+    out: &Output,
+    client: &dyn TronRpc,
+    mut result: serde_json::Value,
+    enabled: bool,
+) -> Result<serde_json::Value> {
+    if let Some(txid) = result["transaction"].as_str().filter(|_| enabled) {
+        result["cost"] = confirmed_cost(out, client, txid).await?;
+    }
+    Ok(result)
+}
+
+async fn verify_transfer(
+    out: &Output,
+    client: &dyn TronRpc,
+    txid: &str,
+    expected_to: &str,
+    expected_amount: u128,
+    usdt: Option<&str>,
+) -> Result<serde_json::Value> {
+    let prefix = client.address_prefix();
+    let expected_to_hex = tron_address_to_hex(expected_to, prefix)?;
+    let token_hex = usdt
+        .map(|usdt| tron_address_to_hex(usdt, prefix))
+        .transpose()?;
+
+    let info = client.get_tx_info(txid).await?;
+    if info.as_object().is_none_or(|fields| fields.is_empty()) {
+        return Err(anyhow!(
+            "Transaction {} not found (it may not be confirmed yet)",
+            txid
+        ));
+    }
+
+    let outcome = TxOutcome::classify(&info, &TransactionReceipt::from_info(&info)?);
+    if outcome != TxOutcome::Success {
+        return Err(outcome.error(txid));
+    }
+
+    let events: Vec<TransferEvent> = parse_transfer_events(&info, prefix)?
+        .into_iter()
+        .filter(|event| {
+            token_hex.as_deref().is_none_or(|token| {
+                tron_address_to_hex(&event.token, prefix).ok().as_deref() == Some(token)
+            })
+        })
+        .collect();
+
+    for event in &events {
+        out.line(format!(
+            "Transfer: {} -> {} amount {} (token {})",
+            event.from, event.to, event.amount, event.token
+        ));
+    }
+
+    let matched = events.iter().find(|event| {
+        event.amount == expected_amount
+            && tron_address_to_hex(&event.to, prefix).ok().as_deref()
+                == Some(expected_to_hex.as_str())
+    });
+
+    let Some(event) = matched else {
+        return Err(anyhow!(
+            "No Transfer of {} to {} found in transaction {} ({} Transfer event(s) checked)",
+            expected_amount,
+            expected_to,
+            txid,
+            events.len()
+        ));
+    };
+
+    out.line(format!(
+        "\n✅ Verified: {} received {} from {}",
+        event.to, event.amount, event.from
+    ));
+
+    Ok(serde_json::json!({
+        "verified": true,
+        "token": event.token,
+        "from": event.from,
+        "to": event.to,
+        "amount": event.amount.to_string()
+    }))
+}
+
+/// Compares the on-chain runtime code of `contract` with the artifact's
+/// `deployedBytecode`, ignoring immutable variables (set by the constructor)
+/// and reporting the solc metadata trailer separately, since it changes with
+/// comments and compiler settings without changing behavior. Runtime code
+/// comes from `/wallet/getcontractinfo`; `/wallet/getcontract` only has the
+/// creation code.
+async fn verify_bytecode(
+    out: &Output,
+    client: &TronClient,
+    contract: &str,
+    artifact: &std::path::Path,
+) -> Result<serde_json::Value> {
+    let artifact_json = ContractJson::load(artifact)?;
+    let deployed = artifact_json
+        .deployed_bytecode
+        .ok_or_else(|| invalid_input(format!("{:?} has no deployedBytecode", artifact)))?;
+    let expected = hex::decode(deployed.object.trim_start_matches("0x"))
+        .context("Invalid deployedBytecode hex in artifact")?;
+    let immutables: Vec<(usize, usize)> = deployed
+        .immutable_references
+        .values()
+        .flatten()
+        .map(|r| (r.start, r.length))
+        .collect();
+
+    tron_address_to_hex(contract, client.address_prefix())?;
+    let response = client
+        .post(
+            "/wallet/getcontractinfo",
+            &serde_json::json!({ "value": contract, "visible": true }),
+        )
+        .await?;
+    let runtime = response
+        .get("runtimecode")
+        .and_then(|c| c.as_str())
+        .filter(|c| !c.is_empty())
+        .ok_or_else(|| anyhow!("No runtime code found for {}", contract))?;
+    let actual = hex::decode(runtime).context("Invalid runtime code hex")?;
+
+    let (actual_code, actual_metadata) = bytecode::split_metadata(&actual);
+    let (expected_code, expected_metadata) = bytecode::split_metadata(&expected);
+    let actual_masked = bytecode::mask_ranges(actual_code, &immutables);
+    let expected_masked = bytecode::mask_ranges(expected_code, &immutables);
+    let code_matches = actual_masked == expected_masked;
+    let metadata_matches = actual_metadata == expected_metadata;
+
+    let actual_hash = hex::encode(keccak256(&actual_masked));
+    let expected_hash = hex::encode(keccak256(&expected_masked));
+    out.line(format!(
+        "On-chain: {} bytes, keccak256 0x{}",
+        actual.len(),
+        actual_hash
+    ));
+    out.line(format!(
+        "Artifact: {} bytes, keccak256 0x{}",
+        expected.len(),
+        expected_hash
+    ));
+    out.line(format!("Immutables masked: {}", immutables.len()));
+    out.line(format!(
+        "Metadata: {}",
+        if metadata_matches {
+            "identical"
+        } else {
+            "differs"
+        }
+    ));
+
+    if !code_matches {
+        return Err(anyhow!(
+            "Bytecode mismatch: {} is not the code in {:?}",
+            contract,
+            artifact
+        ));
+    }
+    out.line(format!("\n✅ {} matches {}", contract, artifact.display()));
+
+    Ok(serde_json::json!({
+        "contract": contract,
+        "match": true,
+        "metadataMatch": metadata_matches,
+        "onChainHash": actual_hash,
+        "artifactHash": expected_hash
+    }))
+}
+
+/// Returns whether `contract` has code deployed, via `/wallet/getcontract`.
+async fn contract_exists(client: &dyn TronRpc, contract: &str) -> Result<bool> {
+    tron_address_to_hex(contract, client.address_prefix())?;
+    let response = client.get_contract(contract).await?;
+    Ok(response
+        .get("bytecode")
+        .and_then(|b| b.as_str())
+        .is_some_and(|b| !b.is_empty()))
+}
+
+/// Calls a view function via `/wallet/triggerconstantcontract` and returns the
+/// ABI-encoded result as hex.
+async fn trigger_constant(
+    client: &dyn TronRpc,
+    contract: &str,
+    function_selector: &str,
+    parameter: &str,
+) -> Result<String> {
+    let contract_hex = tron_address_to_hex(contract, client.address_prefix())?;
+    let response = client
+        .trigger_constant(&contract_hex, function_selector, parameter)
+        .await?;
+    constant_result(&response, function_selector)
+}
+
+/// The hex result of a `/wallet/triggerconstantcontract` `response`, or the
+/// node's message if the call to `function_selector` failed.
+fn constant_result(response: &serde_json::Value, function_selector: &str) -> Result<String> {
+    if response.pointer("/result/result") != Some(&serde_json::json!(true)) {
+        let msg = response
+            .pointer("/result/message")
+            .and_then(|m| m.as_str())
+            .map(decode_hex_message)
+            .unwrap_or_else(|| "Unknown error".to_string());
+        return Err(
+            TronError::Revert(format!("Call to {} failed: {}", function_selector, msg)).into(),
+        );
+    }
+
+    response
+        .get("constant_result")
+        .and_then(|r| r.get(0))
+        .and_then(|r| r.as_str())
+        .map(String::from)
+        .ok_or_else(|| anyhow!("No result returned by {}", function_selector))
+}
+
+/// Builds the common part of a `/wallet/trigger*contract` request calling
+/// `signature` (e.g. `approveTransaction(uint256)`) with hex-encoded
+/// `parameter`, in the given encoding. Addresses are hex (`41...`).
+fn contract_call_body(
+    owner_hex: &str,
+    contract_hex: &str,
+    signature: &str,
+    parameter: &str,
+    encoding: CallEncoding,
+) -> serde_json::Value {
+    let mut body = serde_json::json!({
+        "owner_address": owner_hex,
+        "contract_address": contract_hex,
+    });
+    let fields = body.as_object_mut().expect("body is an object");
+    match encoding {
+        CallEncoding::Split => {
+            fields.insert("function_selector".to_string(), signature.into());
+            fields.insert("parameter".to_string(), parameter.into());
+        }
+        CallEncoding::Data => {
+            let data = format!("{}{}", hex::encode(function_selector(signature)), parameter);
+            fields.insert("data".to_string(), data.into());
+        }
+    }
+    body
+}
+
+async fn fetch_multisig_info(client: &dyn TronRpc, contract: &str) -> Result<MultisigInfo> {
+    let usdt = trigger_constant(client, contract, "usdt()", "").await?;
+    let owners = trigger_constant(client, contract, "getOwners()", "").await?;
+    let threshold = trigger_constant(client, contract, "threshold()", "").await?;
+
+    Ok(MultisigInfo {
+        usdt: abi_word_to_tron_address(abi_word(&usdt, 0)?, client.address_prefix())?,
+        owners: decode_address_array(&owners, client.address_prefix())?,
+        threshold: abi_word_to_u128(abi_word(&threshold, 0)?)?
+            .try_into()
+            .context("Threshold does not fit in 64 bits")?,
+    })
+}
+
+/// Differences between the requested deployment and a deployed contract's
+/// configuration, one line per field; empty if they match. Owners are
+/// compared as a set, since their order carries no meaning in the contract.
+fn deployment_diff(plan: &DeployPlan, info: &MultisigInfo, prefix: u8) -> Result<Vec<String>> {
+    let mut changes = Vec::new();
+    if tron_address_to_hex(&plan.usdt, prefix)? != tron_address_to_hex(&info.usdt, prefix)? {
+        changes.push(format!(
+            "usdt: requested {}, on-chain {}",
+            plan.usdt, info.usdt
+        ));
+    }
+    if plan.threshold != info.threshold {
+        changes.push(format!(
+            "threshold: requested {}, on-chain {}",
+            plan.threshold, info.threshold
+        ));
+    }
+
+    let deployed: HashSet<String> = info
+        .owners
+        .iter()
+        .map(|owner| tron_address_to_hex(owner, prefix))
+        .collect::<Result<_, _>>()?;
+    let requested: HashSet<String> = plan
+        .owners
+        .iter()
+        .map(|owner| tron_address_to_hex(owner, prefix))
+        .collect::<Result<_, _>>()?;
+    for owner in &plan.owners {
+        if !deployed.contains(&tron_address_to_hex(owner, prefix)?) {
+            changes.push(format!("owner {}: requested, not on-chain", owner));
+        }
+    }
+    for owner in &info.owners {
+        if !requested.contains(&tron_address_to_hex(owner, prefix)?) {
+            changes.push(format!("owner {}: on-chain, not requested", owner));
+        }
+    }
+    Ok(changes)
+}
+
+/// Prints each owner's approval of multisig transaction `tx_id` and how far
+/// it is from the threshold, from read-only calls.
+async fn show_approvals(
+    out: &Output,
+    client: &dyn TronRpc,
+    contract: &str,
+    tx_id: u64,
+) -> Result<serde_json::Value> {
+    let info = client.multisig_info(contract).await?;
+    let executed = fetch_transaction_status(client, contract, tx_id)
+        .await?
+        .executed;
+
+    let mut approvals = Vec::with_capacity(info.owners.len());
+    for owner in &info.owners {
+        approvals.push((owner, is_approved(client, contract, tx_id, owner).await?));
+    }
+
+    out.line(format!("Approvals for transaction {}:", tx_id));
+    for (owner, approved) in &approvals {
+        out.line(format!("  {} {}", if *approved { "✓" } else { "✗" }, owner));
+    }
+    let approved = approvals.iter().filter(|(_, approved)| *approved).count() as u64;
+    out.line(format!(
+        "\n{}",
+        approval_summary(approved, info.owners.len(), info.threshold, executed)
+    ));
+
+    Ok(serde_json::json!({
+        "txId": tx_id,
+        "executed": executed,
+        "threshold": info.threshold,
+        "approvalCount": approved,
+        "approvals": approvals
+            .iter()
+            .map(|(owner, approved)| serde_json::json!({ "owner": owner, "approved": approved }))
+            .collect::<Vec<_>>()
+    }))
+}
+
+/// The energy a call of `signature` from `caller` would use, from a
+/// `/wallet/triggerconstantcontract` simulation. Fails if it would revert.
+async fn estimate_call_energy(
+    client: &TronClient,
+    caller: &str,
+    contract: &str,
+    signature: &str,
+    parameter: &str,
+) -> Result<u64> {
+    let body = contract_call_body(
+        &tron_address_to_hex(caller, client.address_prefix())?,
+        &tron_address_to_hex(contract, client.address_prefix())?,
+        signature,
+        parameter,
+        client.options.call_encoding,
+    );
+    let response = client
+        .post("/wallet/triggerconstantcontract", &body)
+        .await?;
+    simulated_energy(&response).with_context(|| format!("{} from {}", signature, caller))
+}
+
+/// `energy_used` of a `triggerconstantcontract` response. A simulated revert
+/// still reports `result.result`, so the transaction's `ret` is checked too.
+fn simulated_energy(response: &serde_json::Value) -> Result<u64> {
+    if response.pointer("/result/result") != Some(&serde_json::json!(true)) {
+        let msg = response
+            .pointer("/result/message")
+            .and_then(|m| m.as_str())
+            .map(decode_hex_message)
+            .unwrap_or_else(|| "Unknown error".to_string());
+        return Err(anyhow!("Simulation failed: {}", msg));
+    }
+    if response
+        .pointer("/transaction/ret/0/ret")
+        .and_then(|r| r.as_str())
+        == Some("FAILED")
+    {
+        let data = response
+            .pointer("/constant_result/0")
+            .and_then(|r| r.as_str())
+            .unwrap_or_default();
+        let err = match hex::decode(data) {
+            Ok(data) => revert_error(&data),
+            Err(_) => TronError::Revert(format!("0x{}", data)),
+        };
+        return Err(err.context("Would revert").into());
+    }
+    response
+        .get("energy_used")
+        .and_then(|e| e.as_u64())
+        .ok_or_else(|| anyhow!("No energy_used in simulation response"))
+}
+
+/// One owner's line of [`estimate_approvals`].
+#[derive(Debug)]
+enum ApprovalEstimate {
+    Approved,
+    Energy(u64),
+    Failed(anyhow::Error),
+}
+
+/// Simulates `approveTransaction(tx_id)` from each owner that hasn't
+/// approved yet and prints what the approvals still needed would cost.
+/// Until the threshold is one approval away, the simulations don't include
+/// the transfer the last approval executes, so that is simulated separately
+/// as a `transfer` from the contract.
+async fn estimate_approvals(
+    out: &Output,
+    client: &TronClient,
+    contract: &str,
+    tx_id: u64,
+) -> Result<serde_json::Value> {
+    let info = client.multisig_info(contract).await?;
+    let param = encode_params(&[AbiValue::uint(tx_id.into())]);
+    let status = fetch_transaction_status(client, contract, tx_id).await?;
+    if status.executed {
+        return Err(invalid_input(format!(
+            "Transaction {} has already executed; no approvals are needed",
+            tx_id
+        )));
+    }
+
+    let mut owners = Vec::with_capacity(info.owners.len());
+    for owner in &info.owners {
+        let estimate = if is_approved(client, contract, tx_id, owner).await? {
+            ApprovalEstimate::Approved
+        } else {
+            out.phase(format!("🔎 Simulating the approval from {}...", owner));
+            match estimate_call_energy(
+                client,
+                owner,
+                contract,
+                "approveTransaction(uint256)",
+                &param,
+            )
+            .await
+            {
+                Ok(energy) => ApprovalEstimate::Energy(energy),
+                Err(err) => ApprovalEstimate::Failed(err),
+            }
+        };
+        owners.push((owner.clone(), estimate));
+    }
+
+    let needed = info.threshold.saturating_sub(status.approval_count);
+    let transfer_energy = if needed > 1 {
+        // `to` and `amount` lead getTransaction's result, encoded as the
+        // transfer's parameters
+        let result = trigger_constant(client, contract, "getTransaction(uint256)", &param).await?;
+        let transfer = format!("{}{}", abi_word(&result, 0)?, abi_word(&result, 1)?);
+        out.phase("🔎 Simulating the transfer on execution...");
+        match estimate_call_energy(
+            client,
+            contract,
+            &info.usdt,
+            "transfer(address,uint256)",
+            &transfer,
+        )
+        .await
+        {
+            Ok(energy) => Some(energy),
+            Err(err) => {
+                out.warn(format!(
+                    "⚠️  Not counting the transfer on execution: {:#}",
+                    err
+                ));
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let energy_fee = match client.chain_parameters().await {
+        Ok(params) => params.get("getEnergyFee").copied().filter(|fee| *fee > 0),
+        Err(err) => {
+            out.detail(format!("Skipping the cost in TRX: {:#}", err));
+            None
+        }
+    };
+    Ok(print_approval_estimate(
+        out,
+        &ApprovalCosts {
+            tx_id,
+            threshold: info.threshold,
+            needed,
+            energy_fee: energy_fee.map(|fee| fee as u64),
+            transfer_energy,
+        },
+        &owners,
+    ))
+}
+
+/// What [`print_approval_estimate`] totals besides the per-owner estimates.
+struct ApprovalCosts {
+    tx_id: u64,
+    threshold: u64,
+    /// Approvals still needed to reach the threshold
+    needed: u64,
+    /// SUN per energy; `None` lists energy only
+    energy_fee: Option<u64>,
+    /// The transfer executed by the last approval, when the estimates don't
+    /// include it
+    transfer_energy: Option<u64>,
+}
+
+/// The warning for a total that covers fewer than the `needed` approvals,
+/// when only `counted` owners could be estimated.
+fn approval_shortfall(needed: u64, counted: usize) -> Option<String> {
+    (counted < needed as usize).then(|| {
+        format!(
+            "⚠️  {} more approvals are needed but only {} owners can be estimated; \
+             the total covers those",
+            needed, counted
+        )
+    })
+}
+
+/// Prints each owner's estimate and the total for the next `costs.needed`
+/// approvals (in owner order), and returns them for `--json`.
+fn print_approval_estimate(
+    out: &Output,
+    costs: &ApprovalCosts,
+    owners: &[(String, ApprovalEstimate)],
+) -> serde_json::Value {
+    let cost = |energy: u64| costs.energy_fee.map(|fee| energy.saturating_mul(fee));
+    let priced = |energy: u64| match cost(energy) {
+        Some(sun) => format!("~{} energy (~{} TRX)", energy, format_trx(sun)),
+        None => format!("~{} energy", energy),
+    };
+
+    out.line(format!("⚡ Approval costs for transaction {}", costs.tx_id));
+    if let Some(fee) = costs.energy_fee {
+        out.line(format!("   Energy price: {} SUN/energy", fee));
+    }
+    let mut rows = Vec::with_capacity(owners.len());
+    for (owner, estimate) in owners {
+        let (line, row) = match estimate {
+            ApprovalEstimate::Approved => (
+                "✓ already approved".to_string(),
+                serde_json::json!({ "owner": owner, "approved": true }),
+            ),
+            ApprovalEstimate::Energy(energy) => (
+                priced(*energy),
+                serde_json::json!({
+                    "owner": owner,
+                    "approved": false,
+                    "energy": energy,
+                    "costSun": cost(*energy)
+                }),
+            ),
+            ApprovalEstimate::Failed(err) => (
+                format!("⚠️  {:#}", err),
+                serde_json::json!({
+                    "owner": owner,
+                    "approved": false,
+                    "error": format!("{:#}", err)
+                }),
+            ),
+        };
+        out.line(format!("   {}  {}", owner, line));
+        rows.push(row);
+    }
+
+    let estimates: Vec<u64> = owners
+        .iter()
+        .filter_map(|(_, estimate)| match estimate {
+            ApprovalEstimate::Energy(energy) => Some(*energy),
+            _ => None,
+        })
+        .collect();
+    let counted = estimates.len().min(costs.needed as usize);
+    if let Some(warning) = approval_shortfall(costs.needed, counted) {
+        out.warn(warning);
+    }
+    let total_energy = estimates[..counted]
+        .iter()
+        .chain(costs.transfer_energy.as_ref())
+        .fold(0u64, |total, energy| total.saturating_add(*energy));
+
+    out.line(format!(
+        "\n   {} of {} approvals still needed",
+        costs.needed, costs.threshold
+    ));
+    if let Some(energy) = costs.transfer_energy {
+        out.line(format!("   Transfer on execution: {}", priced(energy)));
+    }
+    out.line(format!(
+        "   Total to threshold:    {}",
+        priced(total_energy)
+    ));
+
+    serde_json::json!({
+        "txId": costs.tx_id,
+        "threshold": costs.threshold,
+        "approvalsNeeded": costs.needed,
+        "energyFee": costs.energy_fee,
+        "owners": rows,
+        "transferEnergy": costs.transfer_energy,
+        "totalEnergy": total_energy,
+        "totalCostSun": cost(total_energy)
+    })
+}
+
+/// A multisig's token balance, as `balances` lists it.
+#[derive(Debug)]
+struct ContractBalance {
+    token: String,
+    decimals: u32,
+    /// Base units
+    amount: u128,
+}
+
+async fn contract_balance(client: &TronClient, contract: &str) -> Result<ContractBalance> {
+    validate_tron_address(contract, client.address_prefix()).context("Invalid contract address")?;
+    let info = client.multisig_info(contract).await?;
+    let (result, decimals) = tokio::try_join!(
+        trigger_constant(client, contract, "getBalance()", ""),
+        token_decimals(client, &info.usdt),
+    )?;
+    Ok(ContractBalance {
+        token: info.usdt,
+        decimals,
+        amount: abi_word_to_u128(abi_word(&result, 0)?)?,
+    })
+}
+
+/// Reads the balance of every contract in `contracts` concurrently and
+/// prints them with their total.
+async fn show_balances(
+    out: &Output,
+    client: &TronClient,
+    contracts: &[String],
+    fail_fast: bool,
+) -> Result<serde_json::Value> {
+    if contracts.is_empty() {
+        return Err(invalid_input("--contracts lists no contracts"));
+    }
+    let balances = query_targets(contracts, fail_fast, |contract| {
+        Box::pin(contract_balance(client, contract))
+    })
+    .await?;
+    Ok(print_balances(out, &balances))
+}
+
+/// Prints one row per contract (or its error) and a total per token, and
+/// returns them for `--json`.
+fn print_balances(out: &Output, balances: &[TargetOutcome<ContractBalance>]) -> serde_json::Value {
+    let width = balances
+        .iter()
+        .map(|balance| balance.target.len())
+        .max()
+        .unwrap_or_default();
+    let mut totals: std::collections::BTreeMap<&str, (u32, u128)> = Default::default();
+    let mut rows = Vec::with_capacity(balances.len());
+    out.line("💰 Multisig balances");
+    for TargetOutcome {
+        target: contract,
+        result,
+    } in balances
+    {
+        match result {
+            Ok(balance) => {
+                let total = totals
+                    .entry(&balance.token)
+                    .or_insert((balance.decimals, 0));
+                total.1 = total.1.saturating_add(balance.amount);
+                let tokens = format_token_amount(balance.amount, balance.decimals);
+                out.line(format!("   {:<width$}  {:>20} USDT", contract, tokens));
+                rows.push(serde_json::json!({
+                    "contract": contract,
+                    "token": balance.token,
+                    "balance": balance.amount.to_string(),
+                    "balanceTokens": tokens
+                }));
+            }
+            Err(err) => {
+                out.line(format!("   {:<width$}  ⚠️  {:#}", contract, err));
+                rows.push(serde_json::json!({
+                    "contract": contract,
+                    "error": format!("{:#}", err)
+                }));
+            }
+        }
+    }
+
+    let read = balances
+        .iter()
+        .filter(|balance| balance.result.is_ok())
+        .count();
+    for (token, (decimals, amount)) in &totals {
+        let label = match totals.len() {
+            1 => "Total".to_string(),
+            _ => format!("Total ({})", token),
+        };
+        out.line(format!(
+            "   {:<width$}  {:>20} USDT",
+            label,
+            format_token_amount(*amount, *decimals)
+        ));
+    }
+    if read < balances.len() {
+        out.warn(format!(
+            "⚠️  {} of {} contracts could not be read; the total leaves them out",
+            balances.len() - read,
+            balances.len()
+        ));
+    }
+    serde_json::json!({
+        "contracts": rows,
+        "totals": totals
+            .iter()
+            .map(|(token, (decimals, amount))| serde_json::json!({
+                "token": token,
+                "balance": amount.to_string(),
+                "balanceTokens": format_token_amount(*amount, *decimals)
+            }))
+            .collect::<Vec<_>>(),
+        "failed": balances.len() - read
+    })
+}
+
+/// A payout to propose with [`submit_transaction`].
+struct Payout<'a> {
+    contract: &'a str,
+    to: &'a str,
+    /// Base units, or `amount_tokens` scaled by the multisig token's decimals
+    amount: Option<u128>,
+    amount_tokens: Option<&'a str>,
+}
+
+/// Submits `payout` from an owner's key. A recipient that has never been
+/// activated is refused unless `allow_inactive`: TRC20 transfers to it cost
+/// extra energy to create its balance entry, and it can't move the USDT
+/// until someone sends it TRX, which is often a sign of a mistyped address.
+async fn submit_transaction(
+    out: &Output,
+    client: &TronClient,
+    payout: &Payout<'_>,
+    signer: &SignerArgs,
+    allow_inactive: bool,
+    fee_limit: u64,
+) -> Result<serde_json::Value> {
+    let prefix = client.address_prefix();
+    let Payout { contract, to, .. } = *payout;
+    validate_tron_address(to, prefix).context("Invalid --to address")?;
+    if tron_address_to_hex(to, prefix)? == tron_address_to_hex(contract, prefix)? {
+        return Err(invalid_input("The multisig cannot pay itself"));
+    }
+
+    if !get_account(client, to).await?.activated {
+        let message = format!(
+            "Recipient {} has never been activated on TRON: the transfer burns extra \
+             energy to create its USDT balance, and the recipient needs TRX before it can move it",
+            to
+        );
+        if !allow_inactive {
+            return Err(invalid_input(format!(
+                "{}. Check the address, or pass --allow-inactive to submit anyway",
+                message
+            )));
+        }
+        out.warn(format!("⚠️  {}.", message));
+    }
+
+    let info = client.multisig_info(contract).await?;
+    let amount = resolve_amount(
+        client,
+        payout.amount,
+        payout.amount_tokens,
+        None,
+        Some(&info.usdt),
+    )
+    .await?;
+    if amount == 0 {
+        return Err(invalid_input("Amount must be greater than zero"));
+    }
+    let signer = signer.signer(prefix)?;
+    let submitter = signer.address(prefix)?;
+    let submitter_hex = tron_address_to_hex(&submitter, prefix)?;
+    let is_owner = info
+        .owners
+        .iter()
+        .any(|owner| tron_address_to_hex(owner, prefix).is_ok_and(|hex| hex == submitter_hex));
+    if !is_owner {
+        return Err(invalid_input(format!(
+            "{} is not an owner of {}",
+            submitter, contract
+        )));
+    }
+
+    check_fee_limit(out, client, fee_limit, false).await?;
+    let signature = "submitTransaction(address,uint256)";
+    let param = encode_params(&[AbiValue::address(to, prefix)?, AbiValue::uint(amount)]);
+    out.phase(format!("📡 Calling {}...", signature));
+    let txid = send_contract_call(client, &signer, contract, signature, &param, fee_limit).await?;
+
+    out.line(format!("✅ Submitted payout of {} to {}", amount, to));
+    out.line(format!("Transaction: {}", txid));
+    if let Some(url) = client.explorer_link(ExplorerLink::Transaction(&txid)) {
+        out.line(format!("View transaction: {}", url));
+    }
+    out.line(format!(
+        "\nℹ️  {} more approval(s) needed; the multisig transaction ID is in the TransactionSubmitted event (see `history`, or pass --wait).",
+        info.threshold.saturating_sub(1)
+    ));
+    Ok(serde_json::json!({
+        "contract": contract,
+        "to": to,
+        "amount": amount.to_string(),
+        "transaction": txid
+    }))
+}
+
+/// Waits for the submission in `result` to confirm and adds the multisig
+/// transaction ID its `TransactionSubmitted` event carries, which the
+/// owners need to approve it.
+async fn with_submitted_tx_id(
+    out: &Output,
+    client: &dyn TronRpc,
+    mut result: serde_json::Value,
+    contract: &str,
+) -> Result<serde_json::Value> {
+    let txid = result["transaction"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    let info = wait_for_confirmation(out, client, &txid).await?;
+    let outcome = TxOutcome::classify(&info, &TransactionReceipt::from_info(&info)?);
+    if outcome != TxOutcome::Success {
+        return Err(outcome.error(&txid));
+    }
+    let tx_id = submitted_tx_id(&info, contract, client.address_prefix())?
+        .ok_or_else(|| anyhow!("Transaction {} has no TransactionSubmitted event", txid))?;
+    out.line(format!("Multisig transaction ID: {}", tx_id));
+    result["txId"] = tx_id.into();
+    Ok(result)
+}
+
+/// The multisig transaction ID in a `TransactionSubmitted` log of
+/// `contract`, if the transaction info has one.
+fn submitted_tx_id(info: &serde_json::Value, contract: &str, prefix: u8) -> Result<Option<u64>> {
+    let contract = parse_address(contract, prefix)?;
+    let topic = hex::encode(keccak256(b"TransactionSubmitted(uint256,address,uint256)"));
+    let logs = info.get("log").and_then(|l| l.as_array());
+    for log in logs.map(Vec::as_slice).unwrap_or_default() {
+        let topics = log.get("topics").and_then(|t| t.as_array());
+        let (Some(event), Some(tx_id)) = (
+            topics.and_then(|t| t.first()).and_then(|t| t.as_str()),
+            topics.and_then(|t| t.get(1)).and_then(|t| t.as_str()),
+        ) else {
+            continue;
+        };
+        // Log addresses are 20-byte hex without the 0x41 prefix
+        let emitter = log
+            .get("address")
+            .and_then(|a| a.as_str())
+            .unwrap_or_default();
+        if event == topic && parse_address(emitter, prefix).ok() == Some(contract) {
+            return Ok(Some(
+                abi_word_to_u128(tx_id)?
+                    .try_into()
+                    .context("Transaction ID does not fit in 64 bits")?,
+            ));
+        }
+    }
+    Ok(None)
+}
+
+/// A multisig transaction as `getTransaction(uint256)` returns it.
+#[derive(Debug, PartialEq)]
+struct MultisigTransaction {
+    to: String,
+    /// Token base units
+    amount: u128,
+    executed: bool,
+    approval_count: u64,
+    /// Unix seconds
+    created_at: u64,
+    /// Unix seconds; each approval extends it
+    expires_at: u64,
+}
+
+impl MultisigTransaction {
+    fn decode(result: &str, prefix: u8) -> Result<Self> {
+        let word = |index| abi_word(result, index);
+        let number = |index| -> Result<u64> {
+            abi_word_to_u128(word(index)?)?
+                .try_into()
+                .context("Value does not fit in 64 bits")
+        };
+        Ok(MultisigTransaction {
+            to: abi_word_to_tron_address(word(0)?, prefix)?,
+            amount: abi_word_to_u128(word(1)?)?,
+            executed: abi_word_to_u128(word(2)?)? != 0,
+            approval_count: number(3)?,
+            created_at: number(4)?,
+            expires_at: number(5)?,
+        })
+    }
+
+    /// Whether the contract now rejects approvals and revocations with
+    /// `TransactionExpired()` (it checks `block.timestamp > expiresAt`).
+    fn is_expired(&self) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        !self.executed && now > self.expires_at
+    }
+}
+
+/// Prints multisig transaction `tx_id` with its approvals against the
+/// threshold and whether it has expired.
+async fn show_multisig_transaction(
+    out: &Output,
+    client: &TronClient,
+    contract: &str,
+    tx_id: u64,
+) -> Result<serde_json::Value> {
+    let info = client.multisig_info(contract).await?;
+    let Some(transaction) = fetch_multisig_transaction(client, contract, tx_id).await? else {
+        let count = trigger_constant(client, contract, "getTransactionCount()", "").await?;
+        return Err(invalid_input(format!(
+            "Transaction {} does not exist; {} has {} transactions (IDs from 0)",
+            tx_id,
+            contract,
+            abi_word_to_u128(abi_word(&count, 0)?)?
+        )));
+    };
+    let decimals = token_decimals(client, &info.usdt).await?;
+    let expired = transaction.is_expired();
+
+    let amount = format_token_amount(transaction.amount, decimals);
+    out.line(format!("📄 Multisig transaction {}", tx_id));
+    out.line(format!("   To:        {}", transaction.to));
+    out.line(format!("   Amount:    {} USDT", amount));
+    out.line(format!(
+        "   Approvals: {}",
+        approval_summary(
+            transaction.approval_count,
+            info.owners.len(),
+            info.threshold,
+            transaction.executed
+        )
+    ));
+    out.line(format!(
+        "   Created:   {} UTC",
+        format_utc(transaction.created_at as i64 * 1000)
+    ));
+    out.line(format!(
+        "   Expires:   {} UTC{}",
+        format_utc(transaction.expires_at as i64 * 1000),
+        if expired { " (expired)" } else { "" }
+    ));
+
+    Ok(serde_json::json!({
+        "txId": tx_id,
+        "to": transaction.to,
+        "amount": transaction.amount.to_string(),
+        "amountTokens": amount,
+        "executed": transaction.executed,
+        "approvalCount": transaction.approval_count,
+        "threshold": info.threshold,
+        "createdAt": transaction.created_at,
+        "expiresAt": transaction.expires_at,
+        "expired": expired
+    }))
+}
+
+/// Multisig transaction `tx_id`, or `None` if the contract reverts with
+/// `TransactionNotFound()`. Any other failure is an error.
+async fn fetch_multisig_transaction(
+    client: &dyn TronRpc,
+    contract: &str,
+    tx_id: u64,
+) -> Result<Option<MultisigTransaction>> {
+    let signature = "getTransaction(uint256)";
+    let param = encode_params(&[AbiValue::uint(tx_id.into())]);
+    let response = client
+        .trigger_constant(
+            &tron_address_to_hex(contract, client.address_prefix())?,
+            signature,
+            &param,
+        )
+        .await?;
+    // The revert data comes back in constant_result whether or not the node
+    // also marks the call as failed
+    let not_found = hex::encode(function_selector("TransactionNotFound()"));
+    let data = response
+        .pointer("/constant_result/0")
+        .and_then(|r| r.as_str())
+        .unwrap_or_default();
+    if data.len() >= 8 && data[..8].eq_ignore_ascii_case(&not_found) {
+        return Ok(None);
+    }
+    let result = constant_result(&response, signature)?;
+    MultisigTransaction::decode(&result, client.address_prefix())
+        .with_context(|| format!("Unexpected {} result for transaction {}", signature, tx_id))
+        .map(Some)
+}
+
+/// Multisig transaction `tx_id`, failing if it doesn't exist.
+async fn fetch_transaction_status(
+    client: &dyn TronRpc,
+    contract: &str,
+    tx_id: u64,
+) -> Result<MultisigTransaction> {
+    fetch_multisig_transaction(client, contract, tx_id)
+        .await
+        .with_context(|| format!("Couldn't read transaction {}", tx_id))?
+        .ok_or_else(|| invalid_input(format!("Transaction {} does not exist", tx_id)))
+}
+
+/// Whether `owner` has approved multisig transaction `tx_id`.
+async fn is_approved(
+    client: &dyn TronRpc,
+    contract: &str,
+    tx_id: u64,
+    owner: &str,
+) -> Result<bool> {
+    let param = encode_params(&[
+        AbiValue::uint(tx_id.into()),
+        AbiValue::address(owner, client.address_prefix())?,
+    ]);
+    let result = trigger_constant(client, contract, "isApproved(uint256,address)", &param).await?;
+    Ok(abi_word_to_u128(abi_word(&result, 0)?)? != 0)
+}
+
+/// Fails unless the contract's `isOwner` mapping lists `address`, since
+/// every approval from a non-owner reverts and still burns its fee.
+async fn check_is_owner(client: &dyn TronRpc, contract: &str, address: &str) -> Result<()> {
+    let param = encode_params(&[AbiValue::address(address, client.address_prefix())?]);
+    let result = trigger_constant(client, contract, "isOwner(address)", &param).await?;
+    if abi_word_to_u128(abi_word(&result, 0)?)? == 0 {
+        return Err(invalid_input(format!(
+            "{} is not an owner of {}, so the approval would revert. Check that this is \
+             the right key, or pass --skip-owner-check to send it anyway",
+            address, contract
+        )));
+    }
+    Ok(())
+}
+
+/// The reads [`preflight_approval`] makes before an approval is sent; each
+/// has a `--skip-*` flag.
+#[derive(Debug, Clone, Copy)]
+struct ApprovalChecks {
+    /// The approver is an owner (`isOwner`)
+    owner: bool,
+    /// The transaction hasn't executed yet (`getTransaction`)
+    not_executed: bool,
+}
+
+/// Catches the approvals that are certain to revert, and so waste their fee,
+/// before they are signed: ones from a non-owner, and ones for a transaction
+/// that already executed, typically because other owners reached the
+/// threshold between this owner checking and signing.
+async fn preflight_approval(
+    out: &Output,
+    client: &dyn TronRpc,
+    contract: &str,
+    tx_id: u64,
+    approver: &str,
+    checks: ApprovalChecks,
+) -> Result<()> {
+    if checks.owner {
+        out.phase(format!("🔎 Checking that {} is an owner...", approver));
+        check_is_owner(client, contract, approver).await?;
+    }
+    if checks.not_executed {
+        out.phase(format!(
+            "🔎 Checking that transaction {} is pending...",
+            tx_id
+        ));
+        if fetch_transaction_status(client, contract, tx_id)
+            .await?
+            .executed
+        {
+            return Err(invalid_input(format!(
+                "Transaction {} has already executed, so the transfer went through (or, if \
+                 every approval was revoked, it was cancelled) and an approval would revert. \
+                 Pass --skip-executed-check to send it anyway",
+                tx_id
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Approves multisig transaction `tx_id` from the signer's address after the
+/// enabled [`preflight_approval`] checks.
+async fn approve_transaction(
+    out: &Output,
+    client: &TronClient,
+    contract: &str,
+    tx_id: u64,
+    signer: &Signer,
+    checks: ApprovalChecks,
+    fee_limit: u64,
+) -> Result<serde_json::Value> {
+    let approver = signer.address(client.address_prefix())?;
+    preflight_approval(out, client, contract, tx_id, &approver, checks).await?;
+
+    check_fee_limit(out, client, fee_limit, false).await?;
+    let signature = "approveTransaction(uint256)";
+    let param = encode_params(&[AbiValue::uint(tx_id.into())]);
+    out.phase(format!("📡 Calling {}...", signature));
+    let txid = send_contract_call(client, signer, contract, signature, &param, fee_limit).await?;
+
+    out.line(format!("✅ Approval of transaction {} broadcast", tx_id));
+    out.line(format!("Transaction: {}", txid));
+    if let Some(url) = client.explorer_link(ExplorerLink::Transaction(&txid)) {
+        out.line(format!("View transaction: {}", url));
+    }
+    Ok(serde_json::json!({
+        "contract": contract,
+        "txId": tx_id,
+        "approver": approver,
+        "transaction": txid
+    }))
+}
+
+/// Catches the revokes that are certain to revert: of a transaction that
+/// already executed, or from a key that hasn't approved it (which covers
+/// non-owners, who never have).
+async fn preflight_revoke(
+    out: &Output,
+    client: &dyn TronRpc,
+    contract: &str,
+    tx_id: u64,
+    revoker: &str,
+) -> Result<()> {
+    out.phase(format!(
+        "🔎 Checking that {} has approved transaction {}...",
+        revoker, tx_id
+    ));
+    let transaction = fetch_transaction_status(client, contract, tx_id).await?;
+    if transaction.executed {
+        return Err(invalid_input(format!(
+            "Transaction {} has already executed (or was cancelled), so there is no approval \
+             to revoke",
+            tx_id
+        )));
+    }
+    if transaction.is_expired() {
+        return Err(invalid_input(format!(
+            "Transaction {} expired at {} UTC, so the revoke would revert with \
+             TransactionExpired()",
+            tx_id,
+            format_utc(transaction.expires_at as i64 * 1000)
+        )));
+    }
+    if !is_approved(client, contract, tx_id, revoker).await? {
+        return Err(invalid_input(format!(
+            "{} has not approved transaction {}, so the revoke would revert. Check the key \
+             and --tx-id, or pass --skip-checks to send it anyway",
+            revoker, tx_id
+        )));
+    }
+    Ok(())
+}
+
+/// Revokes the signer's approval of multisig transaction `tx_id`, after
+/// [`preflight_revoke`] if `check`.
+async fn revoke_approval(
+    out: &Output,
+    client: &TronClient,
+    contract: &str,
+    tx_id: u64,
+    signer: &Signer,
+    check: bool,
+    fee_limit: u64,
+) -> Result<serde_json::Value> {
+    let revoker = signer.address(client.address_prefix())?;
+    if check {
+        preflight_revoke(out, client, contract, tx_id, &revoker).await?;
+    }
+
+    check_fee_limit(out, client, fee_limit, false).await?;
+    let signature = "revokeApproval(uint256)";
+    let param = encode_params(&[AbiValue::uint(tx_id.into())]);
+    out.phase(format!("📡 Calling {}...", signature));
+    let txid = send_contract_call(client, signer, contract, signature, &param, fee_limit).await?;
+
+    out.line(format!("✅ Revocation of transaction {} broadcast", tx_id));
+    out.line(format!("Transaction: {}", txid));
+    if let Some(url) = client.explorer_link(ExplorerLink::Transaction(&txid)) {
+        out.line(format!("View transaction: {}", url));
+    }
+    Ok(serde_json::json!({
+        "contract": contract,
+        "txId": tx_id,
+        "revoker": revoker,
+        "transaction": txid
+    }))
+}
+
+/// One multisig transaction approved with several keys (`approve` with
+/// repeated `--private-key` or `--keys-file`).
+struct ApprovalRun<'a> {
+    contract: &'a str,
+    tx_id: u64,
+    checks: ApprovalChecks,
+    fee_limit: u64,
+    cost_summary: bool,
+}
+
+/// Approves `run.tx_id` with each of `signers` in turn. Each approval is
+/// confirmed before the next is built, so the next one's checks and
+/// reference block see it; once the transaction executes, the remaining
+/// keys are left unused. A failed approval doesn't stop the others, but
+/// fails the command once all are reported.
+async fn approve_with_each(
+    out: &Output,
+    client: &TronClient,
+    run: &ApprovalRun<'_>,
+    signers: &[Signer],
+) -> Result<serde_json::Value> {
+    let mut seen = HashSet::new();
+    for signer in signers {
+        let address = signer.address(client.address_prefix())?;
+        if !seen.insert(address.clone()) {
+            return Err(invalid_input(format!(
+                "The key for {} is given twice",
+                address
+            )));
+        }
+    }
+
+    let mut approvals = Vec::new();
+    let mut failures = Vec::new();
+    for (i, signer) in signers.iter().enumerate() {
+        let approver = signer.address(client.address_prefix())?;
+        if i > 0
+            && run.checks.not_executed
+            && fetch_transaction_status(client, run.contract, run.tx_id)
+                .await?
+                .executed
+        {
+            out.line(format!(
+                "ℹ️  Transaction {} has executed; the remaining {} keys are not needed",
+                run.tx_id,
+                signers.len() - i
+            ));
+            break;
+        }
+        out.line(format!(
+            "\n🔑 Approver {} of {}: {}",
+            i + 1,
+            signers.len(),
+            approver
+        ));
+        let approval = async {
+            let result = approve_transaction(
+                out,
+                client,
+                run.contract,
+                run.tx_id,
+                signer,
+                run.checks,
+                run.fee_limit,
+            )
+            .await?;
+            let txid = result["transaction"].as_str().unwrap_or_default();
+            let cost = if run.cost_summary {
+                Some(confirmed_cost(out, client, txid).await?)
+            } else {
+                let info = wait_for_confirmation(out, client, txid).await?;
+                let outcome = TxOutcome::classify(&info, &TransactionReceipt::from_info(&info)?);
+                if outcome != TxOutcome::Success {
+                    return Err(outcome.error(txid));
+                }
+                None
+            };
+            Ok::<_, anyhow::Error>(serde_json::json!({
+                "approver": approver,
+                "transaction": txid,
+                "cost": cost
+            }))
+        };
+        match approval.await {
+            Ok(approval) => approvals.push(approval),
+            Err(err) => {
+                out.warn(format!("⚠️  Approval by {} failed: {:#}", approver, err));
+                failures.push(format!("{}: {:#}", approver, err));
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        return Err(anyhow!(
+            "{} of {} approvals failed:\n  {}",
+            failures.len(),
+            failures.len() + approvals.len(),
+            failures.join("\n  ")
+        ));
+    }
+    Ok(serde_json::json!({
+        "contract": run.contract,
+        "txId": run.tx_id,
+        "approvals": approvals
+    }))
+}
+
+/// Reads a `--keys-file`: one hex private key per line, skipping blank
+/// lines and `#` comments. Every key is checked before any is used.
+fn read_keys_file(path: &std::path::Path) -> Result<Vec<String>> {
+    let text =
+        read_text_file(path).with_context(|| format!("Failed to read keys file: {:?}", path))?;
+    let keys: Vec<String> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect();
+    for (i, key) in keys.iter().enumerate() {
+        public_key_from_private_key(key)
+            .with_context(|| format!("Invalid key {} in {:?}", i + 1, path))?;
+    }
+    if keys.is_empty() {
+        return Err(invalid_input(format!("No keys in {:?}", path)));
+    }
+    Ok(keys)
+}
+
+/// Executes multisig transaction `tx_id` via `executeTransaction(uint256)` if
+/// the ABI has it. Otherwise the contract executes on the approval that meets
+/// the threshold, so this only explains that and reports how far off it is.
+async fn execute_transaction(
+    out: &Output,
+    client: &TronClient,
+    abi: &ContractAbi,
+    contract: &str,
+    tx_id: u64,
+    signer: &SignerArgs,
+    fee_limit: u64,
+) -> Result<serde_json::Value> {
+    let info = client.multisig_info(contract).await?;
+    let status = fetch_transaction_status(client, contract, tx_id).await?;
+    let summary = approval_summary(
+        status.approval_count,
+        info.owners.len(),
+        info.threshold,
+        status.executed,
+    );
+    let execute_fn = abi
+        .function("executeTransaction", 1)
+        .ok()
+        .filter(|entry| entry.input_types().ok().as_deref() == Some(&[AbiType::Uint(256)]));
+
+    let Some(execute_fn) = execute_fn else {
+        out.line(format!("Transaction {}: {}", tx_id, summary));
+        if !status.executed {
+            out.line("\nℹ️  This contract has no executeTransaction(uint256): the transfer runs automatically");
+            out.line("   as part of the approval that reaches the threshold. Nothing to execute separately.");
+        }
+        return Ok(serde_json::json!({
+            "txId": tx_id,
+            "autoExecutes": true,
+            "executed": status.executed,
+            "approvalCount": status.approval_count,
+            "threshold": info.threshold
+        }));
+    };
+
+    if status.executed {
+        return Err(invalid_input(format!(
+            "Transaction {} was already executed",
+            tx_id
+        )));
+    }
+    if status.approval_count < info.threshold {
+        return Err(invalid_input(format!(
+            "Transaction {} is not ready to execute: {}",
+            tx_id, summary
+        )));
+    }
+    let signer = signer.signer(client.address_prefix())?;
+
+    check_fee_limit(out, client, fee_limit, false).await?;
+    let signature = execute_fn.signature()?;
+    let param = encode_params(&[AbiValue::uint(tx_id.into())]);
+    out.phase(format!("📡 Calling {}...", signature));
+    let txid = send_contract_call(client, &signer, contract, &signature, &param, fee_limit).await?;
+
+    out.line(format!("✅ Execution of transaction {} broadcast", tx_id));
+    out.line(format!("Transaction: {}", txid));
+    if let Some(url) = client.explorer_link(ExplorerLink::Transaction(&txid)) {
+        out.line(format!("View transaction: {}", url));
+    }
+    Ok(serde_json::json!({
+        "txId": tx_id,
+        "autoExecutes": false,
+        "transaction": txid
+    }))
+}
+
+/// Builds a state-changing call of `signature` on `contract` from the key's
+/// address, signs it and broadcasts it. Returns the transaction ID.
+async fn send_contract_call(
+    client: &TronClient,
+    signer: &Signer,
+    contract: &str,
+    signature: &str,
+    parameter: &str,
+    fee_limit: u64,
+) -> Result<String> {
+    let prefix = client.address_prefix();
+    let owner_hex = tron_address_to_hex(&signer.address(prefix)?, prefix)?;
+    let contract_hex = tron_address_to_hex(contract, prefix)?;
+    let mut body = contract_call_body(
+        &owner_hex,
+        &contract_hex,
+        signature,
+        parameter,
+        client.options.call_encoding,
+    );
+    body["fee_limit"] = fee_limit.into();
+    body["call_value"] = 0.into();
+
+    let response = client.post("/wallet/triggersmartcontract", &body).await?;
+    let mut transaction = parse_transaction_response(&response, client.options.api_version)?;
+    let tx_id = distinct_tx_id(
+        &client.out,
+        &mut client.built_tx_ids.lock().unwrap(),
+        &mut transaction,
+    )?;
+    client.out.phase(format!("Transaction ID: {}", tx_id));
+
+    let signed_tx = signer.sign_transaction_json(&transaction, &tx_id, V_OFFSET_TRON)?;
+    broadcast_transaction(client, &signed_tx).await?;
+    Ok(tx_id)
+}
+
+/// Active-permission operations bitmap (bit N = contract type N) used by
+/// TRON's documentation and wallets: transfers, staking, voting and smart
+/// contract calls, without `AccountPermissionUpdateContract` (46), which
+/// stays with the owner permission.
+const DEFAULT_ACTIVE_OPERATIONS: &str =
+    "7fff1fc0033e0000000000000000000000000000000000000000000000000000";
+
+/// Chain parameter with the most keys a permission may have.
+const TOTAL_SIGN_NUM_PARAM: &str = "getTotalSignNum";
+
+/// Used when the chain doesn't report [`TOTAL_SIGN_NUM_PARAM`].
+const DEFAULT_TOTAL_SIGN_NUM: i64 = 5;
+
+/// `/wallet/accountpermissionupdate` body giving `account_hex` an owner and
+/// an active permission over `keys_hex` (weight 1 each) with `threshold`.
+fn permission_update_body(
+    account_hex: &str,
+    keys_hex: &[String],
+    threshold: u64,
+    operations: &str,
+) -> serde_json::Value {
+    let keys: Vec<serde_json::Value> = keys_hex
+        .iter()
+        .map(|address| serde_json::json!({ "address": address, "weight": 1 }))
+        .collect();
+    serde_json::json!({
+        "owner_address": account_hex,
+        "owner": {
+            "type": 0,
+            "permission_name": "owner",
+            "threshold": threshold,
+            "keys": keys
+        },
+        "actives": [{
+            "type": 2,
+            "permission_name": "active",
+            "threshold": threshold,
+            "operations": operations,
+            "keys": keys
+        }],
+        "visible": false
+    })
+}
+
+/// Replaces the signing account's owner and active permissions with `owners`
+/// and `threshold`, then signs and broadcasts the update.
+async fn set_permissions(
+    out: &Output,
+    client: &TronClient,
+    owners: &[String],
+    threshold: u64,
+    signer: &SignerArgs,
+    operations: &str,
+    force: bool,
+) -> Result<serde_json::Value> {
+    let prefix = client.address_prefix();
+    let operations = operations.trim_start_matches("0x").to_ascii_lowercase();
+    if operations.len() != 64 || hex::decode(&operations).is_err() {
+        return Err(invalid_input("--operations must be 32 bytes of hex"));
+    }
+
+    let mut owners_hex = Vec::with_capacity(owners.len());
+    for owner in owners {
+        let hex = tron_address_to_hex(owner, prefix)
+            .with_context(|| format!("Invalid owner address {:?}", owner))?;
+        if owners_hex.contains(&hex) {
+            return Err(invalid_input(format!("Duplicate owner: {}", owner)));
+        }
+        owners_hex.push(hex);
+    }
+    if threshold == 0 || threshold as usize > owners.len() {
+        return Err(invalid_input(
+            "Invalid threshold: must be > 0 and <= number of owners",
+        ));
+    }
+    let max_keys = match client.chain_parameters().await {
+        Ok(params) => params
+            .get(TOTAL_SIGN_NUM_PARAM)
+            .copied()
+            .unwrap_or(DEFAULT_TOTAL_SIGN_NUM),
+        Err(err) => {
+            out.detail(format!("Using the default key limit: {:#}", err));
+            DEFAULT_TOTAL_SIGN_NUM
+        }
+    };
+    if owners.len() as i64 > max_keys {
+        return Err(invalid_input(format!(
+            "{} owners given; the network allows at most {} keys per permission",
+            owners.len(),
+            max_keys
+        )));
+    }
+
+    let signer = signer.signer(prefix)?;
+    let account = signer.address(prefix)?;
+    let account_hex = tron_address_to_hex(&account, prefix)?;
+    if !owners_hex.contains(&account_hex) {
+        let message = format!(
+            "{} is not among --owners: after this update it can no longer act for its own account.",
+            account
+        );
+        if !force {
+            return Err(invalid_input(format!(
+                "{} Pass --force if this is intended.",
+                message
+            )));
+        }
+        out.warn(format!("⚠️  {}", message));
+    }
+
+    out.line(format!("Account:   {}", account));
+    out.line(format!("Threshold: {} of {}", threshold, owners.len()));
+    for owner in owners {
+        out.line(format!("   - {}", owner));
+    }
+
+    out.phase("\n📡 Creating permission update transaction...");
+    let body = permission_update_body(&account_hex, &owners_hex, threshold, &operations);
+    let response = client
+        .post("/wallet/accountpermissionupdate", &body)
+        .await?;
+    let transaction = parse_transaction_response(&response, client.options.api_version)?;
+    let tx_id = transaction
+        .get("txID")
+        .and_then(|v| v.as_str())
+        .map(String::from)
+        .ok_or_else(|| anyhow!("No txID in response"))?;
+    out.phase(format!("Transaction ID: {}", tx_id));
+
+    let signed_tx = signer.sign_transaction_json(&transaction, &tx_id, V_OFFSET_TRON)?;
+    broadcast_transaction(client, &signed_tx).await?;
+
+    out.line(format!("\n✅ Permissions of {} updated", account));
+    out.line(format!("Transaction: {}", tx_id));
+    if let Some(url) = client.explorer_link(ExplorerLink::Transaction(&tx_id)) {
+        out.line(format!("View transaction: {}", url));
+    }
+    Ok(serde_json::json!({
+        "account": account,
+        "owners": owners,
+        "threshold": threshold,
+        "transaction": tx_id
+    }))
+}
+
+/// The txID of a transaction file, checked against its `raw_data_hex`, and
+/// the base58 address it targets: the called contract, or for other
+/// contract types the account acting.
+fn transaction_target(transaction: &serde_json::Value, prefix: u8) -> Result<(String, String)> {
+    let tx_id = verified_tx_id(transaction)?;
+    let value = &transaction["raw_data"]["contract"][0]["parameter"]["value"];
+    let target = value
+        .get("contract_address")
+        .or_else(|| value.get("owner_address"))
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| invalid_input("Transaction names no contract or owner address"))?;
+    let target = format_address(
+        &parse_address(target, prefix)?,
+        AddressFormat::Base58,
+        prefix,
+    );
+    Ok((tx_id, target))
+}
+
+/// The txID of a transaction file, after checking it is the SHA-256 of its
+/// `raw_data_hex`: signatures cover the txID, so it must be the one the node
+/// will compute.
+fn verified_tx_id(transaction: &serde_json::Value) -> Result<String> {
+    let tx_id = transaction
+        .get("txID")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| invalid_input("Transaction has no txID"))?;
+    let raw_data_hex = transaction
+        .get("raw_data_hex")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| invalid_input("Transaction has no raw_data_hex"))?;
+    let computed = transaction_id(raw_data_hex)?;
+    if !computed.eq_ignore_ascii_case(tx_id) {
+        return Err(invalid_input(format!(
+            "Transaction txID {} does not match its raw_data_hex ({})",
+            tx_id, computed
+        )));
+    }
+    Ok(computed)
+}
+
+/// Adds a signature to a transaction built elsewhere (e.g. TronWeb's
+/// `transactionBuilder`), leaving every other field as given. Signatures
+/// already present are kept, so owners of a native multisig account can
+/// sign in turn.
+fn sign_external_transaction(
+    out: &Output,
+    transaction: &serde_json::Value,
+    signer: &Signer,
+    v_offset: u8,
+    prefix: u8,
+) -> Result<serde_json::Value> {
+    let tx_id = verified_tx_id(transaction)?;
+    if transaction.get("raw_data").is_none() {
+        return Err(invalid_input("Transaction has no raw_data"));
+    }
+    let address = signer.address(prefix)?;
+    let signature = signer.sign(&tx_id, v_offset)?;
+
+    let mut signed_tx = transaction.clone();
+    let fields = signed_tx
+        .as_object_mut()
+        .ok_or_else(|| invalid_input("Transaction is not an object"))?;
+    let signatures = fields
+        .entry("signature")
+        .or_insert_with(|| serde_json::json!([]))
+        .as_array_mut()
+        .ok_or_else(|| invalid_input("Transaction signature is not an array"))?;
+    if signatures
+        .iter()
+        .any(|s| s.as_str() == Some(signature.as_str()))
+    {
+        out.warn(format!(
+            "⚠️  {} has already signed this transaction",
+            address
+        ));
+    } else {
+        signatures.push(signature.into());
+    }
+    let count = signatures.len();
+
+    out.line(format!("✅ Signed by {}", address));
+    out.line(format!("Transaction: {}", tx_id));
+    out.line(format!("Signatures:  {}", count));
+    Ok(signed_tx)
+}
+
+/// `keystore-inspect`: the plaintext metadata of each keystore. The stored
+/// address isn't covered by the MAC, so it is reported as what the file claims.
+fn inspect_keystores(out: &Output, paths: &[PathBuf], prefix: u8) -> Result<serde_json::Value> {
+    let mut keystores = Vec::new();
+    for path in paths {
+        let json =
+            read_text_file(path).with_context(|| format!("Failed to read keystore: {:?}", path))?;
+        let info = KeystoreInfo::from_json(&json, prefix)
+            .with_context(|| format!("Invalid keystore: {:?}", path))?;
+        out.line(format!("🔑 {}", path.display()));
+        match &info.address {
+            Some(address) => out.line(format!("   Address: {}", address)),
+            None => out.line("   Address: not stored; only decrypting the keystore reveals it"),
+        }
+        out.line(format!("   KDF:     {}", info.kdf));
+        out.line(format!("   Cipher:  {}", info.cipher));
+        let kdf = match &info.kdf {
+            KeystoreKdf::Scrypt { n, r, p, dklen } => serde_json::json!({
+                "name": "scrypt", "n": n, "r": r, "p": p, "dklen": dklen
+            }),
+            KeystoreKdf::Pbkdf2 { c, prf, dklen } => serde_json::json!({
+                "name": "pbkdf2", "c": c, "prf": prf, "dklen": dklen
+            }),
+            KeystoreKdf::Other(name) => serde_json::json!({ "name": name }),
+        };
+        keystores.push(serde_json::json!({
+            "path": path,
+            "id": info.id,
+            "address": info.address,
+            "kdf": kdf,
+            "cipher": info.cipher
+        }));
+    }
+    Ok(serde_json::json!({ "keystores": keystores }))
+}
+
+fn read_collection(path: &std::path::Path, prefix: u8) -> Result<SignatureCollection> {
+    let json = read_text_file(path)
+        .with_context(|| format!("Failed to read signature collection: {:?}", path))?;
+    SignatureCollection::from_json(&json, prefix).map_err(|err| {
+        // The library reports only the message; a syntax error's position
+        // comes from parsing again
+        let snippet = serde_json::from_str::<serde::de::IgnoredAny>(&json)
+            .err()
+            .and_then(|json_err| json_error_snippet(json.as_bytes(), &json_err));
+        let err =
+            anyhow::Error::new(err).context(format!("Invalid signature collection: {:?}", path));
+        match snippet {
+            Some(snippet) => anyhow!("{:#}\n{}", err, snippet),
+            None => err,
+        }
+    })
+}
+
+fn write_collection(
+    out: &Output,
+    path: &std::path::Path,
+    collection: &SignatureCollection,
+) -> Result<()> {
+    fs::write(path, serde_json::to_string_pretty(collection)?)
+        .with_context(|| format!("Failed to write signature collection: {:?}", path))?;
+    out.phase(format!(
+        "💾 Signature collection written to {}",
+        path.display()
+    ));
+    Ok(())
+}
+
+/// Errors unless `collection` is for the transaction `tx_id` calling `target`.
+fn check_collection_target(
+    collection: &SignatureCollection,
+    tx_id: &str,
+    target: &str,
+    prefix: u8,
+) -> Result<()> {
+    if !collection.tx_id.eq_ignore_ascii_case(tx_id) {
+        return Err(invalid_input(format!(
+            "Signature collection is for transaction {}, not {}",
+            collection.tx_id, tx_id
+        )));
+    }
+    if parse_address(&collection.contract, prefix)? != parse_address(target, prefix)? {
+        return Err(invalid_input(format!(
+            "Signature collection is for {}, not {}",
+            collection.contract, target
+        )));
+    }
+    Ok(())
+}
+
+struct CollectionTarget<'a> {
+    transaction: &'a std::path::Path,
+    collection: &'a std::path::Path,
+    chain_id: Option<&'a str>,
+}
+
+async fn sign_collection(
+    out: &Output,
+    client: Option<&TronClient>,
+    target: &CollectionTarget<'_>,
+    signer: &SignerArgs,
+    prefix: u8,
+) -> Result<serde_json::Value> {
+    let transaction: serde_json::Value = read_json_file(target.transaction, "transaction")?;
+    let (tx_id, contract) = transaction_target(&transaction, prefix)?;
+    let existing = if target.collection.exists() {
+        Some(read_collection(target.collection, prefix)?)
+    } else {
+        None
+    };
+
+    let chain_id = match (target.chain_id, client) {
+        (Some(chain_id), _) => Some(chain_id.to_lowercase()),
+        (None, Some(client)) => Some(client.chain_id().await?),
+        (None, None) => None,
+    };
+    let mut collection = match (existing, chain_id) {
+        (Some(existing), chain_id) => {
+            check_collection_target(&existing, &tx_id, &contract, prefix)?;
+            if let Some(chain_id) = chain_id.filter(|c| !c.eq_ignore_ascii_case(&existing.chain_id))
+            {
+                return Err(invalid_input(format!(
+                    "Signature collection is for chain {}, not {}",
+                    existing.chain_id, chain_id
+                )));
+            }
+            existing
+        }
+        (None, Some(chain_id)) => SignatureCollection::new(&tx_id, &contract, &chain_id, prefix)?,
+        (None, None) => {
+            return Err(invalid_input(
+                "Pass --chain-id or --rpc-url to start a new signature collection",
+            ))
+        }
+    };
+
+    let signer = signer.signer(prefix)?;
+    out.phase("🔐 Signing transaction...");
+    let signature = signer.sign(&tx_id, V_OFFSET_TRON)?;
+    let address = collection.add(&signature, prefix)?;
+    write_collection(out, target.collection, &collection)?;
+
+    out.line(format!("✅ Signed by {}", address));
+    out.line(format!("Transaction: {}", tx_id));
+    out.line(format!("Contract:    {}", contract));
+    out.line(format!("Signatures:  {}", collection.signatures.len()));
+    Ok(serde_json::json!({
+        "transaction": tx_id,
+        "contract": contract,
+        "chainId": collection.chain_id,
+        "signer": address,
+        "signatures": collection.signatures.len()
+    }))
+}
+
+/// Merges collection `paths` into `out_path`, and with `signed` (transaction
+/// file, output file) writes the transaction carrying every signature.
+fn merge_signatures(
+    out: &Output,
+    paths: &[PathBuf],
+    out_path: &std::path::Path,
+    signed: Option<(&std::path::Path, &std::path::Path)>,
+    prefix: u8,
+) -> Result<serde_json::Value> {
+    let (first, rest) = paths
+        .split_first()
+        .ok_or_else(|| invalid_input("Pass at least one --collection"))?;
+    let mut merged = read_collection(first, prefix)?;
+    for path in rest {
+        let added = merged
+            .merge(&read_collection(path, prefix)?, prefix)
+            .with_context(|| format!("Failed to merge {:?}", path))?;
+        out.detail(format!("{}: {} new signature(s)", path.display(), added));
+    }
+    write_collection(out, out_path, &merged)?;
+
+    if let Some((transaction_path, signed_tx_out)) = signed {
+        let mut transaction: serde_json::Value = read_json_file(transaction_path, "transaction")?;
+        let (tx_id, contract) = transaction_target(&transaction, prefix)?;
+        check_collection_target(&merged, &tx_id, &contract, prefix)?;
+        let signatures: Vec<&str> = merged.signatures.iter().map(|s| s.sig.as_str()).collect();
+        transaction
+            .as_object_mut()
+            .ok_or_else(|| invalid_input("Transaction is not an object"))?
+            .insert("signature".to_string(), serde_json::json!(signatures));
+        let transaction = bind_to_chain(&transaction, &merged.chain_id)?;
+        fs::write(signed_tx_out, serde_json::to_string_pretty(&transaction)?)
+            .with_context(|| format!("Failed to write signed transaction: {:?}", signed_tx_out))?;
+        out.phase(format!(
+            "💾 Signed transaction written to {}",
+            signed_tx_out.display()
+        ));
+    }
+
+    let signers: Vec<&str> = merged
+        .signatures
+        .iter()
+        .map(|s| s.signer.as_str())
+        .collect();
+    out.line(format!(
+        "✅ {} signature(s) for {}",
+        signers.len(),
+        merged.tx_id
+    ));
+    for signer in &signers {
+        out.line(format!("   - {}", signer));
+    }
+    Ok(serde_json::json!({
+        "transaction": merged.tx_id,
+        "contract": merged.contract,
+        "chainId": merged.chain_id,
+        "signers": signers
+    }))
+}
+
+/// One-line status, e.g. `3/5 approvals, threshold 4 — needs 1 more`.
+fn approval_summary(approved: u64, owners: usize, threshold: u64, executed: bool) -> String {
+    let status = if executed {
+        "executed".to_string()
+    } else if approved >= threshold {
+        "threshold met".to_string()
+    } else {
+        format!("needs {} more", threshold - approved)
+    };
+    format!(
+        "{}/{} approvals, threshold {} — {}",
+        approved, owners, threshold, status
+    )
+}
+
+/// Fetches and parses the ABI stored on-chain for `contract`, once per contract.
+async fn fetch_contract_abi(client: &TronClient, contract: &str) -> Result<ContractAbi> {
+    client
+        .contract_abis
+        .get_or_try_init(contract, || async {
+            tron_address_to_hex(contract, client.address_prefix())?;
+            let response = client
+                .post(
+                    "/wallet/getcontract",
+                    &serde_json::json!({ "value": contract, "visible": true }),
+                )
+                .await?;
+            let abi = response
+                .get("abi")
+                .map(ContractAbi::from_json)
+                .transpose()?
+                .filter(|abi| !abi.entries.is_empty())
+                .ok_or_else(|| anyhow!("Contract {} has no ABI stored on-chain", contract))?;
+            client.out.detail(format!(
+                "Fetched ABI for {} ({} entries)",
+                contract,
+                abi.entries.len()
+            ));
+            Ok(abi)
+        })
+        .await
+}
+
+/// Calls the view function `function` of `contract` with `args` parsed per
+/// the ABI, and decodes its return values.
+async fn call_contract(
+    out: &Output,
+    client: &TronClient,
+    abi: &ContractAbi,
+    contract: &str,
+    function: &str,
+    args: &[String],
+) -> Result<serde_json::Value> {
+    let entry = abi.function(function, args.len())?;
+    let signature = entry.signature()?;
+    out.detail(format!(
+        "Function: {} (selector 0x{})",
+        signature,
+        hex::encode(function_selector(&signature))
+    ));
+
+    let input_types = entry.input_types()?;
+    if input_types.len() != args.len() {
+        return Err(invalid_input(format!(
+            "{} takes {} argument(s), got {}",
+            signature,
+            input_types.len(),
+            args.len()
+        )));
+    }
+    let values = input_types
+        .iter()
+        .zip(args)
+        .map(|(ty, arg)| parse_abi_arg(ty, arg, client.address_prefix()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let result = trigger_constant(client, contract, &signature, &encode_params(&values)).await?;
+    let data = hex::decode(&result).context("Invalid hex in call result")?;
+    let decoded = decode_params(&entry.output_types()?, &data)
+        .with_context(|| format!("Failed to decode result of {}", signature))?;
+
+    Ok(serde_json::json!({
+        "contract": contract,
+        "function": signature,
+        "outputs": show_params(out, &entry.outputs, &decoded, client.address_prefix())
+    }))
+}
+
+/// Calls the view function `function` of `contract` with pre-encoded
+/// `parameter` hex, skipping the ABI encoder. Results are decoded if the ABI
+/// has the function, and returned as raw hex otherwise.
+async fn call_contract_raw(
+    out: &Output,
+    client: &TronClient,
+    abi: &ContractAbi,
+    contract: &str,
+    function: &str,
+    parameter: &str,
+) -> Result<serde_json::Value> {
+    let parameter = parse_parameter_hex(parameter)?;
+    let entry = match abi.function(function, 0) {
+        Ok(entry) => Some(entry),
+        Err(_) if function.contains('(') => None,
+        Err(err) => return Err(err.into()),
+    };
+    let signature = match entry {
+        Some(entry) => entry.signature()?,
+        None => function.chars().filter(|c| !c.is_whitespace()).collect(),
+    };
+    out.warn("⚠️  --parameter-hex is sent as-is: it is not checked against the function's parameter types.");
+
+    let result = trigger_constant(client, contract, &signature, &parameter).await?;
+    let Some(entry) = entry else {
+        out.line(format!("Result: 0x{}", result));
+        return Ok(serde_json::json!({
+            "contract": contract,
+            "function": signature,
+            "raw": format!("0x{}", result)
+        }));
+    };
+
+    let data = hex::decode(&result).context("Invalid hex in call result")?;
+    let decoded = decode_params(&entry.output_types()?, &data)
+        .with_context(|| format!("Failed to decode result of {}", signature))?;
+    Ok(serde_json::json!({
+        "contract": contract,
+        "function": signature,
+        "outputs": show_params(out, &entry.outputs, &decoded, client.address_prefix())
+    }))
+}
+
+/// Normalizes `--parameter-hex`: optional `0x`, even length, hex digits only.
+fn parse_parameter_hex(raw: &str) -> Result<String> {
+    let digits = raw.trim().trim_start_matches("0x");
+    if !digits.len().is_multiple_of(2) || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid_input(format!(
+            "--parameter-hex must be an even number of hex digits, got {:?}",
+            raw
+        )));
+    }
+    Ok(digits.to_ascii_lowercase())
+}
+
+/// Decodes calldata (`selector || params`) against the functions in `abi`.
+fn decode_input(
+    out: &Output,
+    abi: &ContractAbi,
+    data: &str,
+    prefix: u8,
+) -> Result<serde_json::Value> {
+    let data = hex::decode(data.trim_start_matches("0x")).context("Invalid calldata hex")?;
+    if data.len() < 4 {
+        return Err(invalid_input("Calldata is shorter than a 4-byte selector"));
+    }
+    let (selector, params) = data.split_at(4);
+    let entry = abi.function_by_selector(selector).ok_or_else(|| {
+        invalid_input(format!(
+            "No function with selector 0x{} in ABI",
+            hex::encode(selector)
+        ))
+    })?;
+    let signature = entry.signature()?;
+    let decoded = decode_params(&entry.input_types()?, params)
+        .with_context(|| format!("Failed to decode arguments of {}", signature))?;
+
+    out.line(format!("Function: {}", signature));
+    Ok(serde_json::json!({
+        "function": signature,
+        "selector": hex::encode(selector),
+        "inputs": show_params(out, &entry.inputs, &decoded, prefix)
+    }))
+}
+
+/// Prints decoded parameters as `name (type): value` lines and returns them
+/// as JSON. Unnamed parameters are shown by position.
+fn show_params(
+    out: &Output,
+    params: &[AbiParam],
+    values: &[AbiValue],
+    prefix: u8,
+) -> Vec<serde_json::Value> {
+    let mut shown = Vec::new();
+    for (i, (param, value)) in params.iter().zip(values).enumerate() {
+        let name = if param.name.is_empty() {
+            format!("[{}]", i)
+        } else {
+            param.name.clone()
+        };
+        let json = abi_value_to_json(value, prefix);
+        let display = match &json {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        out.line(format!("{} ({}): {}", name, param.kind, display));
+        shown.push(serde_json::json!({
+            "name": param.name,
+            "type": param.kind,
+            "value": json
+        }));
+    }
+    shown
+}
+
+/// Loads an ABI from a forge artifact or plain ABI JSON file, or the
+/// built-in USDTMultisig ABI when no path is given.
+fn load_abi_file(path: Option<&std::path::Path>) -> Result<ContractAbi> {
+    let Some(path) = path else {
+        return Ok(ContractAbi::from_json(&serde_json::from_str(
+            get_contract_abi(),
+        )?)?);
+    };
+    let AbiFile(json) = read_json_file(path, "ABI file")?;
+    Ok(ContractAbi::from_json(&json)?)
+}
+
+/// keccak256("Transfer(address,address,uint256)")
+const TRANSFER_EVENT_TOPIC: &str =
+    "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+
+/// Extracts the TRC20 Transfer events from a `gettransactioninfobyid` response.
+fn parse_transfer_events(info: &serde_json::Value, prefix: u8) -> Result<Vec<TransferEvent>> {
+    let Some(logs) = info.get("log").and_then(|l| l.as_array()) else {
+        return Ok(Vec::new());
+    };
+
+    let mut events = Vec::new();
+    for log in logs {
+        let topics: Vec<&str> = log
+            .get("topics")
+            .and_then(|t| t.as_array())
+            .map(|t| t.iter().filter_map(|topic| topic.as_str()).collect())
+            .unwrap_or_default();
+        if topics.len() != 3 || topics[0] != TRANSFER_EVENT_TOPIC {
+            continue;
+        }
+
+        // Log addresses are 20-byte hex without the 0x41 prefix
+        let token = log
+            .get("address")
+            .and_then(|a| a.as_str())
+            .ok_or_else(|| anyhow!("Transfer log without contract address"))?;
+        let data = log.get("data").and_then(|d| d.as_str()).unwrap_or_default();
+
+        events.push(TransferEvent {
+            token: format_address(
+                &parse_address(token, prefix)?,
+                AddressFormat::Base58,
+                prefix,
+            ),
+            from: abi_word_to_tron_address(topics[1], prefix)?,
+            to: abi_word_to_tron_address(topics[2], prefix)?,
+            amount: abi_word_to_u128(data)?,
+        });
+    }
+    Ok(events)
+}
+
+/// Resolves an amount given either in raw base units or as a decimal token
+/// amount. Decimals come from `decimals` or are read from the `token` contract.
+async fn resolve_amount(
+    client: &TronClient,
+    raw: Option<u128>,
+    tokens: Option<&str>,
+    decimals: Option<u32>,
+    token: Option<&str>,
+) -> Result<u128> {
+    if let Some(raw) = raw {
+        return Ok(raw);
+    }
+    let tokens = tokens.ok_or_else(|| invalid_input("No amount given"))?;
+
+    let decimals = match (decimals, token) {
+        (Some(decimals), _) => decimals,
+        (None, Some(token)) => token_decimals(client, token).await?,
+        (None, None) => {
+            return Err(invalid_input(
+                "Token amounts need --decimals or a token address to read decimals from",
+            ))
+        }
+    };
+    parse_token_amount(tokens, decimals)
+}
+
+async fn token_decimals(client: &TronClient, token: &str) -> Result<u32> {
+    client
+        .token_decimals
+        .get_or_try_init(token, || async {
+            let result = trigger_constant(client, token, "decimals()", "").await?;
+            let decimals = abi_word_to_u128(abi_word(&result, 0)?)?;
+            u32::try_from(decimals)
+                .ok()
+                .filter(|d| *d <= 77)
+                .ok_or_else(|| anyhow!("Token {} reports invalid decimals: {}", token, decimals))
+        })
+        .await
+}
+
+/// Parses a decimal token amount (e.g. `12.5`) into base units for a token
+/// with `decimals` decimals, rejecting more fractional digits than it supports.
+fn parse_token_amount(amount: &str, decimals: u32) -> Result<u128> {
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+    let is_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if whole.is_empty() && fraction.is_empty() || !is_digits(whole) || !is_digits(fraction) {
+        return Err(invalid_input(format!("Invalid token amount: {:?}", amount)));
+    }
+    if fraction.len() > decimals as usize {
+        return Err(invalid_input(format!(
+            "Amount {} has {} fractional digits but the token only supports {}",
+            amount,
+            fraction.len(),
+            decimals
+        )));
+    }
+
+    let digits = format!("{}{:0<width$}", whole, fraction, width = decimals as usize);
+    // Only digits remain, so parsing can fail only on overflow
+    digits
+        .parse::<u128>()
+        .map_err(|_| invalid_input(format!("Token amount too large: {}", amount)))
+}
+
+/// Human-readable label for an address printed in `format`.
+fn address_format_label(format: AddressFormat) -> &'static str {
+    match format {
+        AddressFormat::Base58 => "TRON Address",
+        AddressFormat::Hex41 | AddressFormat::HexRaw => "Hex",
+        AddressFormat::Evm => "EVM Address",
+    }
+}
+
+/// Blocks per `/wallet/getblockbylimitnext` request, the node's maximum.
+const BLOCK_BATCH: u64 = 100;
+
+/// A call to the multisig found while scanning blocks.
+struct ContractCall {
+    block: u64,
+    /// Block time, in ms since the epoch
+    timestamp: i64,
+    transaction: String,
+    caller: String,
+    /// Calldata (selector + parameters)
+    data: Vec<u8>,
+}
+
+/// One governance action, from an event or, for a failed call, its calldata.
+#[derive(Debug, PartialEq, Serialize)]
+struct HistoryEntry {
+    block: u64,
+    time: String,
+    transaction: String,
+    action: String,
+    #[serde(rename = "txId")]
+    tx_id: Option<String>,
+    signer: String,
+    /// Recipient and amount of a submission
+    detail: String,
+    success: bool,
+}
+
+/// Event signature and the action it records.
+const HISTORY_EVENTS: [(&str, &str); 5] = [
+    ("TransactionSubmitted(uint256,address,uint256)", "submit"),
+    ("TransactionApproved(uint256,address)", "approve"),
+    ("TransactionRevoked(uint256,address)", "revoke"),
+    ("TransactionExecuted(uint256)", "execute"),
+    ("TransactionCancelled(uint256)", "cancel"),
+];
+
+/// Scans blocks `from..=to` for calls to `contract`, in chain order.
+async fn scan_contract_calls(
+    client: &TronClient,
+    contract: &str,
+    from: u64,
+    to: u64,
+) -> Result<Vec<ContractCall>> {
+    let prefix = client.address_prefix();
+    let contract_hex = tron_address_to_hex(contract, prefix)?;
+    let mut calls = Vec::new();
+    let mut start = from;
+    while start <= to {
+        let end = (start + BLOCK_BATCH).min(to + 1);
+        client
+            .out
+            .phase(format!("Scanning blocks {}..{}", start, end - 1));
+        let response = client
+            .post(
+                "/wallet/getblockbylimitnext",
+                &serde_json::json!({ "startNum": start, "endNum": end }),
+            )
+            .await?;
+        let blocks = response
+            .get("block")
+            .and_then(|b| b.as_array())
+            .cloned()
+            .unwrap_or_default();
+        let mut batch = Vec::new();
+        for block in &blocks {
+            let header = &block["block_header"]["raw_data"];
+            let number = header["number"].as_u64().unwrap_or_default();
+            let timestamp = header["timestamp"].as_i64().unwrap_or_default();
+            let transactions = block["transactions"].as_array().map(Vec::as_slice);
+            for tx in transactions.unwrap_or_default() {
+                let contract_call = &tx["raw_data"]["contract"][0];
+                let value = &contract_call["parameter"]["value"];
+                if contract_call["type"] != "TriggerSmartContract"
+                    || value["contract_address"].as_str() != Some(contract_hex.as_str())
+                {
+                    continue;
+                }
+                batch.push(ContractCall {
+                    block: number,
+                    timestamp,
+                    transaction: tx["txID"].as_str().unwrap_or_default().to_string(),
+                    caller: value["owner_address"]
+                        .as_str()
+                        .map(|owner| parse_address(owner, prefix))
+                        .transpose()?
+                        .map(|raw| format_address(&raw, AddressFormat::Base58, prefix))
+                        .unwrap_or_default(),
+                    data: hex::decode(value["data"].as_str().unwrap_or_default())
+                        .unwrap_or_default(),
+                });
+            }
+        }
+        // Blocks within a batch may arrive in any order; transactions keep theirs
+        batch.sort_by_key(|call| call.block);
+        calls.extend(batch);
+        start = end;
+    }
+    Ok(calls)
+}
+
+/// The governance actions of `call` given its transaction info: one per
+/// multisig event the contract emitted, or, if the call failed, a single
+/// unsuccessful entry named after the called function.
+fn history_entries(
+    call: &ContractCall,
+    info: &serde_json::Value,
+    contract: &[u8; 20],
+    abi: &ContractAbi,
+    prefix: u8,
+) -> Result<Vec<HistoryEntry>> {
+    let entry =
+        |action: &str, tx_id: Option<String>, signer: &str, detail: String, success| HistoryEntry {
+            block: call.block,
+            time: format_utc(call.timestamp),
+            transaction: call.transaction.clone(),
+            action: action.to_string(),
+            tx_id,
+            signer: signer.to_string(),
+            detail,
+            success,
+        };
+
+    let outcome = TxOutcome::classify(info, &TransactionReceipt::from_info(info)?);
+    if outcome != TxOutcome::Success {
+        let selector = call.data.get(..4).unwrap_or_default();
+        let function = abi.function_by_selector(selector);
+        let action = function
+            .map(|f| f.name.clone())
+            .unwrap_or_else(|| format!("0x{}", hex::encode(selector)));
+        // Every multisig action takes the transaction ID first
+        let tx_id = function
+            .filter(|f| f.inputs.first().is_some_and(|p| p.kind == "uint256"))
+            .and_then(|_| call.data.get(4..36))
+            .map(|word| abi_word_to_u128(&hex::encode(word)))
+            .transpose()?
+            .map(|id| id.to_string());
+        let detail = match outcome {
+            TxOutcome::Reverted(reason) => reason.unwrap_or_default(),
+            other => other.code().to_string(),
+        };
+        return Ok(vec![entry(&action, tx_id, &call.caller, detail, false)]);
+    }
+
+    let topics: Vec<(String, &str)> = HISTORY_EVENTS
+        .iter()
+        .map(|(signature, action)| (hex::encode(keccak256(signature.as_bytes())), *action))
+        .collect();
+    let mut entries = Vec::new();
+    for log in info["log"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+    {
+        let emitter = log["address"]
+            .as_str()
+            .map(|address| parse_address(address, prefix))
+            .transpose()?;
+        if emitter.as_ref() != Some(contract) {
+            continue;
+        }
+        let log_topics: Vec<&str> = log["topics"]
+            .as_array()
+            .map(|t| t.iter().filter_map(|topic| topic.as_str()).collect())
+            .unwrap_or_default();
+        let Some((_, action)) = topics
+            .iter()
+            .find(|(topic, _)| Some(&topic.as_str()) == log_topics.first())
+        else {
+            continue;
+        };
+        let tx_id = log_topics
+            .get(1)
+            .map(|word| abi_word_to_u128(word))
+            .transpose()?
+            .map(|id| id.to_string());
+        let (signer, detail) = match *action {
+            "approve" | "revoke" => match log_topics.get(2) {
+                Some(owner) => (abi_word_to_tron_address(owner, prefix)?, String::new()),
+                None => (call.caller.clone(), String::new()),
+            },
+            "submit" => {
+                let to = log_topics
+                    .get(2)
+                    .map(|t| abi_word_to_tron_address(t, prefix))
+                    .transpose()?;
+                let amount = abi_word_to_u128(log["data"].as_str().unwrap_or_default())?;
+                (
+                    call.caller.clone(),
+                    format!("{} to {}", amount, to.unwrap_or_default()),
+                )
+            }
+            _ => (call.caller.clone(), String::new()),
+        };
+        entries.push(entry(action, tx_id, &signer, detail, true));
+    }
+    Ok(entries)
+}
+
+/// Prints the governance history of `contract` over blocks `from..=to`.
+async fn show_history(
+    out: &Output,
+    client: &TronClient,
+    contract: &str,
+    from: u64,
+    to: u64,
+    csv: bool,
+) -> Result<serde_json::Value> {
+    if from > to {
+        return Err(invalid_input("--from-block must not be after --to-block"));
+    }
+    let contract_raw = parse_address(contract, client.address_prefix())?;
+    let abi = load_abi_file(None)?;
+
+    let calls = scan_contract_calls(client, contract, from, to).await?;
+    let mut entries = Vec::new();
+    for call in &calls {
+        let info = client.get_tx_info(&call.transaction).await?;
+        entries.extend(history_entries(
+            call,
+            &info,
+            &contract_raw,
+            &abi,
+            client.address_prefix(),
+        )?);
+    }
+
+    if csv {
+        out.line("block,time,transaction,action,tx_id,signer,detail,success");
+        for e in &entries {
+            out.line(format!(
+                "{},{},{},{},{},{},{},{}",
+                e.block,
+                e.time,
+                e.transaction,
+                e.action,
+                e.tx_id.as_deref().unwrap_or_default(),
+                e.signer,
+                csv_field(&e.detail),
+                e.success
+            ));
+        }
+    } else {
+        out.line(format!(
+            "{} action(s) in {} call(s) to {} over blocks {}..={}",
+            entries.len(),
+            calls.len(),
+            contract,
+            from,
+            to
+        ));
+        for e in &entries {
+            out.line(format!(
+                "{}  #{:<9} {:<20} tx {:<4} {}{}{}",
+                e.time,
+                e.block,
+                if e.success {
+                    e.action.clone()
+                } else {
+                    format!("{} (failed)", e.action)
+                },
+                e.tx_id.as_deref().unwrap_or("-"),
+                e.signer,
+                if e.detail.is_empty() { "" } else { "  " },
+                e.detail
+            ));
+            out.detail(format!("    transaction {}", e.transaction));
+        }
+    }
+    Ok(serde_json::json!({
+        "contract": contract,
+        "fromBlock": from,
+        "toBlock": to,
+        "entries": entries
+    }))
+}
+
+/// Quotes a CSV field if it contains a separator, quote or newline.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Formats milliseconds since the epoch as `YYYY-MM-DD HH:MM:SS` UTC.
+fn format_utc(ms: i64) -> String {
+    let secs = ms.div_euclid(1000);
+    let (days, day_secs) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        day_secs / 3600,
+        day_secs % 3600 / 60,
+        day_secs % 60
+    )
+}
+
+/// Formats `amount` base units of a token with `decimals` decimals, e.g.
+/// `12.500000` for 12,500,000 at 6 decimals.
+fn format_token_amount(amount: u128, decimals: u32) -> String {
+    let digits = format!("{:0>width$}", amount, width = decimals as usize + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals as usize);
+    match fraction {
+        "" => whole.to_string(),
+        _ => format!("{}.{}", whole, fraction),
+    }
+}
+
+/// Formats an amount in SUN as TRX (1 TRX = 1,000,000 SUN).
+fn format_trx(sun: u64) -> String {
+    format!("{}.{:06}", sun / 1_000_000, sun % 1_000_000)
+}
+
+fn decode_hex_message(hex_msg: &str) -> String {
+    if let Ok(bytes) = hex::decode(hex_msg) {
+        if let Ok(s) = String::from_utf8(bytes) {
+            return s;
+        }
+    }
+    hex_msg.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tron_utils::address::hex_to_tron_address;
+
+    #[test]
+    fn submissions_yield_their_multisig_tx_id() {
+        let contract = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t";
+        let submitted = |address: &str| {
+            serde_json::json!({
+                "address": address,
+                "topics": [
+                    hex::encode(keccak256(b"TransactionSubmitted(uint256,address,uint256)")),
+                    "000000000000000000000000000000000000000000000000000000000000002a",
+                    "000000000000000000000000a614f803b6fd780986a42c78ec9c7f77e6ded13c"
+                ],
+                "data": "00000000000000000000000000000000000000000000000000000000000f4240"
+            })
+        };
+        // Another contract's identical event comes first
+        let info = serde_json::json!({
+            "log": [
+                submitted("78c842ee63b253d8f0d2955bbc582c661a078c9d"),
+                submitted("a614f803b6fd780986a42c78ec9c7f77e6ded13c")
+            ]
+        });
+        assert_eq!(
+            submitted_tx_id(&info, contract, TRON_ADDRESS_PREFIX).unwrap(),
+            Some(42)
+        );
+        assert_eq!(
+            submitted_tx_id(&serde_json::json!({}), contract, TRON_ADDRESS_PREFIX).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_transfer_events_decodes_trc20_logs() {
+        let info = serde_json::json!({
+            "id": "7c2d",
+            "receipt": { "result": "SUCCESS" },
+            "log": [
+                {
+                    "address": "a614f803b6fd780986a42c78ec9c7f77e6ded13c",
+                    "topics": [
+                        TRANSFER_EVENT_TOPIC,
+                        "00000000000000000000000078c842ee63b253d8f0d2955bbc582c661a078c9d",
+                        "000000000000000000000000a614f803b6fd780986a42c78ec9c7f77e6ded13c"
+                    ],
+                    "data": "00000000000000000000000000000000000000000000000000000000000f4240"
+                },
+                {
+                    "address": "a614f803b6fd780986a42c78ec9c7f77e6ded13c",
+                    "topics": ["0000000000000000000000000000000000000000000000000000000000000001"],
+                    "data": ""
+                }
+            ]
+        });
+
+        let events = parse_transfer_events(&info, TRON_ADDRESS_PREFIX).unwrap();
+
+        assert_eq!(
+            events,
+            vec![TransferEvent {
+                token: "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t".to_string(),
+                from: hex_to_tron_address(
+                    "4178c842ee63b253d8f0d2955bbc582c661a078c9d",
+                    TRON_ADDRESS_PREFIX
+                )
+                .unwrap(),
+                to: "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t".to_string(),
+                amount: 1_000_000,
+            }]
+        );
+    }
+
+    fn sample_transaction() -> serde_json::Value {
+        serde_json::json!({
+            "txID": "1f8b3c6a",
+            "raw_data": { "contract": [], "expiration": 1 },
+            "raw_data_hex": "0a02"
+        })
+    }
+
+    #[test]
+    fn write_secret_file_refuses_overwrite_without_force() {
+        let dir = TestDir::new("secret");
+        let path = dir.join("key.json");
+
+        write_secret_file(&path, "first", false).unwrap();
+        let err = write_secret_file(&path, "second", false).unwrap_err();
+        assert!(err
+            .to_string()
+            .ends_with("already exists; pass --force to overwrite it"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+
+        write_secret_file(&path, "second", true).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn network_is_recognised_from_rpc_url() {
+        assert_eq!(
+            Network::from_rpc_url("https://api.trongrid.io"),
+            Network::Mainnet
+        );
+        assert_eq!(
+            Network::from_rpc_url("https://nile.trongrid.io/"),
+            Network::Nile
+        );
+        assert_eq!(
+            Network::from_rpc_url("https://api.shasta.trongrid.io"),
+            Network::Shasta
+        );
+        assert_eq!(
+            Network::from_rpc_url("http://127.0.0.1:8090"),
+            Network::Unknown
+        );
+    }
+
+    #[test]
+    fn usdt_mismatch_flags_cross_network_token() {
+        let mismatch =
+            |network: Network, usdt: &str| network.usdt_mismatch(usdt, TRON_ADDRESS_PREFIX);
+        let mainnet_usdt_hex = "41a614f803b6fd780986a42c78ec9c7f77e6ded13c";
+        assert!(mismatch(Network::Nile, MAINNET_USDT).is_some());
+        assert!(mismatch(Network::Shasta, mainnet_usdt_hex).is_some());
+        assert!(mismatch(Network::Mainnet, MAINNET_USDT).is_none());
+        assert!(mismatch(Network::Mainnet, mainnet_usdt_hex).is_none());
+        assert!(mismatch(Network::Mainnet, &format!("0x{}", &mainnet_usdt_hex[2..])).is_none());
+        assert!(mismatch(Network::Mainnet, "TXYZopYRdj2D9XRtbG411XZZ3kM5VkAeBf").is_some());
+        assert!(mismatch(Network::Unknown, MAINNET_USDT).is_none());
+    }
+
+    #[test]
+    fn parse_parameter_hex_requires_even_hex() {
+        assert_eq!(parse_parameter_hex("0x00FF").unwrap(), "00ff");
+        assert_eq!(parse_parameter_hex("").unwrap(), "");
+        assert!(parse_parameter_hex("0x0").is_err());
+        assert!(parse_parameter_hex("zz").is_err());
+    }
+
+    #[test]
+    fn permission_update_body_sets_owner_and_active_keys() {
+        let keys = vec![
+            format!("41{}", "11".repeat(20)),
+            "41a614f803b6fd780986a42c78ec9c7f77e6ded13c".to_string(),
+        ];
+        let body = permission_update_body(&keys[0], &keys, 2, DEFAULT_ACTIVE_OPERATIONS);
+
+        assert_eq!(body["owner_address"], keys[0]);
+        for permission in [&body["owner"], &body["actives"][0]] {
+            assert_eq!(permission["threshold"], 2);
+            assert_eq!(permission["keys"][1]["address"], keys[1]);
+            assert_eq!(permission["keys"][1]["weight"], 1);
+        }
+        assert_eq!(body["owner"]["type"], 0);
+        assert_eq!(body["actives"][0]["type"], 2);
+        assert_eq!(body["actives"][0]["operations"], DEFAULT_ACTIVE_OPERATIONS);
+        assert!(body["owner"].get("operations").is_none());
+    }
+
+    #[test]
+    fn style_text_degrades_to_plain_ascii() {
+        let banner = "━".repeat(42);
+        assert_eq!(style_text(&banner, false), None);
+        assert_eq!(
+            style_text("\n✅ Contract deployed successfully!", false).as_deref(),
+            Some("\nContract deployed successfully!")
+        );
+        assert_eq!(
+            style_text("⚠️  Mainnet — real funds", false).as_deref(),
+            Some("Warning: Mainnet - real funds")
+        );
+        assert_eq!(
+            style_text("\nℹ️  Nothing to execute", false).as_deref(),
+            Some("\nNote: Nothing to execute")
+        );
+        assert_eq!(
+            style_text("  ✓ TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b", false).as_deref(),
+            Some("  [x] TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b")
+        );
+        assert_eq!(style_text("", false).as_deref(), Some(""));
+
+        assert_eq!(
+            style_text("✅ Done", true).as_deref(),
+            Some("\x1b[32m✅ Done\x1b[0m")
+        );
+        assert_eq!(style_text(&banner, true), Some(banner));
+    }
+
+    #[test]
+    fn quiet_value_picks_the_result_identifier() {
+        let deployed =
+            serde_json::json!({ "transaction": "ab12", "contractAddress": MAINNET_USDT });
+        assert_eq!(
+            quiet_value(&deployed, None, TRON_ADDRESS_PREFIX).as_deref(),
+            Some(MAINNET_USDT)
+        );
+        let unknown = serde_json::json!({
+            "transaction": "ab12",
+            "contractAddress": "(Check TronScan for contract address)"
+        });
+        assert_eq!(
+            quiet_value(&unknown, None, TRON_ADDRESS_PREFIX).as_deref(),
+            Some("ab12")
+        );
+
+        let list = serde_json::json!({ "addresses": [MAINNET_USDT, MAINNET_USDT] });
+        assert_eq!(
+            quiet_value(&list, None, TRON_ADDRESS_PREFIX).unwrap(),
+            format!("{}\n{}", MAINNET_USDT, MAINNET_USDT)
+        );
+
+        let key = serde_json::json!({ "privateKey": "c88c", "address": MAINNET_USDT });
+        assert_eq!(
+            quiet_value(&key, None, TRON_ADDRESS_PREFIX).as_deref(),
+            Some(MAINNET_USDT)
+        );
+        let call = serde_json::json!({
+            "function": "getTransaction(uint256)",
+            "outputs": [{ "name": "to", "value": MAINNET_USDT }, { "name": "executed", "value": false }]
+        });
+        assert_eq!(
+            quiet_value(&call, None, TRON_ADDRESS_PREFIX).unwrap(),
+            format!("{}\nfalse", MAINNET_USDT)
+        );
+        assert_eq!(
+            quiet_value(
+                &serde_json::json!({ "verified": true }),
+                None,
+                TRON_ADDRESS_PREFIX
+            ),
+            None
+        );
+
+        // Commands whose value is not their first well-known key
+        let quiet = |command: &[&str], result: serde_json::Value| {
+            let cli = Cli::try_parse_from(
+                [&["tron-utils"], command, &["--rpc-url", "http://node"]].concat(),
+            )
+            .unwrap();
+            quiet_value(&result, cli.command.quiet_key(), TRON_ADDRESS_PREFIX)
+        };
+        let owners = serde_json::json!({
+            "contract": MAINNET_USDT,
+            "owners": ["TA", "TB"],
+            "threshold": 2,
+            "usdt": MAINNET_USDT
+        });
+        let owners_cmd = ["get-owners", "--contract", MAINNET_USDT];
+        assert_eq!(quiet(&owners_cmd, owners).as_deref(), Some("TA\nTB"));
+        let found = serde_json::json!({ "address": MAINNET_USDT, "isOwner": true, "index": 2 });
+        let owner_index = [
+            "owner-index",
+            "--contract",
+            MAINNET_USDT,
+            "--address",
+            MAINNET_USDT,
+        ];
+        assert_eq!(quiet(&owner_index, found).as_deref(), Some("2"));
+        let missing =
+            serde_json::json!({ "address": MAINNET_USDT, "isOwner": false, "index": null });
+        assert_eq!(quiet(&owner_index, missing), None);
+        let tx = [
+            "get-transaction",
+            "--contract",
+            MAINNET_USDT,
+            "--tx-id",
+            "0",
+        ];
+        let pending = serde_json::json!({ "txId": 0, "to": MAINNET_USDT, "executed": false });
+        assert_eq!(quiet(&tx, pending).as_deref(), Some("false"));
+        let approvals = serde_json::json!({
+            "txId": 0,
+            "approvals": [
+                { "owner": "TA", "approved": true },
+                { "owner": "TB", "approved": false },
+                { "owner": "TC", "approved": true }
+            ]
+        });
+        let approvals_cmd = ["approvals", "--contract", MAINNET_USDT, "--tx-id", "0"];
+        assert_eq!(quiet(&approvals_cmd, approvals).as_deref(), Some("TA\nTC"));
+        let balances = serde_json::json!({
+            "contracts": [
+                { "contract": "TA", "balanceTokens": "1.5" },
+                { "contract": "TB", "error": "unreachable" },
+                { "contract": "TC", "balanceTokens": "0" }
+            ]
+        });
+        let balances_cmd = ["balances", "--contracts", "TA,TB,TC"];
+        assert_eq!(quiet(&balances_cmd, balances).as_deref(), Some("1.5\n\n0"));
+        let params = serde_json::json!({
+            "economic": { "getEnergyFee": 420, "getTransactionFee": 1000 },
+            "all": {}
+        });
+        assert_eq!(
+            quiet(&["chain-params"], params).as_deref(),
+            Some("getEnergyFee=420\ngetTransactionFee=1000")
+        );
+    }
+
+    #[test]
+    fn transaction_target_checks_the_txid_and_reads_the_contract() {
+        let raw_data_hex = "0a02abcd5a03010203";
+        let tx_id = transaction_id(raw_data_hex).unwrap();
+        let mut transaction = serde_json::json!({
+            "txID": tx_id,
+            "raw_data_hex": raw_data_hex,
+            "raw_data": { "contract": [{ "parameter": { "value": {
+                "owner_address": "4177e2c2ccf3eae21463a927d2767884468b7615de",
+                "contract_address": "41a614f803b6fd780986a42c78ec9c7f77e6ded13c"
+            } } }] }
+        });
+        let (id, target) = transaction_target(&transaction, TRON_ADDRESS_PREFIX).unwrap();
+        assert_eq!(
+            (id.as_str(), target.as_str()),
+            (tx_id.as_str(), MAINNET_USDT)
+        );
+
+        let value = &mut transaction["raw_data"]["contract"][0]["parameter"]["value"];
+        value.as_object_mut().unwrap().remove("contract_address");
+        let (_, target) = transaction_target(&transaction, TRON_ADDRESS_PREFIX).unwrap();
+        assert_eq!(target, "TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b");
+
+        transaction["raw_data_hex"] = "0a02abce5a03010203".into();
+        let err = transaction_target(&transaction, TRON_ADDRESS_PREFIX)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("does not match its raw_data_hex"), "{}", err);
+    }
+
+    #[test]
+    fn external_transactions_are_signed_without_changes() {
+        let out = test_output();
+        let raw_data_hex = "0a02abcd2208000102030405060740e8075a03010203709807";
+        let transaction = serde_json::json!({
+            "visible": false,
+            "txID": transaction_id(raw_data_hex).unwrap(),
+            "raw_data_hex": raw_data_hex,
+            "raw_data": {
+                "ref_block_bytes": "abcd",
+                "expiration": 1000u64,
+                "timestamp": 920u64,
+                "fee_limit": 150_000_000u64,
+                "contract": [{ "type": "TriggerSmartContract", "Permission_id": 2 }]
+            }
+        });
+        let first = Signer::PrivateKey(TEST_KEY.to_string());
+        let second = Signer::PrivateKey("01".repeat(32));
+
+        let signed = sign_external_transaction(
+            &out,
+            &transaction,
+            &first,
+            V_OFFSET_TRON,
+            TRON_ADDRESS_PREFIX,
+        )
+        .unwrap();
+        let signed =
+            sign_external_transaction(&out, &signed, &second, V_OFFSET_TRON, TRON_ADDRESS_PREFIX)
+                .unwrap();
+        let again =
+            sign_external_transaction(&out, &signed, &second, V_OFFSET_TRON, TRON_ADDRESS_PREFIX)
+                .unwrap();
+        assert_eq!(again, signed);
+
+        let mut unsigned = signed.clone();
+        let signatures = unsigned
+            .as_object_mut()
+            .unwrap()
+            .remove("signature")
+            .unwrap();
+        assert_eq!(unsigned, transaction);
+        let tx_id = transaction["txID"].as_str().unwrap();
+        assert_eq!(
+            signatures,
+            serde_json::json!([
+                sign_transaction_with_v_offset(tx_id, TEST_KEY, V_OFFSET_TRON).unwrap(),
+                sign_transaction_with_v_offset(tx_id, &"01".repeat(32), V_OFFSET_TRON).unwrap()
+            ])
+        );
+    }
+
+    #[test]
+    fn owner_count_above_the_cap_needs_force() {
+        let out = test_output();
+        assert!(check_owner_count(&out, 20, DEFAULT_MAX_OWNERS, false).is_ok());
+        let err = check_owner_count(&out, 21, DEFAULT_MAX_OWNERS, false).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("21 owners is more than --max-owners 20."));
+        assert!(check_owner_count(&out, 21, DEFAULT_MAX_OWNERS, true).is_ok());
+        assert!(check_owner_count(&out, 21, 25, false).is_ok());
+    }
+
+    #[test]
+    fn password_keys_require_the_minimum_work_factor() {
+        let out = test_output();
+        let salt = "alice@example.com wallet";
+        for (memory_mib, iterations) in [(32, 3), (256, 1)] {
+            let err = password_secret_key(&out, salt, memory_mib, iterations).unwrap_err();
+            assert!(
+                err.to_string().contains("at least --argon2-memory 64"),
+                "{}",
+                err
+            );
+        }
+        let err = password_secret_key(&out, "alice", 256, 3).unwrap_err();
+        assert_eq!(err.to_string(), "--salt must be at least 16 bytes, got 5");
+    }
+
+    #[tokio::test]
+    async fn chain_params_list_economics_with_units() {
+        assert_eq!(
+            format_chain_param(15_000_000_000, ParamUnit::Sun),
+            "15000000000 SUN (15000.000000 TRX)"
+        );
+        assert_eq!(
+            format_chain_param(86_400_000, ParamUnit::Millis),
+            "86400000 ms (24h)"
+        );
+        assert_eq!(
+            format_chain_param(90_000, ParamUnit::Millis),
+            "90000 ms (90s)"
+        );
+
+        let out = test_output();
+        let result = show_chain_params(&out, &MockRpc::default()).await.unwrap();
+        assert_eq!(
+            result["economic"],
+            serde_json::json!({ "getMaxFeeLimit": 15_000_000_000i64 })
+        );
+        assert_eq!(result["all"], result["economic"]);
+    }
+
+    #[test]
+    fn price_histories_parse_and_list_recent_changes() {
+        let points = parse_price_history("0:100,1575331200000:10, 1606537680000:40,").unwrap();
+        assert_eq!(
+            points,
+            [
+                PricePoint {
+                    since_ms: 0,
+                    sun: 100
+                },
+                PricePoint {
+                    since_ms: 1_575_331_200_000,
+                    sun: 10
+                },
+                PricePoint {
+                    since_ms: 1_606_537_680_000,
+                    sun: 40
+                },
+            ]
+        );
+        for bad in ["", "0=100", "0:-1", "x:100"] {
+            assert!(parse_price_history(bad).is_err(), "accepted {:?}", bad);
+        }
+
+        let out = test_output();
+        let shown = print_price_history(&out, "Energy", ParamUnit::SunPerEnergy, &points, 1);
+        assert_eq!(
+            shown,
+            serde_json::json!({
+                "current": 40,
+                "since": 1_606_537_680_000i64,
+                "history": [
+                    { "since": 1_575_331_200_000i64, "sun": 10 },
+                    { "since": 1_606_537_680_000i64, "sun": 40 }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn balances_total_what_could_be_read() {
+        assert_eq!(format_token_amount(12_500_000, 6), "12.500000");
+        assert_eq!(format_token_amount(5, 6), "0.000005");
+        assert_eq!(format_token_amount(42, 0), "42");
+
+        let out = test_output();
+        let balance = |target, amount| TargetOutcome {
+            target,
+            result: Ok(ContractBalance {
+                token: MAINNET_USDT.to_string(),
+                decimals: 6,
+                amount,
+            }),
+        };
+        let balances = [
+            balance("TA", 1_000_000),
+            TargetOutcome {
+                target: "TB",
+                result: Err(anyhow!("Contract not found")),
+            },
+            balance("TC", 2_500_000),
+        ];
+        let shown = print_balances(&out, &balances);
+        assert_eq!(shown["contracts"][0]["balance"], "1000000");
+        assert_eq!(shown["contracts"][1]["error"], "Contract not found");
+        assert_eq!(
+            shown["totals"],
+            serde_json::json!([{
+                "token": MAINNET_USDT,
+                "balance": "3500000",
+                "balanceTokens": "3.500000"
+            }])
+        );
+        assert_eq!(shown["failed"], 1);
+    }
+
+    #[tokio::test]
+    async fn failed_targets_are_reported_or_fail_fast() {
+        let targets = ["TA", "TB", "TC"].map(String::from);
+        let query = |target: &str| -> BoxFuture<'_, Result<usize>> {
+            let target = target.to_string();
+            Box::pin(async move {
+                match target.as_str() {
+                    "TB" => Err(anyhow!("Contract not found")),
+                    _ => Ok(target.len()),
+                }
+            })
+        };
+
+        let outcomes = query_targets(&targets, false, query).await.unwrap();
+        let targets_seen: Vec<&str> = outcomes.iter().map(|outcome| outcome.target).collect();
+        assert_eq!(targets_seen, ["TA", "TB", "TC"]);
+        assert_eq!(outcomes[0].result.as_ref().unwrap(), &2);
+        assert!(outcomes[1].result.is_err());
+
+        let err = query_targets(&targets, true, query).await.err().unwrap();
+        assert_eq!(format!("{:#}", err), "TB: Contract not found");
+    }
+
+    #[test]
+    fn approval_estimates_total_the_approvals_still_needed() {
+        let ok = serde_json::json!({ "result": { "result": true }, "energy_used": 31_000 });
+        assert_eq!(simulated_energy(&ok).unwrap(), 31_000);
+        let reverted = serde_json::json!({
+            "result": { "result": true },
+            "energy_used": 900,
+            "constant_result": ["08c379a0\
+                0000000000000000000000000000000000000000000000000000000000000020\
+                000000000000000000000000000000000000000000000000000000000000000c\
+                4e6f7420616e206f776e65720000000000000000000000000000000000000000"],
+            "transaction": { "ret": [{ "ret": "FAILED" }] }
+        });
+        let err = simulated_energy(&reverted).unwrap_err();
+        assert_eq!(err.to_string(), "Would revert: Not an owner");
+        assert!(matches!(
+            err.downcast_ref::<TronError>(),
+            Some(TronError::Revert(_))
+        ));
+        assert_eq!(error_code(&err), EXIT_FAILURE);
+
+        let out = test_output();
+        let owners = [
+            ("TA".to_string(), ApprovalEstimate::Approved),
+            ("TB".to_string(), ApprovalEstimate::Energy(30_000)),
+            (
+                "TC".to_string(),
+                ApprovalEstimate::Failed(anyhow!("Would revert")),
+            ),
+            ("TD".to_string(), ApprovalEstimate::Energy(31_000)),
+        ];
+        let costs = ApprovalCosts {
+            tx_id: 7,
+            threshold: 3,
+            needed: 2,
+            energy_fee: Some(100),
+            transfer_energy: Some(15_000),
+        };
+        let shown = print_approval_estimate(&out, &costs, &owners);
+        assert_eq!(shown["owners"][1]["costSun"], 3_000_000);
+        assert_eq!(shown["owners"][2]["error"], "Would revert");
+        assert_eq!(shown["totalEnergy"], 76_000);
+        assert_eq!(shown["totalCostSun"], 7_600_000);
+        assert_eq!(approval_shortfall(costs.needed, 2), None);
+
+        // More approvals needed than owners that can be estimated
+        let short = ApprovalCosts { needed: 3, ..costs };
+        let shown = print_approval_estimate(&out, &short, &owners);
+        assert_eq!(shown["totalEnergy"], 76_000);
+        assert_eq!(
+            approval_shortfall(short.needed, 2).as_deref(),
+            Some(
+                "⚠️  3 more approvals are needed but only 2 owners can be estimated; \
+                 the total covers those"
+            )
+        );
+
+        // One approval away: the next approval's simulation includes the transfer
+        let costs = ApprovalCosts {
+            needed: 1,
+            energy_fee: None,
+            transfer_energy: None,
+            ..costs
+        };
+        let shown = print_approval_estimate(&out, &costs, &owners);
+        assert_eq!(shown["totalEnergy"], 30_000);
+        assert_eq!(shown["totalCostSun"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn delegated_resources_parse_with_missing_fields() {
+        let response = serde_json::json!({
+            "delegatedResource": [{
+                "from": "TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b",
+                "to": "TMVQGm1qAQYVdetCeGRRkTWYYrLXuHK2HC",
+                "frozen_balance_for_energy": 5_000_000_000u64,
+                "expire_time_for_energy": 1_709_251_199_999i64
+            }]
+        });
+        let entries = parse_delegated_resources(&response).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].frozen_balance_for_bandwidth, 0);
+        assert_eq!(
+            delegation_lock(entries[0].expire_time_for_energy),
+            "locked until 2024-02-29 23:59:59 UTC"
+        );
+        assert_eq!(
+            delegation_lock(entries[0].expire_time_for_bandwidth),
+            "unlocked"
+        );
+        assert!(parse_delegated_resources(&serde_json::json!({}))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn json_syntax_errors_show_the_offending_text() {
+        let dir = TestDir::new("bad-json");
+        let path = dir.join("bad.json");
+        fs::write(&path, "{\n  \"bytecode\": { \"object\": \"6080\" },,\n}").unwrap();
+        let err = ContractJson::load(&path).unwrap_err().to_string();
+        let mut lines = err.lines();
+        assert!(lines.next().unwrap().ends_with("at line 2 column 36"));
+        assert_eq!(
+            lines.next(),
+            Some("2 |   \"bytecode\": { \"object\": \"6080\" },,")
+        );
+        assert_eq!(
+            lines.next(),
+            Some("  |                                    ^")
+        );
+
+        let long = format!("[{}x]", "1,".repeat(100));
+        let err = serde_json::from_str::<serde_json::Value>(&long).unwrap_err();
+        let snippet = json_error_snippet(long.as_bytes(), &err).unwrap();
+        assert!(snippet.starts_with("1 | …1,1,"));
+        assert!(snippet.lines().next().unwrap().ends_with("x]"));
+        assert_eq!(snippet.lines().nth(1).unwrap().find('^'), Some(4 + 40 + 1));
+
+        // Errors about the content, not the syntax, have no snippet
+        let err = serde_json::from_str::<ContractJson>("{}").unwrap_err();
+        assert_eq!(json_error_snippet("{}".as_bytes(), &err), None);
+    }
+
+    #[test]
+    fn files_load_despite_a_byte_order_mark_and_crlf() {
+        let dir = TestDir::new("bom");
+        let artifact = dir.join("artifact.json");
+        fs::write(
+            &artifact,
+            "\u{feff}{\r\n  \"bytecode\": { \"object\": \"6080\" }\r\n}\r\n",
+        )
+        .unwrap();
+        assert_eq!(
+            ContractJson::load(&artifact).unwrap().bytecode.object,
+            "6080"
+        );
+
+        let manifest = dir.join("deploy.toml");
+        fs::write(
+            &manifest,
+            "\u{feff}network = \"nile\"\r\nthreshold = 2\r\n\r\n",
+        )
+        .unwrap();
+        let text = read_text_file(&manifest).unwrap();
+        assert_eq!(text, "network = \"nile\"\r\nthreshold = 2");
+        assert!(toml::from_str::<toml::Value>(&text).is_ok());
+
+        // A syntax error's snippet is of the line after the mark, not the mark
+        fs::write(&artifact, "\u{feff}{,}").unwrap();
+        let err = ContractJson::load(&artifact).unwrap_err().to_string();
+        assert_eq!(err.lines().nth(1), Some("1 | {,}"));
+    }
+
+    #[test]
+    fn format_utc_renders_calendar_dates() {
+        assert_eq!(format_utc(0), "1970-01-01 00:00:00");
+        assert_eq!(format_utc(1_709_251_199_999), "2024-02-29 23:59:59");
+        assert_eq!(format_utc(951_782_400_000), "2000-02-29 00:00:00");
+    }
+
+    #[test]
+    fn history_entries_decode_events_and_failed_calls() {
+        let contract = parse_address(MAINNET_USDT, TRON_ADDRESS_PREFIX).unwrap();
+        let abi = load_abi_file(None).unwrap();
+        let topic = |signature: &str| hex::encode(keccak256(signature.as_bytes()));
+        let word = |value: &str| format!("{:0>64}", value);
+        let owner = "77e2c2ccf3eae21463a927d2767884468b7615de";
+        let call = ContractCall {
+            block: 42,
+            timestamp: 0,
+            transaction: "ab".repeat(32),
+            caller: "TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b".to_string(),
+            data: [
+                function_selector("approveTransaction(uint256)").to_vec(),
+                hex::decode(word("7")).unwrap(),
+            ]
+            .concat(),
+        };
+
+        let info = serde_json::json!({
+            "receipt": { "result": "SUCCESS" },
+            "log": [
+                {
+                    "address": hex::encode(contract),
+                    "topics": [
+                        topic("TransactionSubmitted(uint256,address,uint256)"),
+                        word("7"),
+                        word("a614f803b6fd780986a42c78ec9c7f77e6ded13c")
+                    ],
+                    "data": word("f4240")
+                },
+                {
+                    "address": hex::encode(contract),
+                    "topics": [topic("TransactionApproved(uint256,address)"), word("7"), word(owner)]
+                },
+                {
+                    // A token Transfer in the same transaction is not governance
+                    "address": "a614f803b6fd780986a42c78ec9c7f77e6ded13d",
+                    "topics": [TRANSFER_EVENT_TOPIC, word(owner), word(owner)]
+                }
+            ]
+        });
+        let entries = history_entries(&call, &info, &contract, &abi, TRON_ADDRESS_PREFIX).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].action, "submit");
+        assert_eq!(entries[0].tx_id.as_deref(), Some("7"));
+        assert_eq!(entries[0].detail, format!("1000000 to {}", MAINNET_USDT));
+        assert_eq!(entries[1].action, "approve");
+        assert_eq!(entries[1].signer, "TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b");
+
+        let failed = serde_json::json!({ "receipt": { "result": "REVERT" } });
+        let entries =
+            history_entries(&call, &failed, &contract, &abi, TRON_ADDRESS_PREFIX).unwrap();
+        assert_eq!(
+            entries,
+            vec![HistoryEntry {
+                block: 42,
+                time: "1970-01-01 00:00:00".to_string(),
+                transaction: "ab".repeat(32),
+                action: "approveTransaction".to_string(),
+                tx_id: Some("7".to_string()),
+                signer: "TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b".to_string(),
+                detail: String::new(),
+                success: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn approval_summary_reports_remaining_approvals() {
+        assert_eq!(
+            approval_summary(3, 5, 4, false),
+            "3/5 approvals, threshold 4 — needs 1 more"
+        );
+        assert_eq!(
+            approval_summary(4, 5, 4, true),
+            "4/5 approvals, threshold 4 — executed"
+        );
+        assert_eq!(
+            approval_summary(2, 3, 2, false),
+            "2/3 approvals, threshold 2 — threshold met"
+        );
+    }
+
+    #[test]
+    fn tampered_signatures_are_caught_before_broadcast() {
+        let tx_id = hex::encode(tron_utils::hash::sha256(b"deployment"));
+        let signed_tx =
+            sign_transaction_json(&sample_transaction(), &tx_id, TEST_KEY, V_OFFSET_TRON).unwrap();
+        let signer = private_key_to_tron_address(TEST_KEY, TRON_ADDRESS_PREFIX).unwrap();
+        check_signed_by(&signed_tx, &tx_id, &signer, TRON_ADDRESS_PREFIX).unwrap();
+
+        // Flipping the recovery byte recovers some other key
+        let mut signature = hex::decode(signed_tx["signature"][0].as_str().unwrap()).unwrap();
+        signature[64] ^= 1;
+        let mut tampered = signed_tx.clone();
+        tampered["signature"] = serde_json::json!([hex::encode(signature)]);
+        let err = check_signed_by(&tampered, &tx_id, &signer, TRON_ADDRESS_PREFIX).unwrap_err();
+        assert!(err.to_string().contains("SIGERROR"), "{}", err);
+
+        let other_tx = hex::encode(tron_utils::hash::sha256(b"another deployment"));
+        assert!(check_signed_by(&signed_tx, &other_tx, &signer, TRON_ADDRESS_PREFIX).is_err());
+        assert!(
+            check_signed_by(&sample_transaction(), &tx_id, &signer, TRON_ADDRESS_PREFIX).is_err()
+        );
+    }
+
+    #[test]
+    fn broadcast_error_explains_signature_mismatch() {
+        for code in ["SIGERROR", "SIGOBJ_NOT_MATCH"] {
+            let err = broadcast_error(code, "Validate signature error").to_string();
+            assert!(err.starts_with(&format!(
+                "Broadcast failed [{}]: Validate signature error",
+                code
+            )));
+            assert!(err.contains("does not belong to the transaction's owner address"));
+            assert!(err.contains("raw_data was modified after signing"));
+        }
+
+        assert_eq!(
+            broadcast_error("BANDWITH_ERROR", "Account resource insufficient").to_string(),
+            "Broadcast failed [BANDWITH_ERROR]: Account resource insufficient"
+        );
+    }
+
+    #[test]
+    fn parse_transaction_response_reads_root_level_transaction() {
+        let mut response = sample_transaction();
+        response["contract_address"] =
+            serde_json::json!("41a614f803b6fd780986a42c78ec9c7f77e6ded13c");
+
+        for api_version in [ApiVersion::Auto, ApiVersion::Current] {
+            let tx = parse_transaction_response(&response, api_version).unwrap();
+            assert_eq!(tx["txID"], "1f8b3c6a");
+        }
+        assert!(parse_transaction_response(&response, ApiVersion::Legacy).is_err());
+    }
+
+    #[test]
+    fn parse_transaction_response_reads_nested_transaction() {
+        let response = serde_json::json!({
+            "result": { "result": true },
+            "transaction": sample_transaction()
+        });
+
+        for api_version in [ApiVersion::Auto, ApiVersion::Legacy] {
+            let tx = parse_transaction_response(&response, api_version).unwrap();
+            assert_eq!(tx, sample_transaction());
+        }
+        assert!(parse_transaction_response(&response, ApiVersion::Current).is_err());
+    }
+
+    #[test]
+    fn parse_transaction_response_decodes_failed_result_message() {
+        let response = serde_json::json!({
+            "result": {
+                "result": false,
+                "code": "CONTRACT_VALIDATE_ERROR",
+                "message": hex::encode("account does not exist")
+            }
+        });
+
+        let err = parse_transaction_response(&response, ApiVersion::Auto).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Failed to create transaction: account does not exist"
+        );
+    }
+
+    #[test]
+    fn parse_transaction_response_reports_error_field() {
+        let response =
+            serde_json::json!({ "Error": "class java.lang.NullPointerException : null" });
+
+        let err = parse_transaction_response(&response, ApiVersion::Current).unwrap_err();
+        assert!(err.to_string().starts_with("API Error:"));
+    }
+
+    #[test]
+    fn parse_transaction_response_rejects_empty_response() {
+        let err = parse_transaction_response(&serde_json::json!({}), ApiVersion::Auto).unwrap_err();
+        assert!(err.to_string().starts_with("No transaction in response"));
+    }
+
+    #[test]
+    fn parse_token_amount_scales_by_decimals() {
+        assert_eq!(parse_token_amount("12.5", 6).unwrap(), 12_500_000);
+        assert_eq!(parse_token_amount("12", 6).unwrap(), 12_000_000);
+        assert_eq!(parse_token_amount("0.000001", 6).unwrap(), 1);
+        assert_eq!(parse_token_amount(".5", 6).unwrap(), 500_000);
+        assert_eq!(parse_token_amount("0", 6).unwrap(), 0);
+        assert_eq!(parse_token_amount("7", 0).unwrap(), 7);
+    }
+
+    #[test]
+    fn parse_token_amount_rejects_malformed_input() {
+        assert!(parse_token_amount("1.0000001", 6)
+            .unwrap_err()
+            .to_string()
+            .contains("only supports 6"));
+        for bad in ["", ".", "-1", "1e6", "1,5", "1.2.3", " 1"] {
+            assert!(parse_token_amount(bad, 6).is_err(), "accepted {:?}", bad);
+        }
+        assert!(parse_token_amount(&"9".repeat(40), 6).is_err());
+    }
+
+    /// Answers every request with the next canned body.
+    struct CannedTransport(std::sync::Mutex<Vec<&'static str>>);
+
+    impl Transport for CannedTransport {
+        fn post<'a>(
+            &'a self,
+            _rpc_url: &'a str,
+            _path: &'a str,
+            _body: &'a serde_json::Value,
+        ) -> BoxFuture<'a, Result<RawResponse>> {
+            let body = self.0.lock().unwrap().remove(0).to_string();
+            Box::pin(async move {
+                Ok(RawResponse {
+                    status: 200,
+                    body,
+                    retry_after: None,
+                })
+            })
+        }
+    }
+
+    /// Answers requests in order with canned bodies, or a connection
+    /// failure for `None`, recording the paths asked for.
+    #[derive(Default)]
+    struct FlakyTransport {
+        replies: std::sync::Mutex<Vec<Option<&'static str>>>,
+        paths: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl Transport for FlakyTransport {
+        fn post<'a>(
+            &'a self,
+            _rpc_url: &'a str,
+            path: &'a str,
+            _body: &'a serde_json::Value,
+        ) -> BoxFuture<'a, Result<RawResponse>> {
+            self.paths.lock().unwrap().push(path.to_string());
+            let reply = self.replies.lock().unwrap().remove(0);
+            Box::pin(async move {
+                match reply {
+                    Some(body) => Ok(RawResponse {
+                        status: 200,
+                        body: body.to_string(),
+                        retry_after: None,
+                    }),
+                    None => Err(anyhow!("connection reset")),
+                }
+            })
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn broadcast_retries_without_sending_twice() {
+        let out = test_output();
+        let client = |replies: Vec<Option<&'static str>>| {
+            let transport = std::sync::Arc::new(FlakyTransport {
+                replies: std::sync::Mutex::new(replies),
+                ..Default::default()
+            });
+            let options = ClientOptions {
+                api_version: ApiVersion::Auto,
+                call_encoding: CallEncoding::Split,
+                transport: transport.clone(),
+                explorer_url: None,
+                broadcast_attempts: 3,
+                address_prefix: TRON_ADDRESS_PREFIX,
+            };
+            (TronClient::new("http://node", &out, &options), transport)
+        };
+        let signed_tx = serde_json::json!({ "txID": "ab".repeat(32) });
+        let broadcast = "/wallet/broadcasttransaction";
+        let info = "/wallet/gettransactioninfobyid";
+
+        // The reply to the first attempt was lost, but the transaction confirmed
+        let (landed, transport) = client(vec![None, Some(r#"{"id":"ab","blockNumber":1}"#)]);
+        let response = landed.broadcast(&signed_tx).await.unwrap();
+        assert_eq!(response["result"], true);
+        assert_eq!(*transport.paths.lock().unwrap(), [broadcast, info]);
+
+        // Still in the node's pool: the resend is reported as a duplicate
+        let (pending, transport) = client(vec![
+            None,
+            Some("{}"),
+            Some(r#"{"code":"DUP_TRANSACTION_ERROR"}"#),
+        ]);
+        assert_eq!(pending.broadcast(&signed_tx).await.unwrap()["result"], true);
+        assert_eq!(
+            *transport.paths.lock().unwrap(),
+            [broadcast, info, broadcast]
+        );
+
+        // A duplicate on the first attempt is the node's answer, not ours
+        let (first, _) = client(vec![Some(r#"{"code":"DUP_TRANSACTION_ERROR"}"#)]);
+        let response = first.broadcast(&signed_tx).await.unwrap();
+        assert_eq!(response["code"], "DUP_TRANSACTION_ERROR");
+    }
+
+    /// Answers with HTTP 429 and `Retry-After: 7` the first `limited` times.
+    struct RateLimitedTransport {
+        limited: std::sync::atomic::AtomicU32,
+    }
+
+    impl Transport for RateLimitedTransport {
+        fn post<'a>(
+            &'a self,
+            _rpc_url: &'a str,
+            _path: &'a str,
+            _body: &'a serde_json::Value,
+        ) -> BoxFuture<'a, Result<RawResponse>> {
+            let limited = self
+                .limited
+                .fetch_update(
+                    std::sync::atomic::Ordering::SeqCst,
+                    std::sync::atomic::Ordering::SeqCst,
+                    |n| n.checked_sub(1),
+                )
+                .is_ok();
+            Box::pin(async move {
+                Ok(match limited {
+                    true => RawResponse {
+                        status: STATUS_RATE_LIMITED,
+                        body: "Too Many Requests".to_string(),
+                        retry_after: Some(std::time::Duration::from_secs(7)),
+                    },
+                    false => RawResponse {
+                        status: 200,
+                        body: "{}".to_string(),
+                        retry_after: None,
+                    },
+                })
+            })
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rate_limited_requests_wait_out_retry_after() {
+        let out = test_output();
+        let client = |limited: u32| {
+            let options = ClientOptions {
+                api_version: ApiVersion::Auto,
+                call_encoding: CallEncoding::Split,
+                transport: std::sync::Arc::new(RateLimitedTransport {
+                    limited: limited.into(),
+                }),
+                explorer_url: None,
+                broadcast_attempts: 1,
+                address_prefix: TRON_ADDRESS_PREFIX,
+            };
+            TronClient::new("http://node", &out, &options)
+        };
+
+        let start = tokio::time::Instant::now();
+        client(2).post("/wallet/getnowblock", &()).await.unwrap();
+        assert_eq!(start.elapsed(), std::time::Duration::from_secs(14));
+
+        let err = client(RATE_LIMIT_RETRIES + 1)
+            .post("/wallet/getnowblock", &())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("pass --api-key"));
+        assert_eq!(error_code(&err), EXIT_RPC);
+    }
+
+    /// Serves one HTTP response with a `len`-byte body, without a
+    /// Content-Length so the size is only known by reading it.
+    async fn serve_body(len: usize) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 4096];
+            let _ = socket.read(&mut request).await;
+            let head = "HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n";
+            let _ = socket.write_all(head.as_bytes()).await;
+            let _ = socket.write_all(&vec![b'a'; len]).await;
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn http_transport_caps_response_size() {
+        let transport = HttpTransport {
+            client: reqwest::Client::new(),
+            max_body_size: 1000,
+            api_key: None,
+        };
+        let body = serde_json::json!({});
+
+        let url = serve_body(1000).await;
+        let response = transport.post(&url, "/wallet/x", &body).await.unwrap();
+        assert_eq!(response.body.len(), 1000);
+
+        let url = serve_body(1001).await;
+        let err = transport.post(&url, "/wallet/x", &body).await.unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("exceeds --max-body-size (1000 bytes)"));
+    }
+
+    #[tokio::test]
+    async fn multisig_info_is_read_once_per_contract() {
+        let out = test_output();
+        // usdt(), getOwners() and threshold(); a second read would run out
+        let canned = vec![
+            r#"{"result":{"result":true},"constant_result":["000000000000000000000000a614f803b6fd780986a42c78ec9c7f77e6ded13c"]}"#,
+            concat!(
+                r#"{"result":{"result":true},"constant_result":["#,
+                r#""0000000000000000000000000000000000000000000000000000000000000020"#,
+                r#"0000000000000000000000000000000000000000000000000000000000000001"#,
+                r#"000000000000000000000000a614f803b6fd780986a42c78ec9c7f77e6ded13c"]}"#
+            ),
+            r#"{"result":{"result":true},"constant_result":["0000000000000000000000000000000000000000000000000000000000000001"]}"#,
+        ];
+        let options = ClientOptions {
+            api_version: ApiVersion::Auto,
+            call_encoding: CallEncoding::Split,
+            transport: std::sync::Arc::new(CannedTransport(std::sync::Mutex::new(canned))),
+            explorer_url: None,
+            broadcast_attempts: 1,
+            address_prefix: TRON_ADDRESS_PREFIX,
+        };
+        let client = TronClient::new("http://node", &out, &options);
+
+        for _ in 0..2 {
+            let info = client.multisig_info(MAINNET_USDT).await.unwrap();
+            assert_eq!(info.owners, [MAINNET_USDT]);
+            assert_eq!(info.threshold, 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn identical_calls_get_distinct_tx_ids() {
+        let out = test_output();
+        // The node builds both payouts in the same millisecond, identically
+        let built = r#"{"result":{"result":true},"transaction":{"txID":"c4b7bee5adad4424bf0b3d02bfb5c9cc911deb2ec6dc04024327dc252752f23e","raw_data":{"timestamp":1000},"raw_data_hex":"0a02abcd5a0301020370e807"}}"#;
+        let accepted = r#"{"result":true}"#;
+        let options = ClientOptions {
+            api_version: ApiVersion::Auto,
+            call_encoding: CallEncoding::Split,
+            transport: std::sync::Arc::new(CannedTransport(std::sync::Mutex::new(vec![
+                built, accepted, built, accepted,
+            ]))),
+            explorer_url: None,
+            broadcast_attempts: 1,
+            address_prefix: TRON_ADDRESS_PREFIX,
+        };
+        let client = TronClient::new("http://node", &out, &options);
+        let signer = Signer::PrivateKey(TEST_KEY.to_string());
+        let param = encode_params(&[
+            AbiValue::Address(parse_address(MAINNET_USDT, TRON_ADDRESS_PREFIX).unwrap()),
+            AbiValue::uint(1_000_000),
+        ]);
+
+        let mut tx_ids = Vec::new();
+        for _ in 0..2 {
+            let signature = "submitTransaction(address,uint256)";
+            tx_ids.push(
+                send_contract_call(&client, &signer, MAINNET_USDT, signature, &param, 1)
+                    .await
+                    .unwrap(),
+            );
+        }
+        assert_eq!(
+            tx_ids[0],
+            "c4b7bee5adad4424bf0b3d02bfb5c9cc911deb2ec6dc04024327dc252752f23e"
+        );
+        // Restamped a millisecond later and rehashed
+        assert_eq!(
+            tx_ids[1],
+            transaction_id(&set_timestamp("0a02abcd5a0301020370e807", 1001).unwrap()).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn submit_refuses_inactive_recipients() {
+        let out = test_output();
+        let options = ClientOptions {
+            api_version: ApiVersion::Auto,
+            call_encoding: CallEncoding::Split,
+            // getaccount for a never-activated address
+            transport: std::sync::Arc::new(CannedTransport(std::sync::Mutex::new(vec!["{}"]))),
+            explorer_url: None,
+            broadcast_attempts: 1,
+            address_prefix: TRON_ADDRESS_PREFIX,
+        };
+        let client = TronClient::new("http://node", &out, &options);
+        let payout = Payout {
+            contract: MAINNET_USDT,
+            to: "TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b",
+            amount: Some(1),
+            amount_tokens: None,
+        };
+        let signer = SignerArgs {
+            private_key: vec![TEST_KEY.to_string()],
+            private_key_env: None,
+            private_key_file: None,
+            keystore: None,
+            mnemonic: None,
+            path: hd::DerivationPath::parse(hd::DEFAULT_TRON_PATH).unwrap(),
+            account: None,
+            index: None,
+            #[cfg(feature = "pkcs11")]
+            pkcs11: Default::default(),
+        };
+
+        let err = submit_transaction(&out, &client, &payout, &signer, false, 1)
+            .await
+            .unwrap_err();
+        assert_eq!(error_code(&err), EXIT_INVALID_INPUT);
+        assert!(err.to_string().contains("pass --allow-inactive"));
+
+        let to_self = Payout {
+            to: MAINNET_USDT,
+            ..payout
+        };
+        let err = submit_transaction(&out, &client, &to_self, &signer, true, 1).await;
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "The multisig cannot pay itself"
+        );
+    }
+
+    #[tokio::test]
+    async fn recorded_exchanges_replay_by_request() {
+        let replay_dir = TestDir::new("replay");
+        let dir = replay_dir.join("exchanges");
+        let poll = serde_json::json!({ "value": "ab" });
+        let account = serde_json::json!({ "address": "T..." });
+
+        let recorder = RecordingTransport {
+            inner: std::sync::Arc::new(CannedTransport(std::sync::Mutex::new(vec![
+                r#"{}"#,
+                r#"{"blockNumber":1}"#,
+                r#"{"balance":5}"#,
+            ]))),
+            dir: dir.clone(),
+            count: Default::default(),
+        };
+        for (path, body) in [
+            ("/wallet/gettransactioninfobyid", &poll),
+            ("/wallet/gettransactioninfobyid", &poll),
+            ("/wallet/getaccount", &account),
+        ] {
+            recorder.post("http://node", path, body).await.unwrap();
+        }
+
+        let replay = ReplayTransport::new(dir.clone());
+        let info = "/wallet/gettransactioninfobyid";
+        let account_reply = replay.post("http://other", "/wallet/getaccount", &account);
+        assert_eq!(account_reply.await.unwrap().body, r#"{"balance":5}"#);
+        assert_eq!(replay.post("", info, &poll).await.unwrap().body, "{}");
+        assert_eq!(
+            replay.post("", info, &poll).await.unwrap().body,
+            r#"{"blockNumber":1}"#
+        );
+        assert!(replay.post("", info, &poll).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn single_flight_shares_concurrent_lookups() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let cache = SingleFlight::<u32>::new();
+        let calls = AtomicUsize::new(0);
+        let lookup = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            Ok(6)
+        };
+
+        let (a, b) = tokio::join!(
+            cache.get_or_try_init("usdt", lookup),
+            cache.get_or_try_init("usdt", lookup)
+        );
+        assert_eq!((a.unwrap(), b.unwrap()), (6, 6));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        cache.get_or_try_init("other", lookup).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
 
-    /// Deploy the USDTMultisig contract
-    Deploy {
-        /// TRON RPC URL (e.g., https://api.trongrid.io)
-        #[arg(long)]
-        rpc_url: String,
+    #[tokio::test]
+    async fn single_flight_retries_after_failure() {
+        let cache = SingleFlight::<u32>::new();
 
-        /// Private key (hex, with or without 0x prefix)
-        #[arg(long)]
-        private_key: String,
+        let failed = cache
+            .get_or_try_init("usdt", || async { Err(anyhow!("rate limited")) })
+            .await;
+        assert!(failed.is_err());
 
-        /// USDT token address (TRON base58 format, e.g., TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t)
-        #[arg(long)]
-        usdt: String,
+        let value = cache.get_or_try_init("usdt", || async { Ok(6) }).await;
+        assert_eq!(value.unwrap(), 6);
+    }
 
-        /// Owner addresses (comma-separated TRON base58 addresses)
-        #[arg(long)]
-        owners: String,
+    #[test]
+    fn contract_call_body_split_form() {
+        let body = contract_call_body(
+            "4177e2c2ccf3eae21463a927d2767884468b7615de",
+            "41a614f803b6fd780986a42c78ec9c7f77e6ded13c",
+            "transfer(address,uint256)",
+            "00ff",
+            CallEncoding::Split,
+        );
 
-        /// Required approval threshold
-        #[arg(long)]
-        threshold: u64,
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "owner_address": "4177e2c2ccf3eae21463a927d2767884468b7615de",
+                "contract_address": "41a614f803b6fd780986a42c78ec9c7f77e6ded13c",
+                "function_selector": "transfer(address,uint256)",
+                "parameter": "00ff"
+            })
+        );
+    }
 
-        /// Path to compiled contract JSON (from forge build)
-        #[arg(long, default_value = "../out/Multisig.sol/USDTMultisig.json")]
-        contract_json: PathBuf,
+    #[test]
+    fn contract_call_body_packed_data_form() {
+        let body = contract_call_body(
+            "4177e2c2ccf3eae21463a927d2767884468b7615de",
+            "41a614f803b6fd780986a42c78ec9c7f77e6ded13c",
+            "transfer(address,uint256)",
+            "00ff",
+            CallEncoding::Data,
+        );
 
-        /// Fee limit in SUN (default: 1000 TRX = 1,000,000,000 SUN)
-        #[arg(long, default_value = "1000000000")]
-        fee_limit: u64,
-    },
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "owner_address": "4177e2c2ccf3eae21463a927d2767884468b7615de",
+                "contract_address": "41a614f803b6fd780986a42c78ec9c7f77e6ded13c",
+                "data": "a9059cbb00ff"
+            })
+        );
+    }
 
-    /// Convert private key to TRON address
-    Address {
-        /// Private key (hex, with or without 0x prefix)
-        #[arg(long)]
-        private_key: String,
-    },
+    const TEST_KEY: &str = "c88c165be5e6d8c58eca95747f8811aa956fa6227c9e0276543bc30d49252d76";
+    /// The chain ID `MockRpc` reports.
+    const NILE_GENESIS: &str = "0000000000000000d698d4192c56cb6be724a558448e2684802de4d6cd8690dc";
 
-    /// Convert hex address to TRON base58 address
-    ToBase58 {
-        /// Hex address (with or without 0x prefix)
-        #[arg(long)]
-        hex: String,
-    },
+    /// Canned node responses, for running command flows without a network.
+    #[derive(Default)]
+    struct MockRpc {
+        deploy: serde_json::Value,
+        constant: serde_json::Value,
+        broadcast: serde_json::Value,
+        tx_info: serde_json::Value,
+        /// Signed transactions passed to `broadcast`
+        broadcasts: std::sync::Mutex<Vec<serde_json::Value>>,
+    }
 
-    /// Convert TRON base58 address to hex
-    ToHex {
-        /// TRON base58 address
-        #[arg(long)]
-        address: String,
-    },
-}
+    /// JSON output with no styling, as the tests read results.
+    fn test_output() -> Output {
+        Output {
+            json: true,
+            compact: false,
+            quiet: false,
+            verbosity: 0,
+            decorated: false,
+        }
+    }
 
-#[derive(Debug, Deserialize)]
-struct ContractJson {
-    bytecode: BytecodeObject,
-}
+    /// A per-test directory under the system temp dir, removed on drop.
+    struct TestDir(std::path::PathBuf);
 
-#[derive(Debug, Deserialize)]
-struct BytecodeObject {
-    object: String,
-}
+    impl TestDir {
+        fn new(name: &str) -> Self {
+            let dir =
+                std::env::temp_dir().join(format!("tron-utils-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TestDir(dir)
+        }
 
-#[derive(Debug, Serialize)]
-struct DeployContractRequest {
-    owner_address: String,
-    fee_limit: u64,
-    call_value: u64,
-    consume_user_resource_percent: u64,
-    origin_energy_limit: u64,
-    abi: String,
-    bytecode: String,
-    parameter: String,
-    name: String,
-}
+        fn join(&self, file: &str) -> std::path::PathBuf {
+            self.0.join(file)
+        }
+    }
 
-#[derive(Debug, Deserialize)]
-struct BroadcastResponse {
-    result: Option<bool>,
-    #[allow(dead_code)]
-    txid: Option<String>,
-    code: Option<String>,
-    message: Option<String>,
-}
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
+    fn ready<'a, T: 'a>(value: T) -> BoxFuture<'a, Result<T>> {
+        Box::pin(async move { Ok(value) })
+    }
 
-    match cli.command {
-        Commands::GenerateKey { json } => {
-            generate_private_key(json)?;
+    impl TronRpc for MockRpc {
+        fn get_contract<'a>(&'a self, _: &'a str) -> BoxFuture<'a, Result<serde_json::Value>> {
+            ready(serde_json::json!({}))
         }
-        Commands::Deploy {
-            rpc_url,
-            private_key,
-            usdt,
-            owners,
-            threshold,
-            contract_json,
-            fee_limit,
-        } => {
-            deploy_contract(
-                &rpc_url,
-                &private_key,
-                &usdt,
-                &owners,
-                threshold,
-                &contract_json,
-                fee_limit,
-            )
-            .await?;
+
+        fn chain_parameters(&self) -> BoxFuture<'_, Result<HashMap<String, i64>>> {
+            ready(HashMap::from([(
+                "getMaxFeeLimit".to_string(),
+                15_000_000_000,
+            )]))
         }
-        Commands::Address { private_key } => {
-            let address = private_key_to_tron_address(&private_key)?;
-            println!("TRON Address: {}", address);
+
+        fn deploy_contract<'a>(
+            &'a self,
+            _: &'a DeployContractRequest,
+        ) -> BoxFuture<'a, Result<serde_json::Value>> {
+            ready(self.deploy.clone())
         }
-        Commands::ToBase58 { hex } => {
-            let address = hex_to_tron_address(&hex)?;
-            println!("TRON Address: {}", address);
+
+        fn trigger_constant<'a>(
+            &'a self,
+            _: &'a str,
+            _: &'a str,
+            _: &'a str,
+        ) -> BoxFuture<'a, Result<serde_json::Value>> {
+            ready(self.constant.clone())
         }
-        Commands::ToHex { address } => {
-            let hex = tron_address_to_hex(&address)?;
-            println!("Hex: {}", hex);
+
+        fn broadcast<'a>(
+            &'a self,
+            signed_tx: &'a serde_json::Value,
+        ) -> BoxFuture<'a, Result<serde_json::Value>> {
+            self.broadcasts.lock().unwrap().push(signed_tx.clone());
+            ready(self.broadcast.clone())
         }
-    }
 
-    Ok(())
-}
+        fn get_tx_info<'a>(&'a self, _: &'a str) -> BoxFuture<'a, Result<serde_json::Value>> {
+            ready(self.tx_info.clone())
+        }
 
-fn generate_private_key(json_output: bool) -> Result<()> {
-    let secp = Secp256k1::new();
-    let (secret_key, _public_key) = secp.generate_keypair(&mut OsRng);
-    
-    let private_key_hex = hex::encode(secret_key.secret_bytes());
-    let address = private_key_to_tron_address(&private_key_hex)?;
-    
-    if json_output {
-        let output = serde_json::json!({
-            "privateKey": private_key_hex,
-            "address": address
-        });
-        println!("{}", serde_json::to_string_pretty(&output)?);
-    } else {
-        println!("🔑 New TRON Wallet Generated");
-        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-        println!("Private Key: {}", private_key_hex);
-        println!("Address:     {}", address);
-        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-        println!("\n⚠️  IMPORTANT: Save your private key securely! Never share it!");
-    }
-    
-    Ok(())
-}
+        fn multisig_info<'a>(&'a self, contract: &'a str) -> BoxFuture<'a, Result<MultisigInfo>> {
+            Box::pin(fetch_multisig_info(self, contract))
+        }
 
-async fn deploy_contract(
-    rpc_url: &str,
-    private_key: &str,
-    usdt: &str,
-    owners: &str,
-    threshold: u64,
-    contract_json: &PathBuf,
-    fee_limit: u64,
-) -> Result<()> {
-    println!("🚀 Deploying USDTMultisig contract to TRON...\n");
+        fn chain_id(&self) -> BoxFuture<'_, Result<String>> {
+            ready(NILE_GENESIS.to_string())
+        }
 
-    // Parse private key and get deployer address
-    let deployer = private_key_to_tron_address(private_key)?;
-    println!("Deployer: {}", deployer);
+        fn address_prefix(&self) -> u8 {
+            TRON_ADDRESS_PREFIX
+        }
+    }
 
-    // Parse owners
-    let owner_list: Vec<&str> = owners.split(',').map(|s| s.trim()).collect();
-    println!("Owners: {:?}", owner_list);
-    println!("Threshold: {}", threshold);
-    println!("USDT: {}", usdt);
+    /// A deployment of the sample manifest against a minimal artifact.
+    fn mock_deployment<'a>(
+        rpc: &'a MockRpc,
+        name: &str,
+        flags: &[&str],
+    ) -> impl Future<Output = Result<serde_json::Value>> + 'a {
+        let dir = TestDir::new(name);
+        let artifact = dir.join("artifact.json");
+        fs::write(&artifact, r#"{"bytecode":{"object":"6080"}}"#).unwrap();
+        let mut plan = DeployPlan::from_manifest(
+            sample_manifest(),
+            std::path::Path::new(""),
+            TRON_ADDRESS_PREFIX,
+        )
+        .unwrap();
+        plan.contract_json = artifact;
+        let Cli {
+            command: Commands::Deploy(args),
+            ..
+        } = Cli::try_parse_from(
+            [
+                "tron-utils",
+                "deploy",
+                "--manifest",
+                "deploy.toml",
+                "--private-key",
+                TEST_KEY,
+            ]
+            .iter()
+            .chain(flags),
+        )
+        .unwrap()
+        else {
+            unreachable!("parsed a deploy command")
+        };
+        let out = test_output();
+        async move {
+            let signer = args.signer.signer(TRON_ADDRESS_PREFIX)?;
+            let result =
+                submit_deployment(&out, rpc, ApiVersion::Auto, None, &plan, &args, &signer).await;
+            drop(dir);
+            result
+        }
+    }
 
-    // Validate threshold
-    if threshold == 0 || threshold as usize > owner_list.len() {
-        return Err(anyhow!(
-            "Invalid threshold: must be > 0 and <= number of owners"
-        ));
+    fn deploy_response() -> serde_json::Value {
+        serde_json::json!({
+            "txID": "ab".repeat(32),
+            "raw_data": { "contract": [], "expiration": 1 },
+            "raw_data_hex": "0a02",
+            "contract_address": "41a614f803b6fd780986a42c78ec9c7f77e6ded13c"
+        })
     }
 
-    // Load contract bytecode
-    let contract_data = fs::read_to_string(contract_json)
-        .with_context(|| format!("Failed to read contract JSON: {:?}", contract_json))?;
-    let contract: ContractJson =
-        serde_json::from_str(&contract_data).context("Failed to parse contract JSON")?;
-    let bytecode = &contract.bytecode.object;
-    println!("Bytecode length: {} bytes", bytecode.len() / 2);
+    #[tokio::test]
+    async fn deployment_signs_and_broadcasts() {
+        let rpc = MockRpc {
+            deploy: deploy_response(),
+            broadcast: serde_json::json!({ "result": true, "txid": "ab".repeat(32) }),
+            ..Default::default()
+        };
 
-    // Encode constructor parameters
-    let params = encode_constructor_params(usdt, &owner_list, threshold)?;
-    println!("Constructor params: {}", params);
+        let result = mock_deployment(&rpc, "deploy-ok", &[]).await.unwrap();
+        assert_eq!(
+            result["contractAddress"],
+            "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t"
+        );
+        assert_eq!(result["broadcast"], true);
 
-    // Contract ABI (simplified for deployment)
-    let abi = get_contract_abi();
+        let broadcasts = rpc.broadcasts.lock().unwrap();
+        assert_eq!(broadcasts.len(), 1);
+        assert_eq!(broadcasts[0]["txID"], "ab".repeat(32));
+        assert_eq!(broadcasts[0]["signature"].as_array().unwrap().len(), 1);
+        assert_eq!(result["transaction"], broadcasts[0]["txID"]);
+        assert!(result.get("txId").is_none());
+        assert!(result.get("abi").is_none());
+    }
 
-    // Build deploy request
-    let deployer_hex = tron_address_to_hex(&deployer)?;
-    let request = DeployContractRequest {
-        owner_address: deployer_hex.clone(),
-        fee_limit,
-        call_value: 0,
-        consume_user_resource_percent: 100,
-        origin_energy_limit: 10000000,
-        abi: abi.to_string(),
-        bytecode: bytecode.clone(),
-        parameter: params,
-        name: "USDTMultisig".to_string(),
-    };
+    #[tokio::test]
+    async fn deployment_records_carry_the_abi_on_request() {
+        let rpc = MockRpc {
+            deploy: deploy_response(),
+            broadcast: serde_json::json!({ "result": true, "txid": "ab".repeat(32) }),
+            ..Default::default()
+        };
+        let result = mock_deployment(&rpc, "deploy-abi", &["--include-abi"])
+            .await
+            .unwrap();
 
-    println!("\n📡 Creating deployment transaction...");
+        // A saved record is an ABI file for `call --abi`
+        let dir = TestDir::new("deploy-record");
+        let record = dir.join("record.json");
+        fs::write(&record, success_envelope(result).to_string()).unwrap();
+        let abi = load_abi_file(Some(&record)).unwrap();
+        assert_eq!(
+            abi.function("submitTransaction", 2)
+                .unwrap()
+                .signature()
+                .unwrap(),
+            "submitTransaction(address,uint256)"
+        );
+    }
 
-    // Create deployment transaction
-    let client = reqwest::Client::new();
-    let response_text = client
-        .post(format!("{}/wallet/deploycontract", rpc_url))
-        .json(&request)
-        .send()
-        .await?
-        .text()
-        .await?;
+    #[tokio::test]
+    async fn printed_requests_are_not_signed_or_broadcast() {
+        let rpc = MockRpc {
+            deploy: deploy_response(),
+            ..Default::default()
+        };
 
-    // Parse response
-    let response: serde_json::Value = serde_json::from_str(&response_text)
-        .with_context(|| format!("Failed to parse response: {}", response_text))?;
+        let result = mock_deployment(&rpc, "deploy-print", &["--print-request"])
+            .await
+            .unwrap();
+        let requests = &result["requests"];
+        assert_eq!(
+            requests["/wallet/deploycontract"]["bytecode"],
+            serde_json::json!("6080")
+        );
+        assert_eq!(
+            requests["/wallet/broadcasttransaction"]["signature"],
+            serde_json::json!(["<redacted>"])
+        );
+        assert!(rpc.broadcasts.lock().unwrap().is_empty());
+    }
 
-    // Check for errors in response
-    if let Some(result) = response.get("result") {
-        if result.get("result") == Some(&serde_json::json!(false)) {
-            let msg = result
-                .get("message")
-                .and_then(|m| m.as_str())
-                .map(|m| decode_hex_message(m))
-                .unwrap_or_else(|| "Unknown error".to_string());
-            return Err(anyhow!("Failed to create transaction: {}", msg));
+    #[tokio::test]
+    async fn deployment_reports_node_rejection() {
+        let rpc = MockRpc {
+            deploy: serde_json::json!({
+                "result": { "result": false, "message": hex::encode("contract validate error : No contract or not a valid smart contract") }
+            }),
+            ..Default::default()
+        };
+
+        let err = mock_deployment(&rpc, "deploy-rejected", &[])
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Failed to create transaction: contract validate error : No contract or not a valid smart contract"
+        );
+        assert!(rpc.broadcasts.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn deployment_explains_expired_transaction() {
+        let rpc = MockRpc {
+            deploy: deploy_response(),
+            broadcast: serde_json::json!({
+                "code": "TRANSACTION_EXPIRATION_ERROR",
+                "message": hex::encode("Transaction expired")
+            }),
+            ..Default::default()
+        };
+
+        let err = mock_deployment(&rpc, "deploy-expired", &[])
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.starts_with("Broadcast failed [TRANSACTION_EXPIRATION_ERROR]: Transaction expired")
+        );
+        assert!(err.contains("build and sign a fresh one"));
+    }
+
+    #[tokio::test]
+    async fn broadcast_refuses_transactions_bound_to_another_chain() {
+        let mut deploy = deploy_response();
+        deploy["raw_data_hex"] = "0a02abcd".into();
+        deploy["txID"] = transaction_id("0a02abcd").unwrap().into();
+        let rpc = MockRpc {
+            deploy,
+            broadcast: serde_json::json!({ "result": true }),
+            ..Default::default()
+        };
+        let dir = TestDir::new("bound");
+        let signed_tx_out = dir.join("signed.json");
+        let path = signed_tx_out.to_str().unwrap();
+        mock_deployment(
+            &rpc,
+            "deploy-bound",
+            &["--no-broadcast", "--signed-tx-out", path],
+        )
+        .await
+        .unwrap();
+        let exported: serde_json::Value =
+            read_json_file(&signed_tx_out, "signed transaction").unwrap();
+        assert_eq!(exported[CHAIN_ID_FIELD], NILE_GENESIS);
+        // Without a file, --no-broadcast hands back the same transaction
+        let result = mock_deployment(&rpc, "deploy-bound", &["--no-broadcast"])
+            .await
+            .unwrap();
+        assert_eq!(result["signedTransaction"], exported);
+        for field in ["txID", "raw_data", "raw_data_hex", "signature"] {
+            assert!(exported.get(field).is_some(), "no {}", field);
         }
+
+        let out = test_output();
+        let mut mainnet = exported.clone();
+        mainnet[CHAIN_ID_FIELD] =
+            "00000000000000001ebf88508a03865c71d452e25f4d51194196a1d22b6653dc".into();
+        let err = broadcast_signed(&out, &rpc, mainnet.clone(), false)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Pass --force"), "{}", err);
+        assert!(rpc.broadcasts.lock().unwrap().is_empty());
+
+        broadcast_signed(&out, &rpc, exported, false).await.unwrap();
+        broadcast_signed(&out, &rpc, mainnet, true).await.unwrap();
+        let broadcasts = rpc.broadcasts.lock().unwrap();
+        assert_eq!(broadcasts.len(), 2);
+        assert!(broadcasts.iter().all(|tx| tx.get(CHAIN_ID_FIELD).is_none()));
     }
 
-    // Check for Error field (some API versions use this)
-    if let Some(error) = response.get("Error") {
-        return Err(anyhow!("API Error: {}", error));
+    #[tokio::test]
+    async fn extend_expiration_rehashes_raw_data() {
+        let rpc = MockRpc::default();
+        let out = test_output();
+        let mut transaction = serde_json::json!({
+            "txID": "00",
+            "raw_data": { "expiration": 1_000_060_000i64, "timestamp": 1_000_000_000i64 },
+            "raw_data_hex": "0a02abcd40e0e8eedc03708094ebdc03"
+        });
+
+        let tx_id = extend_expiration(&out, &rpc, &mut transaction, 3600)
+            .await
+            .unwrap();
+        let raw_data_hex = transaction["raw_data_hex"].as_str().unwrap();
+        assert_eq!(
+            raw_data_hex,
+            set_expiration("0a02abcd708094ebdc03", 1_003_600_000).unwrap()
+        );
+        assert_eq!(tx_id, transaction_id(raw_data_hex).unwrap());
+        assert_eq!(transaction["txID"], tx_id);
+        assert_eq!(transaction["raw_data"]["expiration"], 1_003_600_000i64);
+
+        let err = extend_expiration(&out, &rpc, &mut transaction, 24 * 3600 + 1)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "--expiration must be between 1 and 86400 seconds"
+        );
     }
 
-    // TRON API returns transaction fields at root level (txID, raw_data, etc.)
-    // NOT nested under a "transaction" key
-    let transaction = if response.get("transaction").is_some() {
-        response.get("transaction").cloned().unwrap()
-    } else if response.get("txID").is_some() {
-        // Transaction fields are at root level
-        response.clone()
-    } else {
-        return Err(anyhow!("No transaction in response. Full response:\n{}", 
-            serde_json::to_string_pretty(&response).unwrap_or_default()));
-    };
+    #[tokio::test]
+    async fn verify_transfer_decodes_res_message_revert_reason() {
+        // resMessage of a transfer rejected by `require(isOwner[msg.sender], "Not an owner")`
+        let res_message = "08c379a0\
+            0000000000000000000000000000000000000000000000000000000000000020\
+            000000000000000000000000000000000000000000000000000000000000000c\
+            4e6f7420616e206f776e65720000000000000000000000000000000000000000";
+        let rpc = MockRpc {
+            tx_info: serde_json::json!({
+                "id": "ab",
+                "result": "FAILED",
+                "resMessage": res_message,
+                "receipt": { "result": "REVERT" }
+            }),
+            ..Default::default()
+        };
+        let out = test_output();
 
-    let tx_id = transaction
-        .get("txID")
-        .and_then(|v| v.as_str())
-        .map(String::from)
-        .ok_or_else(|| anyhow!("No txID in response"))?;
+        let err = verify_transfer(&out, &rpc, "ab", MAINNET_USDT, 1, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Transaction ab reverted: Not an owner");
 
-    println!("Transaction ID: {}", tx_id);
+        let text = serde_json::json!({ "resMessage": hex::encode("REVERT opcode executed") });
+        assert_eq!(
+            revert_reason(&text).as_deref(),
+            Some("REVERT opcode executed")
+        );
+    }
 
-    // Sign transaction
-    println!("\n🔐 Signing transaction...");
-    let signature = sign_transaction(&tx_id, private_key)?;
+    #[tokio::test]
+    async fn view_call_surfaces_revert_reason() {
+        let rpc = MockRpc {
+            constant: serde_json::json!({
+                "result": { "result": false, "message": hex::encode("REVERT opcode executed") }
+            }),
+            ..Default::default()
+        };
 
-    // Add signature to transaction
-    let mut signed_tx = transaction.clone();
-    signed_tx
-        .as_object_mut()
-        .ok_or_else(|| anyhow!("Transaction is not an object"))?
-        .insert("signature".to_string(), serde_json::json!([signature]));
+        let err = fetch_transaction_status(&rpc, "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t", 7)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            format!("{:#}", err),
+            "Couldn't read transaction 7: Call to getTransaction(uint256) failed: REVERT opcode executed"
+        );
 
-    // Broadcast transaction
-    println!("📤 Broadcasting transaction...");
-    let broadcast_response = client
-        .post(format!("{}/wallet/broadcasttransaction", rpc_url))
-        .json(&signed_tx)
-        .send()
-        .await?
-        .json::<BroadcastResponse>()
-        .await?;
+        // Only TransactionNotFound() means the ID is unknown
+        let not_found = MockRpc {
+            constant: serde_json::json!({
+                "result": { "result": false, "message": hex::encode("REVERT opcode executed") },
+                "constant_result": [hex::encode(function_selector("TransactionNotFound()"))]
+            }),
+            ..Default::default()
+        };
+        assert!(
+            fetch_multisig_transaction(&not_found, "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t", 7)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        let err = fetch_transaction_status(&not_found, "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t", 7)
+            .await
+            .unwrap_err();
+        assert_eq!(error_code(&err), EXIT_INVALID_INPUT);
+        assert_eq!(err.to_string(), "Transaction 7 does not exist");
+    }
 
-    if broadcast_response.result != Some(true) {
-        let code = broadcast_response.code.unwrap_or_default();
-        let msg = broadcast_response
-            .message
-            .map(|m| decode_hex_message(&m))
-            .unwrap_or_else(|| "Unknown error".to_string());
-        return Err(anyhow!("Broadcast failed [{}]: {}", code, msg));
+    #[tokio::test]
+    async fn approval_preflight_rejects_non_owners() {
+        let is_owner = |flag: u8| MockRpc {
+            constant: serde_json::json!({
+                "result": { "result": true },
+                "constant_result": [format!("{:064x}", flag)]
+            }),
+            ..Default::default()
+        };
+        let contract = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t";
+        let signer = "TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b";
+
+        check_is_owner(&is_owner(1), contract, signer)
+            .await
+            .unwrap();
+        let err = check_is_owner(&is_owner(0), contract, signer)
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b is not an owner of TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t"));
     }
 
-    // Get contract address from response
-    let contract_address = response
-        .get("contract_address")
-        .and_then(|v| v.as_str())
-        .map(|hex| {
-            // Convert hex address (41...) to base58
-            hex_to_tron_address(hex).unwrap_or_else(|_| hex.to_string())
-        })
-        .unwrap_or_else(|| "(Check TronScan for contract address)".to_string());
+    #[test]
+    fn multisig_transactions_decode_from_get_transaction() {
+        let words = [
+            format!("{:0>64}", "a614f803b6fd780986a42c78ec9c7f77e6ded13c"),
+            format!("{:064x}", 12_500_000),
+            format!("{:064x}", 0),
+            format!("{:064x}", 2),
+            format!("{:064x}", 1_700_000_000),
+            format!("{:064x}", 1_700_086_400),
+        ];
+        assert_eq!(
+            MultisigTransaction::decode(&words.concat(), TRON_ADDRESS_PREFIX).unwrap(),
+            MultisigTransaction {
+                to: "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t".to_string(),
+                amount: 12_500_000,
+                executed: false,
+                approval_count: 2,
+                created_at: 1_700_000_000,
+                expires_at: 1_700_086_400,
+            }
+        );
+        // TransactionNotFound() revert data
+        let not_found = hex::encode(function_selector("TransactionNotFound()"));
+        assert!(MultisigTransaction::decode(&not_found, TRON_ADDRESS_PREFIX).is_err());
+    }
 
-    println!("\n✅ Contract deployed successfully!");
-    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-    println!("Transaction: {}", tx_id);
-    println!("Contract:    {}", contract_address);
-    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-    println!("\nView on TronScan: https://nile.tronscan.org/#/transaction/{}", tx_id);
+    #[tokio::test]
+    async fn revoke_preflight_needs_a_pending_approval() {
+        // Every view call gets this answer: word 0 is read as `isApproved`
+        // and words 2 and 5 as getTransaction's `executed` and `expiresAt`
+        let answer_expiring = |approved: u8, executed: u8, expires_at: u64| {
+            let words = [
+                format!("{:064x}", approved),
+                format!("{:064x}", 1_000_000),
+                format!("{:064x}", executed),
+                format!("{:064x}", 1),
+                format!("{:064x}", 1_700_000_000),
+                format!("{:064x}", expires_at),
+            ];
+            MockRpc {
+                constant: serde_json::json!({
+                    "result": { "result": true },
+                    "constant_result": [words.concat()]
+                }),
+                ..Default::default()
+            }
+        };
+        let answer = |approved, executed| answer_expiring(approved, executed, 4_000_000_000);
+        let out = test_output();
+        let contract = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t";
+        let revoker = "TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b";
 
-    Ok(())
-}
+        preflight_revoke(&out, &answer(1, 0), contract, 4, revoker)
+            .await
+            .unwrap();
+        let err = preflight_revoke(&out, &answer(0, 0), contract, 4, revoker)
+            .await
+            .unwrap_err();
+        assert_eq!(error_code(&err), EXIT_INVALID_INPUT);
+        assert!(err
+            .to_string()
+            .starts_with("TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b has not approved transaction 4"));
+        let err = preflight_revoke(&out, &answer(1, 1), contract, 4, revoker)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("already executed"));
+        let err = preflight_revoke(
+            &out,
+            &answer_expiring(1, 0, 1_700_086_400),
+            contract,
+            4,
+            revoker,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error_code(&err), EXIT_INVALID_INPUT);
+        assert_eq!(
+            err.to_string(),
+            "Transaction 4 expired at 2023-11-15 22:13:20 UTC, so the revoke would revert with \
+             TransactionExpired()"
+        );
+    }
 
-fn private_key_to_tron_address(private_key: &str) -> Result<String> {
-    let key_hex = private_key.trim_start_matches("0x");
-    let key_bytes = hex::decode(key_hex).context("Invalid private key hex")?;
+    #[tokio::test]
+    async fn approval_preflight_rejects_executed_transactions() {
+        // The mock answers every view call with this getTransaction result,
+        // so the owner check (covered above) stays off
+        let status = |executed: u8| {
+            let words = [
+                format!("{:0>64}", "a614f803b6fd780986a42c78ec9c7f77e6ded13c"),
+                format!("{:064x}", 1_000_000),
+                format!("{:064x}", executed),
+                format!("{:064x}", 2),
+                format!("{:064x}", 1_700_000_000),
+                format!("{:064x}", 4_000_000_000u64),
+            ];
+            MockRpc {
+                constant: serde_json::json!({
+                    "result": { "result": true },
+                    "constant_result": [words.concat()]
+                }),
+                ..Default::default()
+            }
+        };
+        let out = test_output();
+        let contract = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t";
+        let approver = "TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b";
+        let executed_only = ApprovalChecks {
+            owner: false,
+            not_executed: true,
+        };
+
+        preflight_approval(&out, &status(0), contract, 4, approver, executed_only)
+            .await
+            .unwrap();
+        let err = preflight_approval(&out, &status(1), contract, 4, approver, executed_only)
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Transaction 4 has already executed"));
+        let none = ApprovalChecks {
+            not_executed: false,
+            ..executed_only
+        };
+        preflight_approval(&out, &status(1), contract, 4, approver, none)
+            .await
+            .unwrap();
+    }
 
-    let secp = Secp256k1::new();
-    let secret_key = SecretKey::from_slice(&key_bytes).context("Invalid private key")?;
-    let public_key = secret_key.public_key(&secp);
+    #[test]
+    fn receipts_are_classified_with_advice() {
+        let outcome = |info: serde_json::Value| {
+            let receipt = TransactionReceipt::from_info(&info).unwrap();
+            TxOutcome::classify(&info, &receipt)
+        };
+        let out_of_energy = serde_json::json!({
+            "receipt": {
+                "result": "OUT_OF_ENERGY",
+                "energy_fee": 100_000_000,
+                "energy_usage_total": 238_095,
+                "energy_penalty_total": 40_000
+            }
+        });
+        let receipt = TransactionReceipt::from_info(&out_of_energy).unwrap();
+        assert_eq!(
+            (
+                receipt.energy_usage_total,
+                receipt.energy_penalty_total,
+                receipt.net_usage
+            ),
+            (238_095, 40_000, 0)
+        );
+        let err = outcome(out_of_energy).error("ab").to_string();
+        assert!(err.starts_with("Transaction ab did not succeed: OUT_OF_ENERGY\n"));
+        assert!(err.contains("higher --fee-limit"));
 
-    // Get uncompressed public key (65 bytes: 04 + x + y)
-    let pub_key_bytes = public_key.serialize_uncompressed();
+        assert_eq!(outcome(serde_json::json!({ "fee": 1 })), TxOutcome::Success);
+        let reverted = serde_json::json!({
+            "receipt": { "result": "REVERT" },
+            "resMessage": hex::encode("REVERT opcode executed")
+        });
+        assert_eq!(
+            outcome(reverted),
+            TxOutcome::Reverted(Some("REVERT opcode executed".to_string()))
+        );
+        let timed_out = outcome(serde_json::json!({ "receipt": { "result": "OUT_OF_TIME" } }));
+        assert!(timed_out.advice().unwrap().contains("won't help"));
+        assert_eq!(
+            outcome(serde_json::json!({ "receipt": { "result": "STACK_TOO_LARGE" } })),
+            TxOutcome::Unknown("STACK_TOO_LARGE".to_string())
+        );
+    }
 
-    // Keccak256 hash of public key (skip first byte 0x04)
-    let mut hasher = Keccak256::new();
-    hasher.update(&pub_key_bytes[1..]);
-    let hash = hasher.finalize();
+    #[tokio::test]
+    async fn cost_summary_reports_what_staking_covered() {
+        let out = test_output();
+        let rpc = MockRpc {
+            tx_info: serde_json::json!({
+                "fee": 6_545_000,
+                "receipt": {
+                    "result": "SUCCESS",
+                    "energy_usage": 35_000,
+                    "energy_fee": 6_300_000,
+                    "energy_usage_total": 65_000,
+                    "net_fee": 245_000
+                }
+            }),
+            ..Default::default()
+        };
+        let result = serde_json::json!({ "transaction": "ab".repeat(32) });
+        let cost = with_cost_summary(&out, &rpc, result.clone(), true)
+            .await
+            .unwrap();
+        assert_eq!(cost["cost"]["fee"], 6_545_000);
+        assert_eq!(cost["cost"]["coveredByStake"], "partly");
+        assert_eq!(
+            with_cost_summary(&out, &rpc, result, false).await.unwrap(),
+            serde_json::json!({ "transaction": "ab".repeat(32) })
+        );
 
-    // Take last 20 bytes and add 0x41 prefix (TRON mainnet)
-    let mut address_bytes = vec![0x41];
-    address_bytes.extend_from_slice(&hash[12..]);
+        let staked = TransactionReceipt {
+            energy_usage: 65_000,
+            net_usage: 345,
+            ..Default::default()
+        };
+        assert_eq!(staked_coverage(&staked), "fully");
+        let burned = TransactionReceipt {
+            energy_fee: 1,
+            net_fee: 1,
+            ..Default::default()
+        };
+        assert_eq!(staked_coverage(&burned), "no");
 
-    // Base58Check encode
-    Ok(bs58_check_encode(&address_bytes))
-}
+        let reverted = MockRpc {
+            tx_info: serde_json::json!({ "fee": 1, "receipt": { "result": "REVERT" } }),
+            ..Default::default()
+        };
+        let err = confirmed_cost(&out, &reverted, "ab").await.unwrap_err();
+        assert_eq!(err.to_string(), "Transaction ab did not succeed: REVERT");
+    }
 
-fn tron_address_to_hex(address: &str) -> Result<String> {
-    let bytes = bs58_check_decode(address)?;
-    Ok(hex::encode(&bytes))
-}
+    #[tokio::test]
+    async fn verify_transfer_rejects_reverted_transaction() {
+        let rpc = MockRpc {
+            tx_info: serde_json::json!({ "id": "ab", "receipt": { "result": "REVERT" } }),
+            ..Default::default()
+        };
+        let out = test_output();
 
-fn hex_to_tron_address(hex_addr: &str) -> Result<String> {
-    let clean_hex = hex_addr.trim_start_matches("0x");
-    let bytes = hex::decode(clean_hex).context("Invalid hex")?;
-    Ok(bs58_check_encode(&bytes))
-}
+        let err = verify_transfer(
+            &out,
+            &rpc,
+            "ab",
+            "TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b",
+            1,
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Transaction ab did not succeed: REVERT");
+    }
 
-fn bs58_check_encode(data: &[u8]) -> String {
-    // Double SHA256 for checksum
-    let hash1 = sha256(data);
-    let hash2 = sha256(&hash1);
-    let checksum = &hash2[0..4];
+    fn sample_manifest() -> DeployManifest {
+        toml::from_str(
+            r#"
+            network = "nile"
+            usdt = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t"
+            owners = ["TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b", "TMSritvdndPwxnL3pUjTWMLGozq7XNFavE"]
+            threshold = 2
+            contract_json = "out/USDTMultisig.json"
+            "#,
+        )
+        .unwrap()
+    }
 
-    let mut with_checksum = data.to_vec();
-    with_checksum.extend_from_slice(checksum);
+    #[test]
+    fn sorted_owners_follow_their_address_bytes() {
+        let given = [
+            "TMVQGm1qAQYVdetCeGRRkTWYYrLXuHK2HC",
+            "TMSritvdndPwxnL3pUjTWMLGozq7XNFavE",
+            "TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b",
+        ];
+        let mut manifest = sample_manifest();
+        manifest.owners = given.iter().map(|owner| owner.to_string()).collect();
+        manifest.sort_owners = true;
+        let plan =
+            DeployPlan::from_manifest(manifest, std::path::Path::new(""), TRON_ADDRESS_PREFIX)
+                .unwrap();
+        assert!(plan.owners_sorted);
+        assert!(plan
+            .owners
+            .is_sorted_by_key(|owner| parse_address(owner, TRON_ADDRESS_PREFIX).unwrap()));
+        assert_ne!(plan.owners, given);
 
-    bs58::encode(&with_checksum).into_string()
-}
+        let Cli {
+            command: Commands::Deploy(args),
+            ..
+        } = Cli::try_parse_from([
+            "tron-utils",
+            "deploy",
+            "--rpc-url",
+            "https://nile.trongrid.io",
+            "--usdt",
+            MAINNET_USDT,
+            "--owners",
+            &given.join(","),
+            "--threshold",
+            "2",
+            "--sort-owners",
+        ])
+        .unwrap()
+        else {
+            unreachable!()
+        };
+        let flagged = DeployPlan::from_args(&args, TRON_ADDRESS_PREFIX).unwrap();
+        assert_eq!(flagged.owners, plan.owners);
+        assert!(deploy_script(&flagged, &args, "00", "ab").contains("--sort-owners"));
+    }
+
+    #[test]
+    fn numeric_flags_read_env_references() {
+        std::env::set_var("TRON_UTILS_TEST_FEE_LIMIT", "250000000\n");
+        std::env::set_var("TRON_UTILS_TEST_THRESHOLD", "two");
+        let parse = |flags: &[&str]| {
+            let args = [
+                "tron-utils",
+                "deploy",
+                "--rpc-url",
+                "http://localhost",
+                "--usdt",
+                MAINNET_USDT,
+                "--owners",
+                MAINNET_USDT,
+                "--threshold",
+                "1",
+            ];
+            Cli::try_parse_from(args.iter().chain(flags)).map(|cli| match cli.command {
+                Commands::Deploy(args) => args,
+                _ => unreachable!("parsed a deploy command"),
+            })
+        };
+        let args = parse(&["--fee-limit", "env:TRON_UTILS_TEST_FEE_LIMIT"]).unwrap();
+        assert_eq!(args.fee_limit, 250_000_000);
 
-fn bs58_check_decode(address: &str) -> Result<Vec<u8>> {
-    let decoded = bs58::decode(address)
-        .into_vec()
-        .context("Invalid base58 address")?;
+        let unset = parse(&["--fee-limit", "env:TRON_UTILS_TEST_UNSET"])
+            .err()
+            .unwrap();
+        assert!(unset
+            .to_string()
+            .contains("environment variable TRON_UTILS_TEST_UNSET is not set"));
+        // Values from the environment are validated like those on the command line
+        let args = [
+            "tron-utils",
+            "set-permissions",
+            "--rpc-url",
+            "http://localhost",
+        ];
+        let invalid = Cli::try_parse_from(args.iter().chain(&[
+            "--owners",
+            MAINNET_USDT,
+            "--threshold",
+            "env:TRON_UTILS_TEST_THRESHOLD",
+        ]));
+        assert!(invalid.err().unwrap().to_string().contains("invalid digit"));
+    }
 
-    if decoded.len() < 4 {
-        return Err(anyhow!("Address too short"));
+    #[test]
+    fn tx_ids_outside_64_bits_are_rejected_clearly() {
+        assert_eq!(parse_tx_id("18446744073709551615"), Ok(u64::MAX));
+        assert_eq!(
+            parse_tx_id("18446744073709551616").unwrap_err(),
+            "transaction IDs above 18446744073709551615 (2^64-1) are not supported; the multisig \
+             counts them up from 0"
+        );
+        for invalid in ["", "-1", "0x10", "one"] {
+            assert_eq!(
+                parse_tx_id(invalid).unwrap_err(),
+                "expected a decimal transaction ID, e.g. 0"
+            );
+        }
+        let err = Cli::try_parse_from([
+            "tron-utils",
+            "transaction",
+            "--rpc-url",
+            "http://localhost",
+            "--contract",
+            MAINNET_USDT,
+            "--tx-id",
+            "1e3",
+        ])
+        .err()
+        .unwrap();
+        assert!(err
+            .to_string()
+            .contains("invalid value '1e3' for '--tx-id <TX_ID>'"));
     }
 
-    let data = &decoded[..decoded.len() - 4];
-    let checksum = &decoded[decoded.len() - 4..];
+    #[test]
+    fn abi_file_keeps_only_the_abi() {
+        let artifact = r#"{
+            "abi": [{ "type": "function", "name": "threshold", "inputs": [] }],
+            "bytecode": { "object": "0x6080" },
+            "ast": { "nodes": [{ "id": 1, "src": "0:10:0" }] }
+        }"#;
+        let AbiFile(json) = serde_json::from_str(artifact).unwrap();
+        assert_eq!(json.as_object().unwrap().len(), 1);
+        assert_eq!(json["abi"][0]["name"], "threshold");
 
-    // Verify checksum
-    let hash1 = sha256(data);
-    let hash2 = sha256(&hash1);
-    if &hash2[0..4] != checksum {
-        return Err(anyhow!("Invalid checksum"));
+        let AbiFile(plain) = serde_json::from_str(r#"[{ "type": "event" }]"#).unwrap();
+        assert_eq!(plain[0]["type"], "event");
+        assert!(serde_json::from_str::<AbiFile>("42").is_err());
     }
 
-    Ok(data.to_vec())
-}
+    #[test]
+    fn explorer_link_uses_template_or_network_default() {
+        let tx = ExplorerLink::Transaction("ab12");
+        let address = ExplorerLink::Address(MAINNET_USDT);
+        assert_eq!(
+            explorer_link(None, "https://api.trongrid.io", tx).as_deref(),
+            Some("https://tronscan.org/#/transaction/ab12")
+        );
+        assert_eq!(
+            explorer_link(None, "https://nile.trongrid.io", address).unwrap(),
+            format!("https://nile.tronscan.org/#/address/{}", MAINNET_USDT)
+        );
+        assert_eq!(explorer_link(None, "http://127.0.0.1:8090", tx), None);
 
-fn sha256(data: &[u8]) -> Vec<u8> {
-    use sha2::{Digest as Sha2Digest, Sha256};
-    let mut hasher = Sha256::new();
-    hasher.update(data);
-    hasher.finalize().to_vec()
-}
+        let template = Some("https://explorer.local/tx/{txid}");
+        assert_eq!(
+            explorer_link(template, "http://127.0.0.1:8090", tx).as_deref(),
+            Some("https://explorer.local/tx/ab12")
+        );
+        assert_eq!(
+            explorer_link(template, "https://api.trongrid.io", address),
+            None
+        );
+    }
 
-fn encode_constructor_params(usdt: &str, owners: &[&str], threshold: u64) -> Result<String> {
-    // ABI encode: (address _usdt, address[] _owners, uint256 _threshold)
+    #[test]
+    fn deployment_diff_lists_changed_fields() {
+        let plan = DeployPlan::from_manifest(
+            sample_manifest(),
+            std::path::Path::new(""),
+            TRON_ADDRESS_PREFIX,
+        )
+        .unwrap();
+        let mut info = MultisigInfo {
+            usdt: plan.usdt.clone(),
+            owners: plan.owners.iter().rev().cloned().collect(),
+            threshold: 2,
+        };
+        assert!(deployment_diff(&plan, &info, TRON_ADDRESS_PREFIX)
+            .unwrap()
+            .is_empty());
 
-    // Convert USDT address to hex (without 41 prefix for ABI encoding)
-    let usdt_hex = tron_address_to_hex(usdt)?;
-    let usdt_addr = &usdt_hex[2..]; // Remove 41 prefix
+        info.threshold = 1;
+        info.owners[0] = MAINNET_USDT.to_string();
+        assert_eq!(
+            deployment_diff(&plan, &info, TRON_ADDRESS_PREFIX).unwrap(),
+            vec![
+                "threshold: requested 2, on-chain 1".to_string(),
+                format!("owner {}: requested, not on-chain", plan.owners[1]),
+                format!("owner {}: on-chain, not requested", MAINNET_USDT),
+            ]
+        );
+    }
 
-    // Encode _usdt (address) - pad to 32 bytes
-    let usdt_param = format!("{:0>64}", usdt_addr);
+    #[test]
+    fn deploy_plan_resolves_manifest() {
+        let plan = DeployPlan::from_manifest(
+            sample_manifest(),
+            std::path::Path::new("deploy"),
+            TRON_ADDRESS_PREFIX,
+        )
+        .unwrap();
 
-    // Encode _threshold
-    let threshold_param = format!("{:0>64x}", threshold);
+        assert_eq!(plan.rpc_url, "https://nile.trongrid.io");
+        assert_eq!(plan.fee_limit, DEFAULT_FEE_LIMIT_SUN);
+        assert_eq!(
+            plan.contract_json,
+            PathBuf::from("deploy/out/USDTMultisig.json")
+        );
+        plan.validate(TRON_ADDRESS_PREFIX).unwrap();
+    }
 
-    // Encode owners array
-    let owners_len = format!("{:0>64x}", owners.len());
-    let mut owners_data = String::new();
-    for owner in owners {
-        let owner_hex = tron_address_to_hex(owner)?;
-        let owner_addr = &owner_hex[2..]; // Remove 41 prefix
-        owners_data.push_str(&format!("{:0>64}", owner_addr));
-    }
-
-    // ABI encoding for constructor: (address _usdt, address[] _owners, uint256 _threshold)
-    // - position 0: address _usdt (32 bytes)
-    // - position 1: offset to _owners array (32 bytes) = 96 (0x60)
-    // - position 2: uint256 _threshold (32 bytes)
-    // - position 3+: array data (length + elements)
-
-    Ok(format!(
-        "{}{}{}{}{}",
-        usdt_param,                     // address _usdt
-        format!("{:0>64x}", 96),        // offset to owners array
-        threshold_param,                // uint256 _threshold
-        owners_len,                     // array length
-        owners_data                     // array elements
-    ))
-}
+    #[test]
+    fn emitted_deploy_script_reproduces_the_plan() {
+        let mut plan = DeployPlan::from_manifest(
+            sample_manifest(),
+            std::path::Path::new("deploy"),
+            TRON_ADDRESS_PREFIX,
+        )
+        .unwrap();
+        plan.fee_limit = 2_000_000_000;
+        let Cli {
+            command: Commands::Deploy(args),
+            ..
+        } = Cli::try_parse_from(["tron-utils", "deploy", "--manifest", "deploy.toml"]).unwrap()
+        else {
+            unreachable!()
+        };
+        let script = deploy_script(&plan, &args, "00", "ab");
+        assert!(script.starts_with("#!/bin/sh\n"));
+        assert!(script.contains("# Constructor parameters: 00\n"));
+
+        // Run the command line back through the parser
+        let command = &script[script.find("\ntron-utils deploy").unwrap()..];
+        let words: Vec<String> = command
+            .replace("\\\n", " ")
+            .split_whitespace()
+            .map(|word| word.replace("\"$TRON_PRIVATE_KEY\"", TEST_KEY))
+            .collect();
+        let Cli {
+            command: Commands::Deploy(replayed),
+            ..
+        } = Cli::try_parse_from(&words).unwrap()
+        else {
+            unreachable!()
+        };
+        let replayed = DeployPlan::from_args(&replayed, TRON_ADDRESS_PREFIX).unwrap();
+        assert_eq!(replayed.rpc_url, plan.rpc_url);
+        assert_eq!(replayed.owners, plan.owners);
+        assert_eq!((replayed.threshold, replayed.fee_limit), (2, 2_000_000_000));
+        assert_eq!(replayed.contract_json, plan.contract_json);
+
+        assert_eq!(
+            shell_quote("https://nile.trongrid.io"),
+            "https://nile.trongrid.io"
+        );
+        assert_eq!(shell_quote("my dir/it's.json"), "'my dir/it'\\''s.json'");
+    }
 
-fn sign_transaction(tx_id: &str, private_key: &str) -> Result<String> {
-    let key_hex = private_key.trim_start_matches("0x");
-    let key_bytes = hex::decode(key_hex).context("Invalid private key hex")?;
+    #[test]
+    fn deploy_plan_from_constructor_hex_swaps_only_the_threshold() {
+        let owners = ["TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b", MAINNET_USDT];
+        let captured =
+            encode_constructor_params(MAINNET_USDT, &owners, 2, TRON_ADDRESS_PREFIX).unwrap();
+        let plan_for = |flags: &[&str]| {
+            let Cli {
+                command: Commands::Deploy(args),
+                ..
+            } = Cli::try_parse_from(
+                [
+                    "tron-utils",
+                    "deploy",
+                    "--rpc-url",
+                    "http://node",
+                    "--constructor-hex",
+                ]
+                .iter()
+                .chain([&captured.as_str()])
+                .chain(flags),
+            )
+            .unwrap()
+            else {
+                unreachable!()
+            };
+            DeployPlan::from_args(&args, TRON_ADDRESS_PREFIX).unwrap()
+        };
 
-    let tx_id_bytes = hex::decode(tx_id).context("Invalid tx_id hex")?;
+        let plan = plan_for(&[]);
+        assert_eq!(plan.constructor_params.as_deref(), Some(captured.as_str()));
+        assert_eq!((plan.usdt.as_str(), plan.threshold), (MAINNET_USDT, 2));
+        assert_eq!(plan.owners, owners);
 
-    let secp = Secp256k1::new();
-    let secret_key = SecretKey::from_slice(&key_bytes).context("Invalid private key")?;
-    let message = Message::from_digest_slice(&tx_id_bytes).context("Invalid message")?;
+        let plan = plan_for(&["--threshold", "1"]);
+        assert_eq!(plan.threshold, 1);
+        assert_eq!(
+            plan.constructor_params.unwrap(),
+            encode_constructor_params(MAINNET_USDT, &owners, 1, TRON_ADDRESS_PREFIX).unwrap()
+        );
+    }
 
-    let sig = secp.sign_ecdsa_recoverable(&message, &secret_key);
-    let (recovery_id, sig_bytes) = sig.serialize_compact();
+    #[test]
+    fn deploy_plan_rejects_duplicate_owners_and_bad_threshold() {
+        let mut manifest = sample_manifest();
+        manifest.owners[1] = manifest.owners[0].clone();
+        let plan =
+            DeployPlan::from_manifest(manifest, std::path::Path::new(""), TRON_ADDRESS_PREFIX)
+                .unwrap();
+        assert_eq!(
+            plan.validate(TRON_ADDRESS_PREFIX).unwrap_err().to_string(),
+            "Duplicate owner: TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b"
+        );
 
-    // TRON signature format: r (32 bytes) + s (32 bytes) + v (1 byte)
-    let mut signature = sig_bytes.to_vec();
-    signature.push(recovery_id.to_i32() as u8);
+        let mut manifest = sample_manifest();
+        manifest.threshold = 3;
+        let plan =
+            DeployPlan::from_manifest(manifest, std::path::Path::new(""), TRON_ADDRESS_PREFIX)
+                .unwrap();
+        assert!(plan.validate(TRON_ADDRESS_PREFIX).is_err());
 
-    Ok(hex::encode(signature))
-}
+        let mut manifest = sample_manifest();
+        manifest.owners[1] = "TZQ9596PFNVSh3tEsypax47Hdff4DKLkmj".into();
+        let plan =
+            DeployPlan::from_manifest(manifest, std::path::Path::new(""), TRON_ADDRESS_PREFIX)
+                .unwrap();
+        assert!(
+            format!("{:#}", plan.validate(TRON_ADDRESS_PREFIX).unwrap_err())
+                .ends_with("expected 0x41 prefix, got 0x42")
+        );
+    }
 
+    #[test]
+    fn deploy_manifest_rejects_unknown_keys() {
+        let result = toml::from_str::<DeployManifest>(
+            r#"
+            network = "nile"
+            usdt = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t"
+            owners = []
+            treshold = 1
+            "#,
+        );
+        assert!(result.is_err());
+    }
 
-fn decode_hex_message(hex_msg: &str) -> String {
-    if let Ok(bytes) = hex::decode(hex_msg) {
-        if let Ok(s) = String::from_utf8(bytes) {
-            return s;
+    #[test]
+    fn mnemonic_signers_derive_the_selected_key() {
+        const ABANDON: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let signer = |flags: &[&str]| -> Result<String> {
+            let args = [
+                "tron-utils",
+                "approve",
+                "--rpc-url",
+                "http://localhost",
+                "--contract",
+                MAINNET_USDT,
+                "--tx-id",
+                "1",
+            ];
+            match Cli::try_parse_from(args.iter().chain(flags))?.command {
+                Commands::Approve { signer, .. } => signer.resolve_signing_key(TRON_ADDRESS_PREFIX),
+                _ => unreachable!("parsed an approve command"),
+            }
+        };
+        let key = |flags: &[&str]| -> String { signer(flags).unwrap() };
+
+        assert_eq!(
+            key(&["--mnemonic", ABANDON]),
+            "b5a4cea271ff424d7c31dc12a3e43e401df7a40d7412a15750f3f0b6b5449a28"
+        );
+        let third = "5b7bff42fdea332c127c455e5a15d691a562773677de88e8256fc8d19a1b0337";
+        assert_eq!(key(&["--mnemonic", ABANDON, "--index", "3"]), third);
+        assert_eq!(
+            key(&["--mnemonic", ABANDON, "--path", "m/44'/195'/0'/0/3"]),
+            third
+        );
+        let Cli {
+            command:
+                Commands::FromMnemonic {
+                    mnemonic,
+                    path,
+                    account,
+                    index,
+                },
+            ..
+        } = Cli::try_parse_from([
+            "tron-utils",
+            "from-mnemonic",
+            "--mnemonic",
+            ABANDON,
+            "--index",
+            "3",
+        ])
+        .unwrap()
+        else {
+            unreachable!()
+        };
+        let (path, private_key) = mnemonic_private_key(&mnemonic, &path, account, index).unwrap();
+        assert_eq!(
+            (path.to_string().as_str(), private_key.as_str()),
+            ("m/44'/195'/0'/0/3", third)
+        );
+        // A mistyped word fails before from-mnemonic can show any address
+        let mistyped = ABANDON.replacen("abandon", "abandom", 1);
+        let err = mnemonic_private_key(&mistyped, &path, None, None).unwrap_err();
+        assert_eq!(error_code(&err), EXIT_INVALID_INPUT);
+        assert!(
+            err.to_string()
+                .contains("(\"abandom\") is not in the BIP39"),
+            "{}",
+            err
+        );
+
+        for conflicting in [
+            &["--mnemonic", ABANDON, "--path", "m/0", "--index", "3"][..],
+            &["--mnemonic", ABANDON, "--private-key", TEST_KEY],
+            &["--index", "3", "--private-key", TEST_KEY],
+            &["--path", "m/0", "--private-key", TEST_KEY],
+        ] {
+            assert!(signer(conflicting).is_err(), "accepted {:?}", conflicting);
         }
     }
-    hex_msg.to_string()
-}
 
-fn get_contract_abi() -> &'static str {
-    r#"[{"inputs":[{"internalType":"address","name":"_usdt","type":"address"},{"internalType":"address[]","name":"_owners","type":"address[]"},{"internalType":"uint256","name":"_threshold","type":"uint256"}],"stateMutability":"nonpayable","type":"constructor"},{"inputs":[],"name":"usdt","outputs":[{"internalType":"contract IERC20","name":"","type":"address"}],"stateMutability":"view","type":"function"},{"inputs":[],"name":"threshold","outputs":[{"internalType":"uint256","name":"","type":"uint256"}],"stateMutability":"view","type":"function"},{"inputs":[],"name":"getOwners","outputs":[{"internalType":"address[]","name":"","type":"address[]"}],"stateMutability":"view","type":"function"},{"inputs":[],"name":"getOwnerCount","outputs":[{"internalType":"uint256","name":"","type":"uint256"}],"stateMutability":"view","type":"function"},{"inputs":[],"name":"getBalance","outputs":[{"internalType":"uint256","name":"","type":"uint256"}],"stateMutability":"view","type":"function"},{"inputs":[],"name":"getTransactionCount","outputs":[{"internalType":"uint256","name":"","type":"uint256"}],"stateMutability":"view","type":"function"},{"inputs":[{"internalType":"uint256","name":"_txId","type":"uint256"}],"name":"getTransaction","outputs":[{"internalType":"address","name":"to","type":"address"},{"internalType":"uint256","name":"amount","type":"uint256"},{"internalType":"bool","name":"executed","type":"bool"},{"internalType":"uint256","name":"approvalCount","type":"uint256"}],"stateMutability":"view","type":"function"},{"inputs":[{"internalType":"uint256","name":"_txId","type":"uint256"},{"internalType":"address","name":"_owner","type":"address"}],"name":"isApproved","outputs":[{"internalType":"bool","name":"","type":"bool"}],"stateMutability":"view","type":"function"},{"inputs":[{"internalType":"address","name":"","type":"address"}],"name":"isOwner","outputs":[{"internalType":"bool","name":"","type":"bool"}],"stateMutability":"view","type":"function"},{"inputs":[{"internalType":"address","name":"_to","type":"address"},{"internalType":"uint256","name":"_amount","type":"uint256"}],"name":"submitTransaction","outputs":[{"internalType":"uint256","name":"txId","type":"uint256"}],"stateMutability":"nonpayable","type":"function"},{"inputs":[{"internalType":"uint256","name":"_txId","type":"uint256"}],"name":"approveTransaction","outputs":[],"stateMutability":"nonpayable","type":"function"},{"inputs":[{"internalType":"uint256","name":"_txId","type":"uint256"}],"name":"revokeApproval","outputs":[],"stateMutability":"nonpayable","type":"function"}]"#
+    #[test]
+    fn private_keys_load_from_env_or_file() {
+        let signer = |flags: &[&str]| -> Result<String> {
+            let args = ["tron-utils", "approve", "--rpc-url", "http://localhost"];
+            let args = args
+                .iter()
+                .chain(&["--contract", MAINNET_USDT, "--tx-id", "1"]);
+            match Cli::try_parse_from(args.chain(flags))?.command {
+                Commands::Approve { signer, .. } => signer.resolve_signing_key(TRON_ADDRESS_PREFIX),
+                _ => unreachable!("parsed an approve command"),
+            }
+        };
+
+        std::env::set_var("TRON_UTILS_TEST_KEY", format!("0x{}\n", TEST_KEY));
+        assert_eq!(
+            signer(&["--private-key-env", "TRON_UTILS_TEST_KEY"]).unwrap(),
+            TEST_KEY
+        );
+        assert!(signer(&["--private-key-env", "TRON_UTILS_TEST_UNSET_KEY"]).is_err());
+
+        let dir = TestDir::new("key-file");
+        let path = dir.join("key");
+        fs::write(&path, format!("  0x{}\r\n", TEST_KEY)).unwrap();
+        let file = path.to_str().unwrap();
+        assert_eq!(signer(&["--private-key-file", file]).unwrap(), TEST_KEY);
+        fs::write(&path, "not-a-key\n").unwrap();
+        assert!(signer(&["--private-key-file", file]).is_err());
+        fs::remove_file(&path).unwrap();
+        assert!(signer(&["--private-key-file", file]).is_err());
+
+        for conflicting in [
+            &[
+                "--private-key-env",
+                "TRON_UTILS_TEST_KEY",
+                "--private-key",
+                TEST_KEY,
+            ][..],
+            &[
+                "--private-key-file",
+                file,
+                "--private-key-env",
+                "TRON_UTILS_TEST_KEY",
+            ],
+            &["--private-key-file", file, "--mnemonic", "abandon about"],
+        ] {
+            assert!(signer(conflicting).is_err(), "accepted {:?}", conflicting);
+        }
+    }
+
+    #[test]
+    fn generated_keystores_sign() {
+        let out = test_output();
+        let dir = TestDir::new("keystore");
+        let path = dir.join("keystore.json");
+        std::env::set_var(KEYSTORE_PASSPHRASE_ENV, "correct horse battery");
+        let generated = generate_private_key(
+            &out,
+            &KeySource::Random,
+            KeyDestination::Keystore(&path),
+            false,
+            TRON_ADDRESS_PREFIX,
+        )
+        .unwrap();
+
+        let Cli {
+            command: Commands::Sign { signer, .. },
+            ..
+        } = Cli::try_parse_from([
+            "tron-utils",
+            "sign",
+            "--tx-json",
+            "tx.json",
+            "--keystore",
+            path.to_str().unwrap(),
+        ])
+        .unwrap()
+        else {
+            unreachable!("parsed a sign command")
+        };
+        let tx_id = hex::encode(keccak256(b"transaction"));
+        let signature = signer
+            .signer(TRON_ADDRESS_PREFIX)
+            .unwrap()
+            .sign(&tx_id, V_OFFSET_TRON)
+            .unwrap();
+        verify_signature(
+            &tx_id,
+            &signature,
+            generated["address"].as_str().unwrap(),
+            TRON_ADDRESS_PREFIX,
+        )
+        .unwrap();
+
+        std::env::set_var(KEYSTORE_PASSPHRASE_ENV, "wrong horse battery");
+        let err = signer.resolve_signing_key(TRON_ADDRESS_PREFIX).unwrap_err();
+        assert_eq!(error_code(&err), EXIT_INVALID_INPUT);
+    }
+
+    #[test]
+    fn keystore_passphrases_change_in_place() {
+        let out = test_output();
+        let dir = TestDir::new("rekey");
+        let path = dir.join("key.json");
+        let key = secp256k1::SecretKey::from_slice(&hex::decode(TEST_KEY).unwrap()).unwrap();
+        let nonces = KeystoreNonces {
+            salt: [1; 32],
+            iv: [2; 16],
+            id: [3; 16],
+        };
+        let cheap = tron_utils::kdf::ScryptParams {
+            n: 1024,
+            r: 8,
+            p: 1,
+        };
+        let keystore =
+            encrypt_key(&key, "old passphrase", cheap, &nonces, TRON_ADDRESS_PREFIX).unwrap();
+        fs::write(&path, keystore.to_string()).unwrap();
+
+        let changed = change_keystore_passphrase(
+            &out,
+            &path,
+            "old passphrase",
+            "new passphrase",
+            TRON_ADDRESS_PREFIX,
+        )
+        .unwrap();
+        assert_eq!(changed["address"], "TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b");
+        let json = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            decrypt_key(&json, "new passphrase", TRON_ADDRESS_PREFIX).unwrap(),
+            key
+        );
+        assert!(decrypt_key(&json, "old passphrase", TRON_ADDRESS_PREFIX).is_err());
+        let rekeyed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_ne!(rekeyed["crypto"]["kdfparams"]["salt"], hex::encode([1; 32]));
+        assert_ne!(
+            rekeyed["crypto"]["cipherparams"]["iv"],
+            hex::encode([2; 16])
+        );
+        // Only the keystore is left: the temporary file was renamed over it
+        assert_eq!(fs::read_dir(&dir.0).unwrap().count(), 1);
+
+        let err = change_keystore_passphrase(
+            &out,
+            &path,
+            "old passphrase",
+            "newer passphrase",
+            TRON_ADDRESS_PREFIX,
+        )
+        .unwrap_err();
+        assert_eq!(error_code(&err), EXIT_INVALID_INPUT);
+        assert_eq!(fs::read_to_string(&path).unwrap(), json);
+        assert!(change_keystore_passphrase(
+            &out,
+            &path,
+            "new passphrase",
+            "new passphrase",
+            TRON_ADDRESS_PREFIX
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn approvals_take_several_keys() {
+        let second = "b5a4cea271ff424d7c31dc12a3e43e401df7a40d7412a15750f3f0b6b5449a28";
+        let args = [
+            "tron-utils",
+            "approve",
+            "--rpc-url",
+            "http://localhost",
+            "--contract",
+            MAINNET_USDT,
+            "--tx-id",
+            "1",
+            "--private-key",
+            TEST_KEY,
+            "--private-key",
+            second,
+        ];
+        let Cli {
+            command: Commands::Approve { signer, .. },
+            ..
+        } = Cli::try_parse_from(args).unwrap()
+        else {
+            unreachable!()
+        };
+        let addresses: Vec<String> = signer
+            .signers(TRON_ADDRESS_PREFIX)
+            .unwrap()
+            .iter()
+            .map(|signer| signer.address(TRON_ADDRESS_PREFIX).unwrap())
+            .collect();
+        assert_eq!(addresses[0], "TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b");
+        assert_eq!(addresses.len(), 2);
+        // Commands that sign once refuse to pick one of them
+        assert!(signer.resolve_signing_key(TRON_ADDRESS_PREFIX).is_err());
+
+        let dir = TestDir::new("keys-file");
+        let path = dir.join("keys.txt");
+        fs::write(
+            &path,
+            format!("# owners\r\n{}\n\n  0x{}  \n", TEST_KEY, second),
+        )
+        .unwrap();
+        assert_eq!(
+            read_keys_file(&path).unwrap(),
+            [TEST_KEY.to_string(), format!("0x{}", second)]
+        );
+        fs::write(&path, format!("{}\nnot-a-key\n", TEST_KEY)).unwrap();
+        assert!(read_keys_file(&path)
+            .unwrap_err()
+            .to_string()
+            .contains("key 2"));
+        fs::write(&path, "# none yet\n").unwrap();
+        assert!(read_keys_file(&path).is_err());
+    }
+
+    #[test]
+    fn contract_function_names_are_command_aliases() {
+        let cli = <Cli as clap::CommandFactory>::command();
+        let name = |alias| cli.find_subcommand(alias).map(|c| c.get_name());
+        assert_eq!(name("submit-transaction"), Some("submit"));
+        assert_eq!(name("approve-transaction"), Some("approve"));
+        assert_eq!(name("revoke-approval"), Some("revoke"));
+        assert_eq!(name("get-transaction"), Some("transaction"));
+        assert_eq!(name("get-owners"), Some("owners"));
+    }
 }
@@ -0,0 +1,439 @@
+//! Transaction and message signing.
+
+use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+use secp256k1::{Message, PublicKey, Secp256k1, SecretKey};
+
+use crate::address::{parse_address, parse_private_key, public_key_to_tron_address};
+use crate::error::{Result, ResultExt, TronError};
+use crate::hash::keccak256;
+
+/// `v` offset for the raw recovery id (0/1). This is what TRON nodes
+/// (`/wallet/broadcasttransaction`) and TronWeb expect.
+pub const V_OFFSET_TRON: u8 = 0;
+/// `v` offset for Ethereum-style `27 + recid` (27/28), expected by tools
+/// built around `ecrecover`, such as ethers.js and Solidity signature helpers.
+pub const V_OFFSET_ETHEREUM: u8 = 27;
+
+/// Signs a transaction ID, returning the hex `r || s || v` signature with
+/// TRON's 0-based `v`.
+pub fn sign_transaction(tx_id: &str, private_key: &str) -> Result<String> {
+    sign_transaction_with_v_offset(tx_id, private_key, V_OFFSET_TRON)
+}
+
+/// Like [`sign_transaction`], with `v = v_offset + recid` where `v_offset` is
+/// [`V_OFFSET_TRON`] or [`V_OFFSET_ETHEREUM`].
+pub fn sign_transaction_with_v_offset(
+    tx_id: &str,
+    private_key: &str,
+    v_offset: u8,
+) -> Result<String> {
+    check_v_offset(v_offset)?;
+    let secret_key = parse_private_key(private_key)?;
+    let tx_id_bytes = hex::decode(tx_id).or_fail(TronError::InvalidInput, "Invalid tx_id hex")?;
+
+    let secp = Secp256k1::new();
+    let message = Message::from_digest_slice(&tx_id_bytes)
+        .or_fail(TronError::InvalidInput, "Invalid message")?;
+
+    let sig = secp.sign_ecdsa_recoverable(&message, &secret_key);
+    let (recovery_id, sig_bytes) = low_s_signature(&secp, &message, &secret_key, &sig)?;
+
+    // TRON signature format: r (32 bytes) + s (32 bytes) + v (1 byte)
+    let mut signature = sig_bytes.to_vec();
+    signature.push(v_offset + recovery_id.to_i32() as u8);
+
+    Ok(hex::encode(signature))
+}
+
+/// Formats an `r || s` signature of `tx_id` made elsewhere (e.g. on a
+/// PKCS#11 token, which returns no recovery id) exactly like
+/// [`sign_transaction_with_v_offset`]: `s` is normalized to low-S and the
+/// recovery id is the one that recovers `public_key`.
+pub fn signature_from_raw(
+    tx_id: &str,
+    raw: &[u8],
+    public_key: &PublicKey,
+    v_offset: u8,
+) -> Result<String> {
+    check_v_offset(v_offset)?;
+    let tx_id_bytes = hex::decode(tx_id).or_fail(TronError::InvalidInput, "Invalid tx_id hex")?;
+    let message = Message::from_digest_slice(&tx_id_bytes)
+        .or_fail(TronError::InvalidInput, "Invalid message")?;
+    let mut standard = secp256k1::ecdsa::Signature::from_compact(raw)
+        .or_fail(TronError::InvalidSignature, "Invalid r || s signature")?;
+    standard.normalize_s();
+    let compact = standard.serialize_compact();
+
+    let secp = Secp256k1::new();
+    let recovery_id = [0, 1]
+        .into_iter()
+        .map(RecoveryId::from_i32)
+        .filter_map(Result::ok)
+        .find(|&id| {
+            RecoverableSignature::from_compact(&compact, id)
+                .and_then(|sig| secp.recover_ecdsa(&message, &sig))
+                .is_ok_and(|recovered| recovered == *public_key)
+        })
+        .ok_or_else(|| {
+            TronError::InvalidSignature("Signature does not recover to the signing key".into())
+        })?;
+
+    let mut signature = compact.to_vec();
+    signature.push(v_offset + recovery_id.to_i32() as u8);
+    Ok(hex::encode(signature))
+}
+
+/// The TRON address (with `prefix`) whose key made `signature` (hex
+/// `r || s || v`, with either `v` encoding) over `tx_id`.
+pub fn recover_signer(tx_id: &str, signature: &str, prefix: u8) -> Result<String> {
+    let tx_id_bytes = hex::decode(tx_id).or_fail(TronError::InvalidInput, "Invalid tx_id hex")?;
+    let message = Message::from_digest_slice(&tx_id_bytes)
+        .or_fail(TronError::InvalidInput, "Invalid message")?;
+    let signature = hex::decode(signature.trim_start_matches("0x"))
+        .or_fail(TronError::InvalidSignature, "Invalid signature hex")?;
+    if signature.len() != 65 {
+        return Err(TronError::InvalidSignature(format!(
+            "Signature must be 65 bytes, got {}",
+            signature.len()
+        )));
+    }
+    let (compact, v) = (&signature[..64], &signature[64]);
+    let recovery_id = match *v {
+        0 | 1 => *v,
+        27 | 28 => v - V_OFFSET_ETHEREUM,
+        other => {
+            return Err(TronError::InvalidSignature(format!(
+                "Invalid signature recovery byte {}",
+                other
+            )))
+        }
+    };
+    let sig = RecoveryId::from_i32(recovery_id.into())
+        .and_then(|id| RecoverableSignature::from_compact(compact, id))
+        .or_fail(TronError::InvalidSignature, "Invalid signature")?;
+    let public_key = Secp256k1::new().recover_ecdsa(&message, &sig).or_fail(
+        TronError::InvalidSignature,
+        "Signature does not recover to any key",
+    )?;
+    Ok(public_key_to_tron_address(&public_key, prefix))
+}
+
+/// Checks that `signature` over `tx_id` was made by the key of `signer` (an
+/// address in any format, prefixed forms with `prefix`), as a node will
+/// before accepting it.
+pub fn verify_signature(tx_id: &str, signature: &str, signer: &str, prefix: u8) -> Result<()> {
+    let recovered = recover_signer(tx_id, signature, prefix)?;
+    if parse_address(&recovered, prefix)? != parse_address(signer, prefix)? {
+        return Err(TronError::InvalidSignature(format!(
+            "Signature recovers to {}, not the signer {}",
+            recovered, signer
+        )));
+    }
+    Ok(())
+}
+
+fn check_v_offset(v_offset: u8) -> Result<()> {
+    if v_offset != V_OFFSET_TRON && v_offset != V_OFFSET_ETHEREUM {
+        return Err(TronError::InvalidInput(format!(
+            "Unsupported v offset {} (expected 0 or 27)",
+            v_offset
+        )));
+    }
+    Ok(())
+}
+
+/// Half the secp256k1 curve order; `s` values above it are "high-S".
+const SECP256K1_HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// Returns the compact signature with `s` in the lower half of the curve
+/// order, since some nodes reject high-S signatures. libsecp256k1 already
+/// signs low-S, but negating `s` flips the recovery id, so the result is
+/// checked to still recover the signer either way.
+fn low_s_signature(
+    secp: &Secp256k1<secp256k1::All>,
+    message: &Message,
+    secret_key: &SecretKey,
+    sig: &RecoverableSignature,
+) -> Result<(RecoveryId, [u8; 64])> {
+    let (mut recovery_id, mut compact) = sig.serialize_compact();
+    if compact[32..] > SECP256K1_HALF_ORDER[..] {
+        let mut standard = sig.to_standard();
+        standard.normalize_s();
+        compact = standard.serialize_compact();
+        recovery_id = RecoveryId::from_i32(recovery_id.to_i32() ^ 1)
+            .or_fail(TronError::InvalidSignature, "Invalid recovery id")?;
+    }
+
+    let recovers = RecoverableSignature::from_compact(&compact, recovery_id)
+        .and_then(|normalized| secp.recover_ecdsa(message, &normalized));
+    if recovers.ok() != Some(secret_key.public_key(secp)) {
+        return Err(TronError::InvalidSignature(
+            "Signature does not recover to the signing key".into(),
+        ));
+    }
+    Ok((recovery_id, compact))
+}
+
+/// What TRON wallets put before a message they sign: this, the message's
+/// length in decimal, then the message, as in EIP-191 with TRON's name.
+pub const TRON_MESSAGE_PREFIX: &str = "\x19TRON Signed Message:\n";
+
+/// The keccak256 digest TronWeb's `signMessageV2` and TronLink sign for
+/// `message`. For 32-byte messages it is also what the older `signMessage`
+/// signed, as that always wrote the length as 32.
+pub fn message_digest(message: &[u8]) -> [u8; 32] {
+    let prefix = format!("{}{}", TRON_MESSAGE_PREFIX, message.len());
+    keccak256(&[prefix.as_bytes(), message].concat())
+}
+
+/// The keccak256 digest TronWeb's original `signMessage` (v1) signs for
+/// `message`: the length is always written as 32, whatever the message's
+/// actual length.
+pub fn legacy_message_digest(message: &[u8]) -> [u8; 32] {
+    keccak256(&[TRON_MESSAGE_PREFIX.as_bytes(), b"32", message].concat())
+}
+
+/// Signs `message` as a TRON wallet would, returning the hex `r || s || v`
+/// signature with `v = v_offset + recid` as in [`sign_transaction_with_v_offset`].
+pub fn sign_message(message: &[u8], private_key: &str, v_offset: u8) -> Result<String> {
+    sign_transaction_with_v_offset(&hex::encode(message_digest(message)), private_key, v_offset)
+}
+
+/// The TRON address whose key signed `message` (see [`message_digest`]),
+/// from a hex `r || s || v` signature with either `v` encoding.
+pub fn recover_message_signer(message: &[u8], signature: &str, prefix: u8) -> Result<String> {
+    recover_signer(&hex::encode(message_digest(message)), signature, prefix)
+}
+
+/// Checks that `signature` over `message` was made by the key of `signer`
+/// (an address in any format).
+pub fn verify_message_signature(
+    message: &[u8],
+    signature: &str,
+    signer: &str,
+    prefix: u8,
+) -> Result<()> {
+    verify_signature(
+        &hex::encode(message_digest(message)),
+        signature,
+        signer,
+        prefix,
+    )
+}
+
+/// Signs `transaction` and returns a copy with the `signature` array attached,
+/// ready to be posted verbatim to `/wallet/broadcasttransaction` (when
+/// `v_offset` is [`V_OFFSET_TRON`]).
+pub fn sign_transaction_json(
+    transaction: &serde_json::Value,
+    tx_id: &str,
+    private_key: &str,
+    v_offset: u8,
+) -> Result<serde_json::Value> {
+    let signature = sign_transaction_with_v_offset(tx_id, private_key, v_offset)?;
+
+    // Add signature to transaction
+    let mut signed_tx = transaction.clone();
+    signed_tx
+        .as_object_mut()
+        .ok_or_else(|| TronError::InvalidInput("Transaction is not an object".into()))?
+        .insert("signature".to_string(), serde_json::json!([signature]));
+    Ok(signed_tx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::{
+        private_key_to_tron_address, public_key_from_private_key, TRON_ADDRESS_PREFIX,
+    };
+    use crate::hash::sha256;
+
+    /// `CURVE_ORDER - s`, to build high-S signatures for testing.
+    fn negate_s(s: &[u8]) -> [u8; 32] {
+        let mut negated = [0u8; 32];
+        let mut borrow = 0i16;
+        for i in (0..32).rev() {
+            let mut diff = secp256k1::constants::CURVE_ORDER[i] as i16 - s[i] as i16 - borrow;
+            borrow = (diff < 0) as i16;
+            if diff < 0 {
+                diff += 256;
+            }
+            negated[i] = diff as u8;
+        }
+        negated
+    }
+
+    #[test]
+    fn sign_transaction_produces_low_s_recoverable_signatures() {
+        let private_key = "c88c165be5e6d8c58eca95747f8811aa956fa6227c9e0276543bc30d49252d76";
+        let secp = Secp256k1::new();
+        let expected = public_key_from_private_key(private_key).unwrap();
+
+        for i in 0u8..16 {
+            let tx_id = hex::encode(sha256(&[i]));
+            let signature = hex::decode(sign_transaction(&tx_id, private_key).unwrap()).unwrap();
+
+            assert!(signature[32..64] <= SECP256K1_HALF_ORDER[..]);
+            let recovery_id = RecoveryId::from_i32(signature[64] as i32).unwrap();
+            let sig = RecoverableSignature::from_compact(&signature[..64], recovery_id).unwrap();
+            let message = Message::from_digest_slice(&hex::decode(&tx_id).unwrap()).unwrap();
+            assert_eq!(secp.recover_ecdsa(&message, &sig).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn low_s_signature_normalizes_high_s_and_flips_recovery_id() {
+        let secp = Secp256k1::new();
+        let secret_key =
+            parse_private_key("c88c165be5e6d8c58eca95747f8811aa956fa6227c9e0276543bc30d49252d76")
+                .unwrap();
+        let message = Message::from_digest_slice(&sha256(b"tx")).unwrap();
+        let sig = secp.sign_ecdsa_recoverable(&message, &secret_key);
+        let (recovery_id, compact) = sig.serialize_compact();
+
+        let mut high = compact;
+        high[32..].copy_from_slice(&negate_s(&compact[32..]));
+        let flipped = RecoveryId::from_i32(recovery_id.to_i32() ^ 1).unwrap();
+        let high_sig = RecoverableSignature::from_compact(&high, flipped).unwrap();
+
+        let normalized = low_s_signature(&secp, &message, &secret_key, &high_sig).unwrap();
+        assert_eq!(normalized, (recovery_id, compact));
+    }
+
+    #[test]
+    fn v_offset_controls_recovery_byte_encoding() {
+        let private_key = "c88c165be5e6d8c58eca95747f8811aa956fa6227c9e0276543bc30d49252d76";
+        let tx_id = hex::encode(sha256(b"fixed transaction"));
+
+        let tron = hex::decode(sign_transaction(&tx_id, private_key).unwrap()).unwrap();
+        let ethereum = hex::decode(
+            sign_transaction_with_v_offset(&tx_id, private_key, V_OFFSET_ETHEREUM).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(tron[..64], ethereum[..64]);
+        assert!(tron[64] <= 1);
+        assert_eq!(ethereum[64], tron[64] + 27);
+        assert!(sign_transaction_with_v_offset(&tx_id, private_key, 1).is_err());
+    }
+
+    #[test]
+    fn recover_signer_accepts_both_v_encodings() {
+        let private_key = "c88c165be5e6d8c58eca95747f8811aa956fa6227c9e0276543bc30d49252d76";
+        let tx_id = hex::encode(sha256(b"fixed transaction"));
+        for v_offset in [V_OFFSET_TRON, V_OFFSET_ETHEREUM] {
+            let signature = sign_transaction_with_v_offset(&tx_id, private_key, v_offset).unwrap();
+            assert_eq!(
+                recover_signer(&tx_id, &signature, TRON_ADDRESS_PREFIX).unwrap(),
+                "TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b"
+            );
+        }
+
+        let other_tx = hex::encode(sha256(b"other transaction"));
+        let signature = sign_transaction(&tx_id, private_key).unwrap();
+        assert_ne!(
+            recover_signer(&other_tx, &signature, TRON_ADDRESS_PREFIX).unwrap(),
+            "TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b"
+        );
+        assert!(recover_signer(&tx_id, &signature[..128], TRON_ADDRESS_PREFIX).is_err());
+    }
+
+    #[test]
+    fn verify_signature_rejects_other_signers_and_tampering() {
+        let private_key = "c88c165be5e6d8c58eca95747f8811aa956fa6227c9e0276543bc30d49252d76";
+        let signer = "TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b";
+        let tx_id = hex::encode(sha256(b"fixed transaction"));
+        let signature = sign_transaction(&tx_id, private_key).unwrap();
+        verify_signature(&tx_id, &signature, signer, TRON_ADDRESS_PREFIX).unwrap();
+
+        let other = private_key_to_tron_address(&"11".repeat(32), TRON_ADDRESS_PREFIX).unwrap();
+        assert!(matches!(
+            verify_signature(&tx_id, &signature, &other, TRON_ADDRESS_PREFIX),
+            Err(TronError::InvalidSignature(_))
+        ));
+        // A flipped recovery byte recovers some other key, or none
+        let mut tampered = hex::decode(&signature).unwrap();
+        tampered[64] ^= 1;
+        assert!(
+            verify_signature(&tx_id, &hex::encode(tampered), signer, TRON_ADDRESS_PREFIX).is_err()
+        );
+    }
+
+    #[test]
+    fn messages_are_signed_over_the_tron_prefix() {
+        let private_key = "c88c165be5e6d8c58eca95747f8811aa956fa6227c9e0276543bc30d49252d76";
+        assert_eq!(
+            message_digest(b"hello"),
+            keccak256(b"\x19TRON Signed Message:\n5hello")
+        );
+        assert_eq!(
+            message_digest(&[0xab; 32]),
+            keccak256(&[&b"\x19TRON Signed Message:\n32"[..], &[0xab; 32]].concat())
+        );
+
+        let signature = sign_message(b"hello", private_key, V_OFFSET_ETHEREUM).unwrap();
+        assert!(matches!(hex::decode(&signature).unwrap()[64], 27 | 28));
+        assert_eq!(
+            recover_message_signer(b"hello", &format!("0x{}", signature), TRON_ADDRESS_PREFIX)
+                .unwrap(),
+            "TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b"
+        );
+        verify_message_signature(
+            b"hello",
+            &signature,
+            "TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b",
+            TRON_ADDRESS_PREFIX,
+        )
+        .unwrap();
+        assert!(matches!(
+            verify_message_signature(
+                b"hello!",
+                &signature,
+                "TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b",
+                TRON_ADDRESS_PREFIX
+            ),
+            Err(TronError::InvalidSignature(_))
+        ));
+        assert_eq!(
+            legacy_message_digest(b"hello"),
+            keccak256(b"\x19TRON Signed Message:\n32hello")
+        );
+        assert_eq!(
+            legacy_message_digest(&[0xab; 32]),
+            message_digest(&[0xab; 32])
+        );
+        // A transaction signature over the bare digest is not a message signature
+        let digest = hex::encode(keccak256(b"hello"));
+        let bare = sign_transaction(&digest, private_key).unwrap();
+        assert_ne!(
+            recover_message_signer(b"hello", &bare, TRON_ADDRESS_PREFIX).unwrap(),
+            "TLu74WiSAfdwCnawzF6EEXPYkgjSWDbK5b"
+        );
+    }
+
+    #[test]
+    fn signature_from_raw_matches_sign_transaction() {
+        let private_key = "c88c165be5e6d8c58eca95747f8811aa956fa6227c9e0276543bc30d49252d76";
+        let public_key = public_key_from_private_key(private_key).unwrap();
+        let tx_id = hex::encode(sha256(b"fixed transaction"));
+        let expected = sign_transaction(&tx_id, private_key).unwrap();
+        let signature = hex::decode(&expected).unwrap();
+
+        // A token may return either s; both must come out identical
+        let mut high_s = signature[..64].to_vec();
+        high_s[32..].copy_from_slice(&negate_s(&signature[32..64]));
+        for raw in [&signature[..64], &high_s[..]] {
+            assert_eq!(
+                signature_from_raw(&tx_id, raw, &public_key, V_OFFSET_TRON).unwrap(),
+                expected
+            );
+        }
+
+        let other = public_key_from_private_key(&"11".repeat(32)).unwrap();
+        assert!(signature_from_raw(&tx_id, &signature[..64], &other, V_OFFSET_TRON).is_err());
+    }
+}
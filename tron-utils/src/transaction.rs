@@ -0,0 +1,158 @@
+//! Editing a transaction's protobuf `raw_data` locally.
+//!
+//! The node builds `raw_data` and returns it both as JSON and as
+//! `raw_data_hex`; the txID is the SHA-256 of the latter. Only top-level
+//! fields are touched here, so nested messages (the contract) stay as the
+//! node serialized them.
+
+use crate::error::{Result, ResultExt, TronError};
+use crate::hash::sha256;
+
+/// `Transaction.raw.expiration` (int64, milliseconds since the epoch).
+const EXPIRATION_FIELD: u64 = 8;
+
+/// `Transaction.raw.timestamp` (int64, milliseconds since the epoch).
+const TIMESTAMP_FIELD: u64 = 14;
+
+/// How far past the head block time TRON accepts an `expiration`, used when
+/// the chain doesn't report its own limit.
+pub const MAX_EXPIRATION_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// The txID of a transaction: the hex SHA-256 of its `raw_data_hex`.
+pub fn transaction_id(raw_data_hex: &str) -> Result<String> {
+    let raw = hex::decode(raw_data_hex).or_fail(TronError::Decode, "Invalid raw_data_hex")?;
+    Ok(hex::encode(sha256(&raw)))
+}
+
+/// Returns `raw_data_hex` with `expiration` set to `expiration_ms`. The field
+/// is replaced in place, or inserted in field-number order if absent, so the
+/// bytes stay in the canonical order the node hashes.
+pub fn set_expiration(raw_data_hex: &str, expiration_ms: i64) -> Result<String> {
+    set_int64_field(raw_data_hex, EXPIRATION_FIELD, expiration_ms)
+}
+
+/// Returns `raw_data_hex` with `timestamp` set to `timestamp_ms`, placed as
+/// [`set_expiration`] places the expiration.
+pub fn set_timestamp(raw_data_hex: &str, timestamp_ms: i64) -> Result<String> {
+    set_int64_field(raw_data_hex, TIMESTAMP_FIELD, timestamp_ms)
+}
+
+fn set_int64_field(raw_data_hex: &str, field_number: u64, value: i64) -> Result<String> {
+    let raw = hex::decode(raw_data_hex).or_fail(TronError::Decode, "Invalid raw_data_hex")?;
+    let mut field = Vec::new();
+    encode_varint(field_number << 3, &mut field);
+    encode_varint(value as u64, &mut field);
+
+    let mut edited = Vec::with_capacity(raw.len() + field.len());
+    let mut pending = Some(field);
+    let mut at = 0;
+    while at < raw.len() {
+        let start = at;
+        let key = decode_varint(&raw, &mut at)?;
+        skip_value(&raw, &mut at, key & 7)?;
+        let number = key >> 3;
+        if number >= field_number {
+            if let Some(field) = pending.take() {
+                edited.extend_from_slice(&field);
+            }
+        }
+        if number != field_number {
+            edited.extend_from_slice(&raw[start..at]);
+        }
+    }
+    if let Some(field) = pending {
+        edited.extend_from_slice(&field);
+    }
+    Ok(hex::encode(edited))
+}
+
+fn encode_varint(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn decode_varint(bytes: &[u8], at: &mut usize) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes
+            .get(*at)
+            .ok_or_else(|| TronError::Decode("Truncated varint in raw_data".into()))?;
+        *at += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(TronError::Decode("Varint too long in raw_data".into()))
+}
+
+fn skip_value(bytes: &[u8], at: &mut usize, wire_type: u64) -> Result<()> {
+    let len = match wire_type {
+        0 => return decode_varint(bytes, at).map(|_| ()),
+        1 => 8,
+        2 => decode_varint(bytes, at)? as usize,
+        5 => 4,
+        other => {
+            return Err(TronError::Decode(format!(
+                "Unsupported protobuf wire type {} in raw_data",
+                other
+            )))
+        }
+    };
+    *at = at
+        .checked_add(len)
+        .filter(|&end| end <= bytes.len())
+        .ok_or_else(|| TronError::Decode("Truncated field in raw_data".into()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_expiration_replaces_or_inserts_in_field_order() {
+        // ref_block_bytes, ref_block_hash, expiration, contract, timestamp
+        let raw = "0a02abcd2208000102030405060740e8075a03010203709807";
+        let edited = set_expiration(raw, 1_700_000_000_000).unwrap();
+        assert_eq!(
+            edited,
+            "0a02abcd220800010203040506074080d095ffbc315a03010203709807"
+        );
+
+        let without = "0a02abcd5a03010203";
+        assert_eq!(
+            set_expiration(without, 300).unwrap(),
+            "0a02abcd40ac025a03010203"
+        );
+        assert_eq!(
+            set_expiration("0a02ab", 1).unwrap_err().to_string(),
+            "Truncated field in raw_data"
+        );
+    }
+
+    #[test]
+    fn set_timestamp_keeps_fee_limit_after_it() {
+        // ref_block_bytes, contract, timestamp, fee_limit
+        let raw = "0a02abcd5a03010203709807900180ade204";
+        assert_eq!(
+            set_timestamp(raw, 1000).unwrap(),
+            "0a02abcd5a0301020370e807900180ade204"
+        );
+        assert_eq!(
+            set_timestamp("0a02abcd900180ade204", 1).unwrap(),
+            "0a02abcd7001900180ade204"
+        );
+    }
+
+    #[test]
+    fn transaction_id_hashes_raw_data() {
+        assert_eq!(
+            transaction_id("").unwrap(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+}
@@ -0,0 +1,59 @@
+//! `wasm-bindgen` exports for use from JavaScript. Failures are thrown as JS
+//! `Error`s carrying the same message the CLI would print. Addresses use
+//! the standard TRON prefix, [`TRON_ADDRESS_PREFIX`].
+
+use wasm_bindgen::prelude::*;
+
+use crate::address::TRON_ADDRESS_PREFIX;
+use crate::error::TronError;
+use crate::{abi, address, signing};
+
+fn js_error(err: TronError) -> JsError {
+    JsError::new(&err.to_string())
+}
+
+/// TRON base58 address for a hex private key (with or without 0x prefix).
+#[wasm_bindgen(js_name = privateKeyToTronAddress)]
+pub fn private_key_to_tron_address(private_key: &str) -> Result<String, JsError> {
+    address::private_key_to_tron_address(private_key, TRON_ADDRESS_PREFIX).map_err(js_error)
+}
+
+/// Hex (`41...`) form of a TRON base58 address.
+#[wasm_bindgen(js_name = tronAddressToHex)]
+pub fn tron_address_to_hex(address: &str) -> Result<String, JsError> {
+    address::tron_address_to_hex(address, TRON_ADDRESS_PREFIX).map_err(js_error)
+}
+
+/// TRON base58 form of a hex (`41...`) address.
+#[wasm_bindgen(js_name = hexToTronAddress)]
+pub fn hex_to_tron_address(hex: &str) -> Result<String, JsError> {
+    address::hex_to_tron_address(hex, TRON_ADDRESS_PREFIX).map_err(js_error)
+}
+
+#[wasm_bindgen(js_name = bs58CheckEncode)]
+pub fn bs58_check_encode(data: &[u8]) -> String {
+    address::bs58_check_encode(data)
+}
+
+/// ABI-encoded USDTMultisig constructor arguments, as hex.
+#[wasm_bindgen(js_name = encodeConstructorParams)]
+pub fn encode_constructor_params(
+    usdt: &str,
+    owners: Vec<String>,
+    threshold: u64,
+) -> Result<String, JsError> {
+    let owners: Vec<&str> = owners.iter().map(String::as_str).collect();
+    abi::encode_constructor_params(usdt, &owners, threshold, TRON_ADDRESS_PREFIX).map_err(js_error)
+}
+
+/// 4-byte selector of a canonical function signature, as hex.
+#[wasm_bindgen(js_name = functionSelector)]
+pub fn function_selector(signature: &str) -> String {
+    hex::encode(abi::function_selector(signature))
+}
+
+/// Signs a transaction ID, returning the hex `r || s || v` signature.
+#[wasm_bindgen(js_name = signTransaction)]
+pub fn sign_transaction(tx_id: &str, private_key: &str) -> Result<String, JsError> {
+    signing::sign_transaction(tx_id, private_key).map_err(js_error)
+}
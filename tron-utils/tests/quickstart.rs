@@ -0,0 +1,277 @@
+//! End-to-end run against a local node: generate owner keys, fund them,
+//! deploy the multisig, submit and approve a transfer, and read it back.
+//!
+//! Needs the `integration` feature, a `tron-quickstart` node and the forge
+//! artifact:
+//!
+//! ```text
+//! docker run -d -p 9090:9090 --name tron trontools/quickstart
+//! forge build
+//! TRON_TEST_RPC=http://127.0.0.1:9090 cargo test --features integration --test quickstart
+//! ```
+//!
+//! Owners are funded from the node's first pre-funded account
+//! (`/admin/accounts-json`) unless `TRON_TEST_FUNDER_KEY` is set, and the
+//! artifact path can be overridden with `TRON_TEST_CONTRACT_JSON`. Without
+//! `TRON_TEST_RPC` the test passes without touching the network.
+#![cfg(feature = "integration")]
+
+use std::process::Command;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tron_utils::abi::{encode_params, AbiValue};
+use tron_utils::address::{private_key_to_tron_address, tron_address_to_hex, TRON_ADDRESS_PREFIX};
+use tron_utils::signing::{sign_transaction_json, V_OFFSET_TRON};
+
+/// TRX sent to each owner for bandwidth and energy.
+const FUNDING_SUN: u64 = 5_000 * 1_000_000;
+const CALL_FEE_LIMIT_SUN: u64 = 100_000_000;
+/// How long to wait for a broadcast transaction to be mined (blocks are ~3s).
+const MINING_TIMEOUT: Duration = Duration::from_secs(30);
+
+struct Node {
+    rpc: String,
+    http: reqwest::Client,
+}
+
+impl Node {
+    async fn get(&self, path: &str) -> Value {
+        let response = self.http.get(format!("{}{}", self.rpc, path)).send().await;
+        let response = response.unwrap_or_else(|e| panic!("GET {} failed: {}", path, e));
+        response
+            .json()
+            .await
+            .unwrap_or_else(|e| panic!("GET {} returned invalid JSON: {}", path, e))
+    }
+
+    async fn post(&self, path: &str, body: &Value) -> Value {
+        let request = self.http.post(format!("{}{}", self.rpc, path)).json(body);
+        let response = request
+            .send()
+            .await
+            .unwrap_or_else(|e| panic!("POST {} failed: {}", path, e));
+        response
+            .json()
+            .await
+            .unwrap_or_else(|e| panic!("POST {} returned invalid JSON: {}", path, e))
+    }
+
+    /// Polls until `tx_id` is in a block and returns its transaction info.
+    async fn wait_for(&self, tx_id: &str) -> Value {
+        let deadline = tokio::time::Instant::now() + MINING_TIMEOUT;
+        while tokio::time::Instant::now() < deadline {
+            let info = self
+                .post("/wallet/gettransactioninfobyid", &json!({ "value": tx_id }))
+                .await;
+            if info.get("blockNumber").is_some() {
+                return info;
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+        panic!(
+            "Transaction {} was not mined within {:?}",
+            tx_id, MINING_TIMEOUT
+        );
+    }
+
+    /// Signs and broadcasts `transaction`, then waits for it to be mined.
+    async fn send(&self, transaction: &Value, private_key: &str) -> Value {
+        let tx_id = transaction["txID"]
+            .as_str()
+            .unwrap_or_else(|| panic!("No txID in {}", transaction));
+        let signed = sign_transaction_json(transaction, tx_id, private_key, V_OFFSET_TRON).unwrap();
+        let result = self.post("/wallet/broadcasttransaction", &signed).await;
+        assert_eq!(result["result"], true, "Broadcast rejected: {}", result);
+        self.wait_for(tx_id).await
+    }
+
+    async fn transfer_trx(&self, private_key: &str, to: &str, amount: u64) {
+        let from = private_key_to_tron_address(private_key, TRON_ADDRESS_PREFIX).unwrap();
+        let transaction = self
+            .post(
+                "/wallet/createtransaction",
+                &json!({
+                    "owner_address": tron_address_to_hex(&from, TRON_ADDRESS_PREFIX).unwrap(),
+                    "to_address": tron_address_to_hex(to, TRON_ADDRESS_PREFIX).unwrap(),
+                    "amount": amount
+                }),
+            )
+            .await;
+        self.send(&transaction, private_key).await;
+    }
+
+    /// Calls `function` on `contract` from the account of `private_key` and
+    /// fails the test unless it succeeds.
+    async fn trigger(
+        &self,
+        private_key: &str,
+        contract: &str,
+        function: &str,
+        params: &[AbiValue],
+    ) {
+        let caller = private_key_to_tron_address(private_key, TRON_ADDRESS_PREFIX).unwrap();
+        let response = self
+            .post(
+                "/wallet/triggersmartcontract",
+                &json!({
+                    "owner_address": tron_address_to_hex(&caller, TRON_ADDRESS_PREFIX).unwrap(),
+                    "contract_address": tron_address_to_hex(contract, TRON_ADDRESS_PREFIX).unwrap(),
+                    "function_selector": function,
+                    "parameter": encode_params(params),
+                    "fee_limit": CALL_FEE_LIMIT_SUN,
+                    "call_value": 0
+                }),
+            )
+            .await;
+        let info = self.send(&response["transaction"], private_key).await;
+        assert_eq!(
+            info["receipt"]["result"], "SUCCESS",
+            "{} failed: {}",
+            function, info
+        );
+    }
+}
+
+/// Runs the CLI with `--json` and returns its output, failing the test if
+/// the command fails.
+fn tron_utils(args: &[&str]) -> Value {
+    let output = Command::new(env!("CARGO_BIN_EXE_tron-utils"))
+        .arg("--json")
+        .args(args)
+        .output()
+        .expect("failed to run tron-utils");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "tron-utils {:?} failed:\n{}{}",
+        args,
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_str(&stdout)
+        .unwrap_or_else(|e| panic!("Invalid JSON output ({}): {}", e, stdout))
+}
+
+/// The value of the output named `name` in `call --json` output.
+fn output<'a>(call: &'a Value, name: &str) -> &'a Value {
+    call["outputs"]
+        .as_array()
+        .and_then(|outputs| outputs.iter().find(|o| o["name"] == name))
+        .map(|o| &o["value"])
+        .unwrap_or_else(|| panic!("No output {} in {}", name, call))
+}
+
+#[tokio::test]
+async fn deploy_submit_and_approve_on_local_node() {
+    let Ok(rpc) = std::env::var("TRON_TEST_RPC") else {
+        eprintln!("TRON_TEST_RPC is not set; skipping the local node test");
+        return;
+    };
+    let node = Node {
+        rpc: rpc.trim_end_matches('/').to_string(),
+        http: reqwest::Client::new(),
+    };
+    let funder = match std::env::var("TRON_TEST_FUNDER_KEY") {
+        Ok(key) => key,
+        Err(_) => node.get("/admin/accounts-json").await["privateKeys"][0]
+            .as_str()
+            .expect("No pre-funded accounts; set TRON_TEST_FUNDER_KEY")
+            .to_string(),
+    };
+    let contract_json = std::env::var("TRON_TEST_CONTRACT_JSON").unwrap_or_else(|_| {
+        concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../out/Multisig.sol/USDTMultisig.json"
+        )
+        .to_string()
+    });
+
+    let mut owners = Vec::new();
+    for _ in 0..3 {
+        let key = tron_utils(&["generate-key"]);
+        let (private_key, address) = (key["privateKey"].as_str(), key["address"].as_str());
+        owners.push((
+            private_key.unwrap().to_string(),
+            address.unwrap().to_string(),
+        ));
+    }
+    for (_, address) in &owners {
+        node.transfer_trx(&funder, address, FUNDING_SUN).await;
+    }
+
+    // The token is only called on execution, which two of three approvals
+    // never reach, so any non-zero address will do
+    let token = private_key_to_tron_address(&funder, TRON_ADDRESS_PREFIX).unwrap();
+    let owner_list: Vec<&str> = owners.iter().map(|(_, address)| address.as_str()).collect();
+    let deployed = tron_utils(&[
+        "deploy",
+        "--rpc-url",
+        &node.rpc,
+        "--private-key",
+        &owners[0].0,
+        "--usdt",
+        &token,
+        "--owners",
+        &owner_list.join(","),
+        "--threshold",
+        "3",
+        "--contract-json",
+        &contract_json,
+    ]);
+    let info = node
+        .wait_for(deployed["transaction"].as_str().unwrap())
+        .await;
+    assert_eq!(
+        info["receipt"]["result"], "SUCCESS",
+        "Deployment failed: {}",
+        info
+    );
+    let contract = deployed["contractAddress"].as_str().unwrap();
+
+    let recipient = AbiValue::address(&token, TRON_ADDRESS_PREFIX).unwrap();
+    let submit = [recipient, AbiValue::uint(1_000_000)];
+    node.trigger(
+        &owners[0].0,
+        contract,
+        "submitTransaction(address,uint256)",
+        &submit,
+    )
+    .await;
+    node.trigger(
+        &owners[1].0,
+        contract,
+        "approveTransaction(uint256)",
+        &[AbiValue::uint(0)],
+    )
+    .await;
+
+    let call = |function: &str, args: &[&str]| {
+        let mut command = vec!["call", "--rpc-url", &node.rpc, "--contract", contract];
+        command.extend(["--function", function]);
+        for arg in args {
+            command.extend(["--arg", arg]);
+        }
+        tron_utils(&command)
+    };
+    assert_eq!(call("threshold", &[])["outputs"][0]["value"], "3");
+    assert_eq!(
+        call("getOwners", &[])["outputs"][0]["value"],
+        json!(owner_list)
+    );
+
+    let transaction = call("getTransaction", &["0"]);
+    assert_eq!(output(&transaction, "to"), token.as_str());
+    assert_eq!(output(&transaction, "amount"), "1000000");
+    assert_eq!(output(&transaction, "approvalCount"), "2");
+    assert_eq!(output(&transaction, "executed"), false);
+    for (i, (_, owner)) in owners.iter().enumerate() {
+        let approved = call("isApproved", &["0", owner]);
+        assert_eq!(
+            approved["outputs"][0]["value"],
+            i < 2,
+            "approval of owner {}",
+            i
+        );
+    }
+}